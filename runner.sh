@@ -1,2 +1,2 @@
 ls -l /
-/hello-phext
+/phext serve
@@ -0,0 +1,530 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Phext Archives
+///
+/// Packs files into a phext (one file per scroll) along with a manifest scroll that records where each file
+/// came from (and which directories were empty), so that an archive can be unpacked back into the original
+/// directory tree. The manifest lives in its own library, so it never collides with packed content.
+/// ----------------------------------------------------------------------------------------------------------
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use libphext::phext as phext;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use crate::subspace;
+
+/// coordinate reserved for the manifest scroll
+pub const MANIFEST_COORDINATE: &str = "2.1.1/1.1.1/1.1.1";
+
+/// first line of every manifest scroll
+const MANIFEST_HEADER: &str = "# phext manifest v3";
+
+/// header written before encodings were recorded (every file was stored as text)
+const MANIFEST_HEADER_V2: &str = "# phext manifest v2";
+
+/// header written before empty directories were recorded
+const MANIFEST_HEADER_V1: &str = "# phext manifest v1";
+
+/// first column of the manifest lines that record empty directories
+const DIRECTORY_MARKER: &str = "directory";
+
+/// width of each line of a base64-encoded scroll
+const BASE64_LINE: usize = 76;
+
+/// deepest directory nesting that fits beneath the library dimension (shelf through section)
+const MAXIMUM_DEPTH: usize = 7;
+
+/// largest value of a single coordinate dimension
+const DIMENSION_LIMIT: usize = 99;
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum Encoding
+///
+/// How a file's bytes are stored in its scroll: UTF-8 text is stored as-is, while binary files (and text
+/// containing phext delimiters, which would otherwise move content to other coordinates) are base64-encoded
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Encoding {
+  Text,
+  Base64,
+}
+
+impl Encoding {
+  pub fn name(&self) -> &'static str {
+    return match self {
+      Encoding::Text => "text",
+      Encoding::Base64 => "base64",
+    };
+  }
+
+  pub fn from_name(name: &str) -> Option<Encoding> {
+    return match name {
+      "text" => Some(Encoding::Text),
+      "base64" => Some(Encoding::Base64),
+      _ => None,
+    };
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct ManifestEntry
+///
+/// Describes one packed file: the scroll it was stored at, its permissions, how it was encoded, a checksum
+/// of the scroll, and its path relative to the directory that was packed
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone)]
+pub struct ManifestEntry {
+  pub coordinate: phext::Coordinate,
+  pub mode: u32,
+  pub encoding: Encoding,
+  pub hash: String,
+  pub path: String,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct DirectoryEntry
+///
+/// An empty directory (relative to the directory that was packed) and its permissions - it has no scroll
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone)]
+pub struct DirectoryEntry {
+  pub mode: u32,
+  pub path: String,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Manifest
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Manifest {
+  pub entries: Vec<ManifestEntry>,
+  pub directories: Vec<DirectoryEntry>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct PackedFile
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone)]
+pub struct PackedFile {
+  pub coordinate: phext::Coordinate,
+  pub path: String,
+  pub mode: u32,
+  pub encoding: Encoding,
+  pub content: String,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn has_delimiters
+///
+/// determines if `bytes` contain a phext delimiter (other than a line break)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn has_delimiters(bytes: &[u8]) -> bool {
+  return bytes.iter().any(|byte| phext::is_phext_break(*byte) && *byte != phext::LINE_BREAK as u8);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn encode
+///
+/// converts the bytes of a file into scroll content, choosing base64 whenever the bytes can't be stored as-is
+/// ----------------------------------------------------------------------------------------------------------
+pub fn encode(bytes: Vec<u8>) -> (Encoding, String) {
+  if !has_delimiters(&bytes) {
+    if let Ok(text) = String::from_utf8(bytes.clone()) {
+      return (Encoding::Text, text);
+    }
+  }
+  let encoded = BASE64.encode(&bytes);
+  let lines: Vec<&str> = encoded.as_bytes().chunks(BASE64_LINE).map(|line| std::str::from_utf8(line).unwrap_or_default()).collect();
+  return (Encoding::Base64, lines.join("\n"));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn decode
+///
+/// reverses `encode`, restoring the original bytes of a file
+/// ----------------------------------------------------------------------------------------------------------
+pub fn decode(encoding: Encoding, content: &str) -> Result<Vec<u8>, String> {
+  return match encoding {
+    Encoding::Text => Ok(content.as_bytes().to_vec()),
+    Encoding::Base64 => {
+      let compact: String = content.chars().filter(|c| !c.is_whitespace()).collect();
+      BASE64.decode(compact).map_err(|error| error.to_string())
+    },
+  };
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Directory
+///
+/// The packable contents of one directory, sorted by name
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Default)]
+struct Directory {
+  files: Vec<PathBuf>,
+  directories: Vec<Directory>,
+  empty: Vec<PathBuf>,
+}
+
+impl Directory {
+  fn is_empty(&self) -> bool {
+    return self.files.is_empty() && self.directories.is_empty();
+  }
+
+  fn depth(&self) -> usize {
+    return self.directories.iter().map(|directory| directory.depth() + 1).max().unwrap_or(0);
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn file_mode
+///
+/// reads the permission bits of a file (approximated from the read-only flag on platforms without unix modes)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn file_mode(metadata: &fs::Metadata) -> u32 {
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    return metadata.permissions().mode() & 0o7777;
+  }
+  #[cfg(not(unix))]
+  {
+    if metadata.permissions().readonly() {
+      return 0o444;
+    }
+    return 0o644;
+  }
+}
+
+fn apply_mode(path: &Path, mode: u32) -> Result<(), String> {
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    return fs::set_permissions(path, fs::Permissions::from_mode(mode))
+      .map_err(|error| format!("unable to set permissions on {}: {}", path.display(), error));
+  }
+  #[cfg(not(unix))]
+  {
+    let mut permissions = fs::metadata(path).map_err(|error| error.to_string())?.permissions();
+    permissions.set_readonly(mode & 0o200 == 0);
+    return fs::set_permissions(path, permissions).map_err(|error| error.to_string());
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn format_manifest
+///
+/// renders one line per entry as coordinate, octal mode, encoding, checksum and path (separated by tabs),
+/// followed by a `directory`, octal mode and path line for each empty directory
+/// ----------------------------------------------------------------------------------------------------------
+pub fn format_manifest(manifest: &Manifest) -> String {
+  let mut result = MANIFEST_HEADER.to_string();
+  for entry in &manifest.entries {
+    result += &format!("\n{}\t{:04o}\t{}\t{}\t{}", entry.coordinate, entry.mode, entry.encoding.name(), entry.hash, entry.path);
+  }
+  for directory in &manifest.directories {
+    result += &format!("\n{}\t{:04o}\t{}", DIRECTORY_MARKER, directory.mode, directory.path);
+  }
+  return result;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn is_manifest
+///
+/// determines if `text` starts like a manifest scroll written by `pack` (of any version)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn is_manifest(text: &str) -> bool {
+  let header = text.lines().next().unwrap_or_default();
+  return header == MANIFEST_HEADER || header == MANIFEST_HEADER_V2 || header == MANIFEST_HEADER_V1;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn parse_manifest
+///
+/// reverses `format_manifest`, rejecting manifests from other tools or with malformed lines. Version 2
+/// manifests (which predate the encoding column) are read as text, and version 1 manifests (which also
+/// predate empty directories) only list files.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn parse_manifest(text: &str) -> Result<Manifest, String> {
+  let mut lines = text.lines();
+  let (columns, directories) = match lines.next() {
+    Some(MANIFEST_HEADER) => (5, true),
+    Some(MANIFEST_HEADER_V2) => (4, true),
+    Some(MANIFEST_HEADER_V1) => (4, false),
+    _ => return Err(format!("no manifest found at {}", MANIFEST_COORDINATE)),
+  };
+
+  let mut manifest = Manifest::default();
+  for (i, line) in lines.enumerate() {
+    if line.trim().is_empty() {
+      continue;
+    }
+    if let Some(directory) = line.strip_prefix(DIRECTORY_MARKER).and_then(|rest| rest.strip_prefix('\t')).filter(|_| directories) {
+      let (mode, path) = directory.split_once('\t').ok_or(format!("malformed manifest line {}: {}", i + 2, line))?;
+      let mode = u32::from_str_radix(mode, 8).map_err(|_| format!("invalid mode on manifest line {}: {}", i + 2, mode))?;
+      manifest.directories.push(DirectoryEntry { mode, path: path.to_string() });
+      continue;
+    }
+    let mut fields: Vec<&str> = line.splitn(columns, '\t').collect();
+    if fields.len() < columns {
+      return Err(format!("malformed manifest line {}: {}", i + 2, line));
+    }
+    if columns == 4 {
+      fields.insert(2, Encoding::Text.name());
+    }
+    let mode = u32::from_str_radix(fields[1], 8).map_err(|_| format!("invalid mode on manifest line {}: {}", i + 2, fields[1]))?;
+    let encoding = Encoding::from_name(fields[2]).ok_or(format!("unknown encoding on manifest line {}: {}", i + 2, fields[2]))?;
+    manifest.entries.push(ManifestEntry {
+      coordinate: phext::to_coordinate(fields[0]),
+      mode,
+      encoding,
+      hash: fields[3].to_string(),
+      path: fields[4].to_string(),
+    });
+  }
+
+  return Ok(manifest);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn is_safe_path
+///
+/// determines if `path` is a relative path that stays beneath the directory it is joined to
+/// ----------------------------------------------------------------------------------------------------------
+pub fn is_safe_path(path: &str) -> bool {
+  if path.is_empty() {
+    return false;
+  }
+  return Path::new(path).components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn to_coordinate
+///
+/// reverses `query::dimensions`, building a coordinate from its nine values (library first)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn to_coordinate(dimensions: &[usize; 9]) -> phext::Coordinate {
+  let mut coord = phext::default_coordinate();
+  coord.z.library = dimensions[0];
+  coord.z.shelf = dimensions[1];
+  coord.z.series = dimensions[2];
+  coord.y.collection = dimensions[3];
+  coord.y.volume = dimensions[4];
+  coord.y.book = dimensions[5];
+  coord.x.chapter = dimensions[6];
+  coord.x.section = dimensions[7];
+  coord.x.scroll = dimensions[8];
+  return coord;
+}
+
+/// paths are stored one per manifest line, separated by tabs, so names containing either can't be packed
+fn relative_path(path: &Path) -> Result<String, String> {
+  let parts: Vec<String> = path.components().map(|component| component.as_os_str().to_string_lossy().to_string()).collect();
+  let relative = parts.join("/");
+  if relative.contains(['\t', '\n', '\r']) {
+    return Err(format!("unable to pack {:?}: names may not contain tabs or line breaks", relative));
+  }
+  return Ok(relative);
+}
+
+fn scan(root: &Path, relative: &Path, ignore: &dyn Fn(&Path, bool) -> bool) -> Result<Directory, String> {
+  let location = root.join(relative);
+  let mut names: Vec<PathBuf> = Vec::new();
+  for ith in fs::read_dir(&location).map_err(|error| format!("unable to list {}: {}", location.display(), error))? {
+    let entry = ith.map_err(|error| error.to_string())?;
+    names.push(relative.join(entry.file_name()));
+  }
+  names.sort();
+
+  let mut directory = Directory::default();
+  for name in names {
+    let metadata = fs::symlink_metadata(root.join(&name)).map_err(|error| error.to_string())?;
+    if ignore(&name, metadata.is_dir()) {
+      continue;
+    }
+    if metadata.is_dir() {
+      let child = scan(root, &name, ignore)?;
+      if !child.is_empty() {
+        directory.directories.push(child);
+      } else {
+        directory.empty.extend(child.empty);
+        directory.empty.push(name);
+      }
+    } else if metadata.is_file() {
+      directory.files.push(name);
+    }
+  }
+  return Ok(directory);
+}
+
+fn place(root: &Path, directory: &Directory, level: usize, depth: usize, dimensions: [usize; 9], files: &mut Vec<PackedFile>) -> Result<(), String> {
+  if directory.files.len() > DIMENSION_LIMIT || directory.directories.len() >= DIMENSION_LIMIT {
+    return Err(format!("too many entries to pack in one directory (limit {})", DIMENSION_LIMIT));
+  }
+
+  for (i, path) in directory.files.iter().enumerate() {
+    let mut coord = dimensions;
+    coord[8] = i + 1;
+    let location = root.join(path);
+    let bytes = fs::read(&location).map_err(|error| format!("unable to read {}: {}", location.display(), error))?;
+    let metadata = fs::metadata(&location).map_err(|error| error.to_string())?;
+    let (encoding, content) = encode(bytes);
+    files.push(PackedFile { coordinate: to_coordinate(&coord), path: relative_path(path)?, mode: file_mode(&metadata), encoding, content });
+  }
+
+  // slot 1 of the next dimension holds this directory's own files, so subdirectories are numbered from 2
+  let dimension = 8 - depth + level;
+  for (i, child) in directory.directories.iter().enumerate() {
+    let mut coord = dimensions;
+    coord[dimension] = i + 2;
+    place(root, child, level + 1, depth, coord, files)?;
+  }
+  return Ok(());
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn collect
+///
+/// reads every file beneath `root` (skipping paths rejected by `ignore`, which is also told whether each path
+/// is a directory) and assigns each one a scroll. Directories left without any files are skipped.
+/// Files become scrolls within their directory; the deepest directories become sections, their parents
+/// become chapters, and so on up through books, volumes and collections to the shelf dimension. Entries are
+/// visited in name order, so packing the same tree always produces the same coordinates.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn collect(root: &Path, ignore: &dyn Fn(&Path, bool) -> bool) -> Result<Vec<PackedFile>, String> {
+  let tree = scan(root, Path::new(""), ignore)?;
+  let depth = tree.depth();
+  if depth > MAXIMUM_DEPTH {
+    return Err(format!("directories are nested {} levels deep, but only {} fit within a phext", depth, MAXIMUM_DEPTH));
+  }
+
+  let mut files: Vec<PackedFile> = Vec::new();
+  place(root, &tree, 0, depth, [1; 9], &mut files)?;
+  files.sort_by(|left, right| left.coordinate.partial_cmp(&right.coordinate).unwrap_or(std::cmp::Ordering::Equal));
+  return Ok(files);
+}
+
+fn gather_empty(root: &Path, directory: &Directory, empty: &mut Vec<DirectoryEntry>) -> Result<(), String> {
+  for path in &directory.empty {
+    let location = root.join(path);
+    let metadata = fs::metadata(&location).map_err(|error| format!("unable to read {}: {}", location.display(), error))?;
+    empty.push(DirectoryEntry { mode: file_mode(&metadata), path: relative_path(path)? });
+  }
+  for child in &directory.directories {
+    gather_empty(root, child, empty)?;
+  }
+  return Ok(());
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn empty_directories
+///
+/// lists the directories beneath `root` that `collect` skips because they hold no files (once `ignore` has
+/// had its say), so that `pack` can record them
+/// ----------------------------------------------------------------------------------------------------------
+pub fn empty_directories(root: &Path, ignore: &dyn Fn(&Path, bool) -> bool) -> Result<Vec<DirectoryEntry>, String> {
+  let tree = scan(root, Path::new(""), ignore)?;
+  let mut empty = Vec::new();
+  gather_empty(root, &tree, &mut empty)?;
+  empty.sort_by(|left, right| left.path.cmp(&right.path));
+  return Ok(empty);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn pack
+///
+/// builds a phext from `files` (in subspace order), followed by the manifest that describes them and the
+/// empty `directories`
+/// ----------------------------------------------------------------------------------------------------------
+pub fn pack(files: &[PackedFile], directories: &[DirectoryEntry]) -> String {
+  let entries: Vec<ManifestEntry> = files.iter().map(|file| ManifestEntry {
+    coordinate: file.coordinate,
+    mode: file.mode,
+    encoding: file.encoding,
+    hash: phext::checksum(&file.content),
+    path: file.path.clone(),
+  }).collect();
+  let manifest = format_manifest(&Manifest { entries, directories: directories.to_vec() });
+
+  let scrolls = files.iter()
+    .map(|file| subspace::Scroll { coord: file.coordinate, content: file.content.as_str(), offset: 0 })
+    .chain(std::iter::once(subspace::Scroll { coord: phext::to_coordinate(MANIFEST_COORDINATE), content: manifest.as_str(), offset: 0 }));
+
+  return subspace::assemble(phext::default_coordinate(), scrolls);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn unpack
+///
+/// restores every file and empty directory listed in the manifest of `buffer` beneath `target`. All paths
+/// and checksums are verified before anything is written, so a damaged or malicious archive leaves `target`
+/// untouched.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn unpack(buffer: &str, target: &Path) -> Result<Manifest, String> {
+  let manifest = parse_manifest(subspace::fetch(buffer, phext::to_coordinate(MANIFEST_COORDINATE)))?;
+
+  let mut contents: Vec<Vec<u8>> = Vec::new();
+  for entry in &manifest.entries {
+    if !is_safe_path(&entry.path) {
+      return Err(format!("refusing to write outside of {}: {}", target.display(), entry.path));
+    }
+    let content = subspace::fetch(buffer, entry.coordinate);
+    if phext::checksum(content) != entry.hash {
+      return Err(format!("checksum mismatch for {} at {}", entry.path, entry.coordinate));
+    }
+    contents.push(decode(entry.encoding, content).map_err(|error| format!("unable to decode {}: {}", entry.path, error))?);
+  }
+  if let Some(directory) = manifest.directories.iter().find(|directory| !is_safe_path(&directory.path)) {
+    return Err(format!("refusing to write outside of {}: {}", target.display(), directory.path));
+  }
+
+  fs::create_dir_all(target).map_err(|error| format!("unable to create {}: {}", target.display(), error))?;
+  for (entry, content) in manifest.entries.iter().zip(contents) {
+    restore(target, entry, &content)?;
+  }
+  for directory in &manifest.directories {
+    restore_directory(target, directory)?;
+  }
+  // deepest first, so a read-only directory doesn't stop the ones inside it from getting their modes
+  for directory in manifest.directories.iter().rev() {
+    apply_mode(&target.join(&directory.path), directory.mode)?;
+  }
+
+  return Ok(manifest);
+}
+
+/// resolves `path` beneath `target`, creating its parent directories and refusing anything that would
+/// escape `target` (including through symbolic links)
+fn destination(target: &Path, path: &str) -> Result<PathBuf, String> {
+  if !is_safe_path(path) {
+    return Err(format!("refusing to write outside of {}: {}", target.display(), path));
+  }
+  let root = target.canonicalize().map_err(|error| error.to_string())?;
+  let destination: PathBuf = root.join(path);
+  if let Some(parent) = destination.parent() {
+    fs::create_dir_all(parent).map_err(|error| format!("unable to create {}: {}", parent.display(), error))?;
+    let resolved = parent.canonicalize().map_err(|error| error.to_string())?;
+    if !resolved.starts_with(&root) {
+      return Err(format!("refusing to write outside of {}: {}", target.display(), path));
+    }
+  }
+  if fs::symlink_metadata(&destination).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+    return Err(format!("refusing to write through a symbolic link: {}", path));
+  }
+  return Ok(destination);
+}
+
+fn restore_directory(target: &Path, directory: &DirectoryEntry) -> Result<(), String> {
+  let destination = destination(target, &directory.path)?;
+  return fs::create_dir_all(&destination).map_err(|error| format!("unable to create {}: {}", destination.display(), error));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn restore
+///
+/// writes `content` to the path of `entry` beneath `target` (which must exist) and applies its recorded
+/// mode, refusing paths and symbolic links that would escape `target`
+/// ----------------------------------------------------------------------------------------------------------
+pub fn restore(target: &Path, entry: &ManifestEntry, content: &[u8]) -> Result<(), String> {
+  let destination = destination(target, &entry.path)?;
+  fs::write(&destination, content).map_err(|error| format!("unable to write {}: {}", destination.display(), error))?;
+  return apply_mode(&destination, entry.mode);
+}
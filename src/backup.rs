@@ -0,0 +1,388 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Phext Backups
+///
+/// Packs worlds from a data root, along with the files kept beside them (`<world>.phext.history`, snapshots,
+/// and anything else named `<world>.phext.*`), into a single tar, tar.gz or zip archive, and restores them
+/// into another data root. Worlds are read from and written to the root's storage backend, so a backup
+/// taken from a SQLite root restores into a file root (and vice versa). Every archive carries a manifest
+/// listing each file with its size and CRC-32, so a damaged or edited backup is rejected before anything is
+/// written.
+/// ----------------------------------------------------------------------------------------------------------
+use rocket::serde::json;
+use rocket::serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::compression::Codec;
+use crate::storage::{Storage, Version};
+
+/// the name of the manifest stored at the start of every backup
+pub const MANIFEST_NAME: &str = "phext-backup.json";
+
+/// the manifest format written by this version
+const MANIFEST_VERSION: u32 = 1;
+
+/// the most a backup may unpack to (1 GiB), so a small archive can't decompress into an unbounded one
+pub const UNPACKED_LIMIT: u64 = 1 << 30;
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum Format
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+  Tar,
+  TarGz,
+  Zip,
+}
+
+impl Format {
+  pub fn parse(name: &str) -> Result<Format, String> {
+    return match name {
+      "tar" => Ok(Format::Tar),
+      "tar.gz" | "tgz" => Ok(Format::TarGz),
+      "zip" => Ok(Format::Zip),
+      _ => Err(format!("Unknown backup format: {} (expected tar, tar.gz or zip)", name)),
+    };
+  }
+
+  /// picks the format from an archive's file name (ex: backup.tar.gz)
+  pub fn from_path(path: &str) -> Result<Format, String> {
+    for (suffix, format) in [(".tar.gz", Format::TarGz), (".tgz", Format::TarGz), (".tar", Format::Tar), (".zip", Format::Zip)] {
+      if path.ends_with(suffix) {
+        return Ok(format);
+      }
+    }
+    return Err(format!("Unable to tell the backup format of {} (use .tar, .tar.gz, .tgz or .zip)", path));
+  }
+
+  /// recognizes an archive from its first bytes, falling back to tar (which has no leading signature)
+  pub fn detect(bytes: &[u8]) -> Format {
+    return match bytes {
+      [b'P', b'K', 3, 4, ..] | [b'P', b'K', 5, 6, ..] => Format::Zip,
+      [0x1f, 0x8b, ..] => Format::TarGz,
+      _ => Format::Tar,
+    };
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct BackupFile
+///
+/// One file from (or for) the data root, identified by its file name
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone)]
+pub struct BackupFile {
+  pub name: String,
+  pub content: Vec<u8>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct ManifestFile
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ManifestFile {
+  pub name: String,
+  pub bytes: usize,
+  pub crc32: u32,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Manifest
+///
+/// Describes a backup: when it was made, which worlds it holds, and every file that should be inside it
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Manifest {
+  pub version: u32,
+  pub created: u64,
+  pub worlds: Vec<String>,
+  pub files: Vec<ManifestFile>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Restored
+///
+/// The outcome of a restore: files written, and files skipped because the data root already matched
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Restored {
+  pub written: Vec<String>,
+  pub unchanged: Vec<String>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn is_backup_name
+///
+/// restricts backups to plain file names that belong to a world, so a restore can't write outside the data
+/// root or replace unrelated files
+/// ----------------------------------------------------------------------------------------------------------
+pub fn is_backup_name(name: &str) -> bool {
+  let plain = !name.contains(['/', '\\']) && !name.starts_with('.') && name != "..";
+  return plain && (name.ends_with(".phext") || name.contains(".phext."));
+}
+
+fn world_of(name: &str) -> Option<&str> {
+  return name.strip_suffix(".phext").filter(|world| !world.is_empty());
+}
+
+/// the world itself, stored compressed - already in the backup as its primary
+fn is_compressed_copy(name: &str, primary: &str) -> bool {
+  return Codec::ALL.iter().any(|codec| *codec != Codec::Plain && name == primary.to_owned() + codec.extension());
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn collect
+///
+/// gathers `worlds` (or every world in `storage` when none are named) and the files kept beside them in
+/// `root`, skipping the staging files left by in-progress saves and the compressed files worlds are kept in
+/// ----------------------------------------------------------------------------------------------------------
+pub fn collect(root: &Path, storage: &dyn Storage, worlds: &[String]) -> Result<(Vec<String>, Vec<BackupFile>), String> {
+  let entries = fs::read_dir(root).map_err(|error| format!("unable to read {}: {}", root.display(), error))?;
+  let mut names: Vec<String> = entries
+    .filter_map(|entry| entry.ok())
+    .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+    .map(|entry| entry.file_name().to_string_lossy().to_string())
+    .filter(|name| is_backup_name(name))
+    .collect();
+  names.sort();
+
+  let stored = storage.worlds()?;
+  let mut selected: Vec<String> = match worlds.len() {
+    0 => stored.clone(),
+    _ => worlds.to_vec(),
+  };
+  selected.sort();
+  selected.dedup();
+
+  let mut files = Vec::new();
+  for world in &selected {
+    let primary = world.to_owned() + ".phext";
+    if !is_backup_name(&primary) || !stored.contains(world) {
+      return Err(format!("No world named {} in {}", world, root.display()));
+    }
+    files.push(BackupFile { name: primary.clone(), content: storage.load(world)?.0 });
+    let siblings = names.iter().filter(|name| {
+      return name.starts_with(&(primary.clone() + ".")) && world_of(name).is_none() && !name.ends_with(".tmp") && !is_compressed_copy(name, &primary);
+    });
+    for name in siblings {
+      let path = root.join(name);
+      let content = fs::read(&path).map_err(|error| format!("unable to read {}: {}", path.display(), error))?;
+      files.push(BackupFile { name: name.clone(), content });
+    }
+  }
+  return Ok((selected, files));
+}
+
+fn io_error(error: impl std::fmt::Display) -> String {
+  return format!("unable to write backup: {}", error);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn write
+///
+/// builds an archive holding the manifest followed by `files`
+/// ----------------------------------------------------------------------------------------------------------
+pub fn write(worlds: &[String], files: &[BackupFile], format: Format) -> Result<Vec<u8>, String> {
+  let created = SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+  let manifest = Manifest {
+    version: MANIFEST_VERSION,
+    created,
+    worlds: worlds.to_vec(),
+    files: files.iter().map(|file| ManifestFile {
+      name: file.name.clone(),
+      bytes: file.content.len(),
+      crc32: crc32fast::hash(&file.content),
+    }).collect(),
+  };
+  let manifest = json::to_pretty_string(&manifest).map_err(io_error)?;
+  let entries = std::iter::once((MANIFEST_NAME, manifest.as_bytes()))
+    .chain(files.iter().map(|file| (file.name.as_str(), file.content.as_slice())));
+
+  return match format {
+    Format::Tar => write_tar(Vec::new(), entries, created),
+    Format::TarGz => {
+      let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+      write_tar(encoder, entries, created)?.finish().map_err(io_error)
+    },
+    Format::Zip => {
+      let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+      let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+      for (name, content) in entries {
+        archive.start_file(name, options).map_err(io_error)?;
+        archive.write_all(content).map_err(io_error)?;
+      }
+      Ok(archive.finish().map_err(io_error)?.into_inner())
+    },
+  };
+}
+
+fn write_tar<'a, W: Write>(output: W, entries: impl Iterator<Item = (&'a str, &'a [u8])>, created: u64) -> Result<W, String> {
+  let mut archive = tar::Builder::new(output);
+  for (name, content) in entries {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(created);
+    archive.append_data(&mut header, name, content).map_err(io_error)?;
+  }
+  return archive.into_inner().map_err(io_error);
+}
+
+/// reads one entry, counting it against the bytes the whole backup may still unpack to
+fn read_entry(entry: impl Read, name: &str, remaining: &mut u64, limit: u64) -> Result<Vec<u8>, String> {
+  let mut content = Vec::new();
+  entry.take(*remaining + 1).read_to_end(&mut content).map_err(|error| format!("unable to read {}: {}", name, error))?;
+  if content.len() as u64 > *remaining {
+    return Err(format!("This backup unpacks to more than {} bytes", limit));
+  }
+  *remaining -= content.len() as u64;
+  return Ok(content);
+}
+
+fn read_tar(input: impl Read, limit: u64) -> Result<Vec<BackupFile>, String> {
+  // headers and padding are bounded too (generously), so a stream of empty entries can't run on forever
+  let mut archive = tar::Archive::new(input.take(limit.saturating_mul(2).saturating_add(1 << 20)));
+  let mut remaining = limit;
+  let mut files = Vec::new();
+  for entry in archive.entries().map_err(|error| format!("unable to read backup: {}", error))? {
+    let entry = entry.map_err(|error| format!("unable to read backup: {}", error))?;
+    if !entry.header().entry_type().is_file() {
+      continue;
+    }
+    let name = entry.path().map_err(|error| format!("unable to read backup: {}", error))?.to_string_lossy().to_string();
+    let content = read_entry(entry, &name, &mut remaining, limit)?;
+    files.push(BackupFile { name, content });
+  }
+  return Ok(files);
+}
+
+fn read_zip(bytes: &[u8], limit: u64) -> Result<Vec<BackupFile>, String> {
+  let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|error| format!("unable to read backup: {}", error))?;
+  let mut remaining = limit;
+  let mut files = Vec::new();
+  for index in 0..archive.len() {
+    let entry = archive.by_index(index).map_err(|error| format!("unable to read backup: {}", error))?;
+    if !entry.is_file() {
+      continue;
+    }
+    let name = entry.name().to_string();
+    let content = read_entry(entry, &name, &mut remaining, limit)?;
+    files.push(BackupFile { name, content });
+  }
+  return Ok(files);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn read
+///
+/// unpacks a backup in any supported format, checking every file against the manifest: nothing may be
+/// missing, altered, added, or repeated, and every name must belong to a world. Backups that unpack to more
+/// than `UNPACKED_LIMIT` bytes are refused.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn read(bytes: &[u8]) -> Result<(Manifest, Vec<BackupFile>), String> {
+  return read_limited(bytes, UNPACKED_LIMIT);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn read_limited
+///
+/// `read`, refusing backups that unpack to more than `limit` bytes
+/// ----------------------------------------------------------------------------------------------------------
+pub fn read_limited(bytes: &[u8], limit: u64) -> Result<(Manifest, Vec<BackupFile>), String> {
+  let mut files = match Format::detect(bytes) {
+    Format::Tar => read_tar(bytes, limit)?,
+    Format::TarGz => read_tar(flate2::read::GzDecoder::new(bytes), limit)?,
+    Format::Zip => read_zip(bytes, limit)?,
+  };
+
+  let mut names = BTreeSet::new();
+  if let Some(repeated) = files.iter().find(|file| !names.insert(file.name.as_str())) {
+    return Err(format!("{} appears more than once in the backup", repeated.name));
+  }
+
+  let position = files.iter().position(|file| file.name == MANIFEST_NAME).ok_or(format!("This backup has no {}", MANIFEST_NAME))?;
+  let manifest: Manifest = json::from_slice(&files.remove(position).content).map_err(|error| format!("Invalid {}: {}", MANIFEST_NAME, error))?;
+  if manifest.version > MANIFEST_VERSION {
+    return Err(format!("This backup was made by a newer version (manifest version {})", manifest.version));
+  }
+  let mut listed = BTreeSet::new();
+  if let Some(repeated) = manifest.files.iter().find(|expected| !listed.insert(expected.name.as_str())) {
+    return Err(format!("{} is listed more than once in {}", repeated.name, MANIFEST_NAME));
+  }
+  for expected in &manifest.files {
+    let file = files.iter().find(|file| file.name == expected.name).ok_or(format!("{} is missing from the backup", expected.name))?;
+    if file.content.len() != expected.bytes || crc32fast::hash(&file.content) != expected.crc32 {
+      return Err(format!("{} does not match its checksum", expected.name));
+    }
+  }
+  for file in &files {
+    if !manifest.files.iter().any(|expected| expected.name == file.name) {
+      return Err(format!("{} is not listed in {}", file.name, MANIFEST_NAME));
+    }
+    if !is_backup_name(&file.name) {
+      return Err(format!("Refusing to restore {}: backups may only hold world files", file.name));
+    }
+  }
+  return Ok((manifest, files));
+}
+
+fn existing(root: &Path, storage: &dyn Storage, name: &str) -> Option<Vec<u8>> {
+  return match world_of(name) {
+    Some(world) => storage.load(world).ok().map(|(bytes, _)| bytes),
+    None => fs::read(root.join(name)).ok(),
+  };
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn conflicts
+///
+/// lists the worlds in `storage` and files in `root` that a restore would replace with different content
+/// ----------------------------------------------------------------------------------------------------------
+pub fn conflicts(root: &Path, storage: &dyn Storage, files: &[BackupFile]) -> Vec<String> {
+  return files.iter()
+    .filter(|file| existing(root, storage, &file.name).is_some_and(|existing| existing != file.content))
+    .map(|file| file.name.clone())
+    .collect();
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn restore
+///
+/// stores the worlds in `files` into `storage` and writes the rest into `root` (check `conflicts` first -
+/// existing worlds and files are replaced). Every file is checked before the first one is written, and each
+/// is staged beside its destination and renamed into place, so readers never see a partial world.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn restore(root: &Path, storage: &dyn Storage, files: &[BackupFile]) -> Result<Restored, String> {
+  for file in files {
+    if !is_backup_name(&file.name) {
+      return Err(format!("Refusing to restore {}: backups may only hold world files", file.name));
+    }
+    if world_of(&file.name).is_some() && std::str::from_utf8(&file.content).is_err() {
+      return Err(format!("{} is not valid UTF-8", file.name));
+    }
+  }
+
+  let mut restored = Restored::default();
+  for file in files {
+    if existing(root, storage, &file.name).is_some_and(|existing| existing == file.content) {
+      restored.unchanged.push(file.name.clone());
+      continue;
+    }
+    if let Some(world) = world_of(&file.name) {
+      let buffer = std::str::from_utf8(&file.content).map_err(|_| format!("{} is not valid UTF-8", file.name))?;
+      storage.store(world, buffer, &Version::Any)?;
+    } else {
+      let destination = root.join(&file.name);
+      let staging = root.join(format!(".{}.restore", file.name));
+      fs::write(&staging, &file.content).map_err(|error| format!("unable to write {}: {}", staging.display(), error))?;
+      fs::rename(&staging, &destination).map_err(|error| format!("unable to write {}: {}", destination.display(), error))?;
+    }
+    restored.written.push(file.name.clone());
+  }
+  return Ok(restored);
+}
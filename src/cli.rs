@@ -0,0 +1,1005 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Phext Command Line
+///
+/// The subcommands of the `phext` binary: `serve` hosts the API, and the rest work with local phext files.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::process::ExitCode;
+use crate::compression::{Codec, Compression};
+use crate::storage::{Storage, Version};
+use crate::{archive, backup, document, filter, fsck, grep, markdown, query, remote, server, shell, site, stats, storage, subspace, sync, toc};
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Command
+///
+/// A single subcommand: its name, usage line, a one-line summary for the help text, and its handler
+/// ----------------------------------------------------------------------------------------------------------
+struct Command
+{
+    name: &'static str,
+    usage: &'static str,
+    summary: &'static str,
+    run: fn(&[String]) -> Result<(), String>,
+}
+
+/// how many passes `sync --once` makes before giving up on a phext that another writer keeps changing
+const SYNC_ATTEMPTS: usize = 5;
+
+const COMMANDS: [Command; 23] = [
+    Command { name: "serve", usage: "serve [--root <directory>] [--storage <file|sqlite|s3://bucket/prefix>] [--compress <zstd|gzip>] [--compress-min-bytes <bytes>] [--compress-idle <seconds>] [--port <port>] [--read-only]", summary: "hosts the phext API for the worlds in the given directory (your local directory by default)", run: run_serve },
+    Command { name: "pack", usage: "pack [--dry-run] [--gitignore] [--include <glob>] [--exclude <glob>] <archive>", summary: "packs the local directory tree into the given archive", run: run_pack },
+    Command { name: "unpack", usage: "unpack <phext> [directory]", summary: "restores the files listed in the manifest of the given phext (into your local directory by default)", run: run_unpack },
+    Command { name: "backup", usage: "backup [--root <directory>] [--storage <file|sqlite|s3://bucket/prefix>] [--world <name>]... <archive>", summary: "packs worlds, their history and snapshots into a .tar, .tar.gz or .zip (every world in the root by default)", run: run_backup },
+    Command { name: "restore", usage: "restore [--root <directory>] [--storage <file|sqlite|s3://bucket/prefix>] [--force] <archive>", summary: "restores a backup into the data root, refusing to replace files that differ unless forced", run: run_restore },
+    Command { name: "convert", usage: "convert [--root <directory>] [--world <name>]... <from> <to>", summary: "copies worlds (every world by default) between storage backends: file, sqlite, or an s3://bucket/prefix URL", run: run_convert },
+    Command { name: "compress", usage: "compress [--root <directory>] [--min-bytes <bytes>] [--idle <seconds>] <zstd|gzip|none>", summary: "stores the worlds in a data root as <world>.phext.zst or .gz (those of at least --min-bytes, idle for --idle), or plain again with none", run: run_compress },
+    Command { name: "sync", usage: "sync [--once] [--interval <ms>] [--gitignore] <phext> [directory]", summary: "keeps a directory (your local directory by default) and a phext in step, reporting conflicting edits", run: run_sync },
+    Command { name: "grep", usage: "grep [-i] [-F] [-C <lines>] [--start <coord>] [--end <coord>] <pattern> <phext>", summary: "prints matching lines as coordinate:line:column:text", run: run_grep },
+    Command { name: "fsck", usage: "fsck [--repair <output>] <phext>", summary: "checks a phext for encoding, delimiter, coordinate and manifest problems (optionally writing a repaired copy)", run: run_fsck },
+    Command { name: "to-json", usage: "to-json [--nested] <phext> [output]", summary: "converts a phext to a JSON array of {coordinate, content} objects (or an object keyed by dimension)", run: run_to_json },
+    Command { name: "from-json", usage: "from-json <document> <phext>", summary: "writes the phext described by a JSON document in either layout", run: run_from_json },
+    Command { name: "to-markdown", usage: "to-markdown [--start <coord>] [--end <coord>] <phext> <directory>", summary: "writes each scroll (optionally just a range) to a Markdown file with its coordinate in the front matter", run: run_to_markdown },
+    Command { name: "from-markdown", usage: "from-markdown [--merge] <directory> <phext>", summary: "builds a phext from a folder of Markdown files (or updates just their scrolls with --merge)", run: run_from_markdown },
+    Command { name: "site", usage: "site <phext> <directory>", summary: "renders a static HTML site with a page per scroll, a table of contents and client-side search", run: run_site },
+    Command { name: "cat", usage: "cat <phext> <coord>", summary: "prints the scroll at the given coordinate", run: run_cat },
+    Command { name: "put", usage: "put <phext> <coord>", summary: "replaces the scroll at the given coordinate with stdin", run: run_put },
+    Command { name: "rm", usage: "rm <phext> <coord>", summary: "removes the scroll at the given coordinate", run: run_rm },
+    Command { name: "ls", usage: "ls <phext> [prefix]", summary: "lists populated coordinates (optionally beneath a partial address) with their sizes and titles", run: run_ls },
+    Command { name: "tree", usage: "tree <phext>", summary: "prints an indented view of every populated scroll across all nine dimensions", run: run_tree },
+    Command { name: "remote", usage: "remote <url> <select|insert|update|delete|replace|merge|subtract|normalize|expand|contract> <world> [args...]", summary: "runs an API operation against a phext server (scroll content is read from stdin)", run: run_remote },
+    Command { name: "shell", usage: "shell [--script <history>] [phext]", summary: "starts an interactive shell (lp, ds, cs, es, ...) or replays a .phext.history file", run: run_shell },
+    Command { name: "help", usage: "help [command]", summary: "print an example phext (or the usage of a command)", run: run_help },
+];
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn run
+///
+/// dispatches `args` (including the program name) to a subcommand, returning the process exit code
+/// ----------------------------------------------------------------------------------------------------------
+pub fn run(args: Vec<String>) -> ExitCode
+{
+    if args.len() < 2 || args[1] == "--help" || args[1] == "-h"
+    {
+        print_commands();
+        return ExitCode::SUCCESS;
+    }
+
+    let name = args[1].as_str();
+    let command = match find_command(name)
+    {
+        Some(command) => command,
+        None =>
+        {
+            eprintln!("Unknown command: {name}");
+            print_commands();
+            return ExitCode::from(2);
+        }
+    };
+
+    if let Err(message) = (command.run)(&args[2..])
+    {
+        eprintln!("{message}");
+        return ExitCode::FAILURE;
+    }
+    return ExitCode::SUCCESS;
+}
+
+fn find_command(name: &str) -> Option<&'static Command>
+{
+    return COMMANDS.iter().find(|command| command.name == name);
+}
+
+fn print_version()
+{
+    let mut v: phext::Coordinate = phext::default_coordinate();
+    v.x.scroll = 6;
+    v.x.section = 0;
+    v.x.chapter = 0;
+    let version = v.x;
+    println!("hello-phext v{version}");
+}
+
+fn print_commands()
+{
+    print_version();
+    println!("\navailable commands:");
+    for command in COMMANDS.iter()
+    {
+        println!(" - {}: {}", command.usage, command.summary);
+    }
+}
+
+fn run_serve(args: &[String]) -> Result<(), String>
+{
+    let mut config = server::ServerConfig::default();
+    let mut i = 0;
+    while i < args.len()
+    {
+        let arg = args[i].as_str();
+        match arg
+        {
+            "--read-only" => config.read_only = true,
+            "--root" | "--storage" | "--port" | "--compress" | "--compress-min-bytes" | "--compress-idle" =>
+            {
+                let value = args.get(i + 1).ok_or(format!("Missing value for {arg}."))?;
+                if arg == "--root" { config.root = PathBuf::from(value); }
+                if arg == "--storage" { config.storage = storage::Backend::parse(value)?; }
+                if arg == "--port" { config.port = Some(value.parse().map_err(|_| format!("Invalid port: {value}"))?); }
+                if arg.starts_with("--compress")
+                {
+                    let policy = config.compression.get_or_insert(Compression { codec: Codec::Zstd, ..Compression::default() });
+                    if arg == "--compress" { policy.codec = Codec::parse(value)?; }
+                    if arg == "--compress-min-bytes" { policy.min_bytes = parse_bytes(value)?; }
+                    if arg == "--compress-idle" { policy.idle = Some(parse_seconds(value)?); }
+                }
+                i += 1;
+            },
+            _ => return Err(format!("Unknown option: {arg}")),
+        }
+        i += 1;
+    }
+    if !config.root.is_dir()
+    {
+        return Err(format!("{} is not a directory", config.root.display()));
+    }
+
+    let result = rocket::execute(server::build_rocket(config).launch());
+    return result.map(|_| ()).map_err(|error| error.to_string());
+}
+
+fn run_pack(args: &[String]) -> Result<(), String>
+{
+    print_version();
+    let mut options = filter::FilterOptions::default();
+    let mut dry_run = false;
+    let mut positional: Vec<&String> = Vec::new();
+    let mut i = 0;
+    while i < args.len()
+    {
+        let arg = args[i].as_str();
+        match arg
+        {
+            "--dry-run" => dry_run = true,
+            "--gitignore" => options.gitignore = true,
+            "--include" | "--exclude" =>
+            {
+                let value = args.get(i + 1).ok_or(format!("Missing value for {arg}."))?;
+                if arg == "--include" { options.includes.push(value.clone()); }
+                if arg == "--exclude" { options.excludes.push(value.clone()); }
+                i += 1;
+            },
+            _ => positional.push(&args[i]),
+        }
+        i += 1;
+    }
+
+    if positional.is_empty()
+    {
+        return Err("Missing file parameter for pack.".to_string());
+    }
+    let file = positional[0].as_str();
+    options.output = Some(file.to_string());
+    let root = Path::new(".");
+    let rules = filter::PackFilter::new(root, &options)?;
+    let ignore = |path: &Path, is_dir: bool| rules.is_ignored(path, is_dir);
+    let files = archive::collect(root, &ignore)?;
+    let directories = archive::empty_directories(root, &ignore)?;
+
+    if dry_run
+    {
+        println!("Planning to pack local files into {file}...");
+    }
+    else
+    {
+        println!("Packing local files into {file}...");
+    }
+    for packed in files.iter()
+    {
+        match packed.encoding
+        {
+            archive::Encoding::Text => println!("{}: {}", packed.coordinate, packed.path),
+            encoding => println!("{}: {} ({})", packed.coordinate, packed.path, encoding.name()),
+        }
+    }
+    for directory in directories.iter()
+    {
+        println!("directory: {}/", directory.path);
+    }
+    if dry_run
+    {
+        return Ok(());
+    }
+
+    save(file, &archive::pack(&files, &directories))?;
+    return Ok(());
+}
+
+fn run_unpack(args: &[String]) -> Result<(), String>
+{
+    print_version();
+    if args.is_empty()
+    {
+        return Err("Missing file parameter for unpack.".to_string());
+    }
+    let file = &args[0];
+    let target = Path::new(args.get(1).map(|arg| arg.as_str()).unwrap_or("."));
+    let buffer = load(file)?;
+
+    println!("Extracting {file} to {}...", target.display());
+    let manifest = archive::unpack(&buffer, target)?;
+    for entry in manifest.entries.iter()
+    {
+        println!("{}: {}", entry.coordinate, entry.path);
+    }
+    for directory in manifest.directories.iter()
+    {
+        println!("directory: {}/", directory.path);
+    }
+    return Ok(());
+}
+
+fn run_backup(args: &[String]) -> Result<(), String>
+{
+    let mut root = PathBuf::from(".");
+    let mut backend = storage::Backend::File;
+    let mut worlds: Vec<String> = Vec::new();
+    let mut positional: Vec<&String> = Vec::new();
+    let mut i = 0;
+    while i < args.len()
+    {
+        let arg = args[i].as_str();
+        match arg
+        {
+            "--root" | "--storage" | "--world" =>
+            {
+                let value = args.get(i + 1).ok_or(format!("Missing value for {arg}."))?;
+                if arg == "--root" { root = PathBuf::from(value); }
+                if arg == "--storage" { backend = storage::Backend::parse(value)?; }
+                if arg == "--world" { worlds.push(value.clone()); }
+                i += 1;
+            },
+            _ => positional.push(&args[i]),
+        }
+        i += 1;
+    }
+    let output = positional.first().ok_or("Missing archive parameter for backup.".to_string())?;
+    let format = backup::Format::from_path(output)?;
+
+    let storage = storage::open(&backend, &root)?;
+    let (worlds, files) = backup::collect(&root, storage.as_ref(), &worlds)?;
+    let archive = backup::write(&worlds, &files, format)?;
+    fs::write(output, archive).map_err(|error| format!("unable to write {output}: {error}"))?;
+    println!("Wrote {} worlds ({} files) to {output}", worlds.len(), files.len());
+    return Ok(());
+}
+
+fn run_restore(args: &[String]) -> Result<(), String>
+{
+    let mut root = PathBuf::from(".");
+    let mut backend = storage::Backend::File;
+    let mut force = false;
+    let mut positional: Vec<&String> = Vec::new();
+    let mut i = 0;
+    while i < args.len()
+    {
+        let arg = args[i].as_str();
+        match arg
+        {
+            "--force" => force = true,
+            "--root" | "--storage" =>
+            {
+                let value = args.get(i + 1).ok_or(format!("Missing value for {arg}."))?;
+                if arg == "--root" { root = PathBuf::from(value); }
+                if arg == "--storage" { backend = storage::Backend::parse(value)?; }
+                i += 1;
+            },
+            _ => positional.push(&args[i]),
+        }
+        i += 1;
+    }
+    let input = positional.first().ok_or("Missing archive parameter for restore.".to_string())?;
+    let bytes = fs::read(input).map_err(|error| format!("unable to read {input}: {error}"))?;
+
+    let (_, files) = backup::read(&bytes)?;
+    fs::create_dir_all(&root).map_err(|error| format!("unable to create {}: {error}", root.display()))?;
+    let storage = storage::open(&backend, &root)?;
+    let conflicts = backup::conflicts(&root, storage.as_ref(), &files);
+    if !conflicts.is_empty() && !force
+    {
+        return Err(format!("These files already exist with different content: {} (use --force to replace them)", conflicts.join(", ")));
+    }
+    let restored = backup::restore(&root, storage.as_ref(), &files)?;
+    for name in &restored.written
+    {
+        println!("restored {name}");
+    }
+    println!("{} files restored, {} already up to date", restored.written.len(), restored.unchanged.len());
+    return Ok(());
+}
+
+fn run_convert(args: &[String]) -> Result<(), String>
+{
+    let mut root = PathBuf::from(".");
+    let mut worlds: Vec<String> = Vec::new();
+    let mut positional: Vec<&String> = Vec::new();
+    let mut i = 0;
+    while i < args.len()
+    {
+        let arg = args[i].as_str();
+        match arg
+        {
+            "--root" | "--world" =>
+            {
+                let value = args.get(i + 1).ok_or(format!("Missing value for {arg}."))?;
+                if arg == "--root" { root = PathBuf::from(value); }
+                if arg == "--world" { worlds.push(value.clone()); }
+                i += 1;
+            },
+            _ => positional.push(&args[i]),
+        }
+        i += 1;
+    }
+    if positional.len() < 2
+    {
+        return Err("Usage: phext convert [--root <directory>] [--world <name>]... <from> <to>".to_string());
+    }
+    let from = storage::Backend::parse(positional[0])?;
+    let to = storage::Backend::parse(positional[1])?;
+    if from == to
+    {
+        return Err(format!("Nothing to convert: both backends are {}", from.name()));
+    }
+    if !root.is_dir()
+    {
+        return Err(format!("{} is not a directory", root.display()));
+    }
+
+    let source = storage::open(&from, &root)?;
+    let destination = storage::open(&to, &root)?;
+    let copied = storage::copy(source.as_ref(), destination.as_ref(), &worlds)?;
+    for world in &copied
+    {
+        println!("copied {world}");
+    }
+    println!("{} worlds copied from {} to {} storage", copied.len(), from.name(), to.name());
+    return Ok(());
+}
+
+fn run_compress(args: &[String]) -> Result<(), String>
+{
+    let mut root = PathBuf::from(".");
+    let mut policy = Compression::default();
+    let mut positional: Vec<&String> = Vec::new();
+    let mut i = 0;
+    while i < args.len()
+    {
+        let arg = args[i].as_str();
+        match arg
+        {
+            "--root" | "--min-bytes" | "--idle" =>
+            {
+                let value = args.get(i + 1).ok_or(format!("Missing value for {arg}."))?;
+                if arg == "--root" { root = PathBuf::from(value); }
+                if arg == "--min-bytes" { policy.min_bytes = parse_bytes(value)?; }
+                if arg == "--idle" { policy.idle = Some(parse_seconds(value)?); }
+                i += 1;
+            },
+            _ => positional.push(&args[i]),
+        }
+        i += 1;
+    }
+    if positional.len() != 1
+    {
+        return Err("Usage: phext compress [--root <directory>] [--min-bytes <bytes>] [--idle <seconds>] <zstd|gzip|none>".to_string());
+    }
+    policy.codec = Codec::parse(positional[0])?;
+    if !root.is_dir()
+    {
+        return Err(format!("{} is not a directory", root.display()));
+    }
+
+    let changed = storage::FileStorage::with_compression(root, policy).sweep()?;
+    for (world, codec) in &changed
+    {
+        println!("{world}: {}", codec.name());
+    }
+    println!("{} worlds re-encoded", changed.len());
+    return Ok(());
+}
+
+fn parse_bytes(value: &str) -> Result<usize, String>
+{
+    return value.parse().map_err(|_| format!("Invalid size: {value}"));
+}
+
+fn parse_seconds(value: &str) -> Result<Duration, String>
+{
+    return value.parse().map(Duration::from_secs).map_err(|_| format!("Invalid number of seconds: {value}"));
+}
+
+fn relative_to(root: &Path, file: &Path) -> Option<PathBuf>
+{
+    let parent = file.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let relative = parent.canonicalize().ok()?.strip_prefix(root.canonicalize().ok()?).ok()?.join(file.file_name()?);
+    return Some(relative);
+}
+
+fn run_sync(args: &[String]) -> Result<(), String>
+{
+    let mut options = filter::FilterOptions::default();
+    let mut once = false;
+    let mut interval = Duration::from_millis(1000);
+    let mut positional: Vec<&String> = Vec::new();
+    let mut i = 0;
+    while i < args.len()
+    {
+        let arg = args[i].as_str();
+        match arg
+        {
+            "--once" => once = true,
+            "--gitignore" => options.gitignore = true,
+            "--interval" =>
+            {
+                let value = args.get(i + 1).ok_or(format!("Missing value for {arg}."))?;
+                interval = Duration::from_millis(value.parse().map_err(|_| format!("Invalid interval: {value}"))?);
+                i += 1;
+            },
+            _ => positional.push(&args[i]),
+        }
+        i += 1;
+    }
+
+    if positional.is_empty()
+    {
+        return Err("Missing file parameter for sync.".to_string());
+    }
+    let file = positional[0].as_str();
+    let root = Path::new(positional.get(1).map(|arg| arg.as_str()).unwrap_or("."));
+    fs::create_dir_all(root).map_err(|error| format!("unable to create {}: {error}", root.display()))?;
+
+    // the phext (in any encoding, and its staging files) may live inside the synced directory
+    let output = relative_to(root, Path::new(file));
+    let staging = output.as_ref().map(|output| PathBuf::from(output.to_string_lossy().to_string() + ".tmp"));
+    let world = world_of(file).map(|(_, world)| world);
+    let beside = output.as_ref().and_then(|output| output.parent().map(Path::to_path_buf));
+    options.output = output.map(|output| output.to_string_lossy().to_string());
+    let rules = filter::PackFilter::new(root, &options)?;
+    let is_world = |path: &Path| {
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        return beside.is_some() && path.parent() == beside.as_deref() && world.as_deref().is_some_and(|world| storage::is_world_file(world, name));
+    };
+    let ignore = |path: &Path, is_dir: bool| rules.is_ignored(path, is_dir) || staging.as_deref() == Some(path) || (!is_dir && is_world(path));
+
+    if !exists(file)
+    {
+        println!("Packing {} into {file}...", root.display());
+        save(file, &archive::pack(&archive::collect(root, &ignore)?, &archive::empty_directories(root, &ignore)?))?;
+    }
+    else
+    {
+        let buffer = load(file)?;
+        let manifest = subspace::fetch(&buffer, phext::to_coordinate(archive::MANIFEST_COORDINATE));
+        let entries = archive::parse_manifest(manifest).map_err(|error| format!("unable to sync {file}: {error}"))?.entries;
+        if !entries.is_empty() && entries.iter().all(|entry| !root.join(&entry.path).exists())
+        {
+            println!("Extracting {file} into {}...", root.display());
+            archive::unpack(&buffer, root)?;
+        }
+    }
+
+    let mut reported: Vec<String> = Vec::new();
+    let mut raced = 0;
+    loop
+    {
+        let buffer = load(file)?;
+        let result = sync::reconcile(&buffer, root, &ignore)?;
+
+        // the phext is saved before any file is touched, and only if nothing else (ex: the server) wrote it
+        // during this pass - otherwise the pass is dropped and the next one starts from the newer phext,
+        // after waiting out the interval so that a busy writer doesn't keep us reloading in a tight loop
+        if result.buffer != buffer
+        {
+            if load(file)? != buffer
+            {
+                raced += 1;
+                if once && raced >= SYNC_ATTEMPTS
+                {
+                    return Err(format!("{file} kept changing during sync ({raced} attempts) - try again"));
+                }
+                std::thread::sleep(interval);
+                continue;
+            }
+            save(file, &result.buffer)?;
+        }
+        raced = 0;
+        if let Err(error) = sync::apply(root, &result.writes)
+        {
+            // files that weren't written would otherwise read as edits against the new checksums
+            if load(file)? == result.buffer
+            {
+                save(file, &buffer)?;
+            }
+            return Err(error);
+        }
+
+        let mut conflicts: Vec<String> = Vec::new();
+        for event in result.events.iter()
+        {
+            let message = event.to_string();
+            if !event.is_conflict()
+            {
+                println!("{message}");
+            }
+            else
+            {
+                if !reported.contains(&message)
+                {
+                    eprintln!("{message}");
+                }
+                conflicts.push(message);
+            }
+        }
+        reported = conflicts;
+
+        if once
+        {
+            return match reported.len()
+            {
+                0 => Ok(()),
+                count => Err(format!("{count} unresolved conflict(s)")),
+            };
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+fn run_grep(args: &[String]) -> Result<(), String>
+{
+    let usage = "Usage: ".to_string() + find_command("grep").map(|command| command.usage).unwrap_or_default();
+    let mut options = grep::GrepOptions::default();
+    let mut positional: Vec<&String> = Vec::new();
+    let mut i = 0;
+    while i < args.len()
+    {
+        let arg = args[i].as_str();
+        match arg
+        {
+            "-i" => options.ignore_case = true,
+            "-F" => options.literal = true,
+            "-C" | "--start" | "--end" =>
+            {
+                let value = args.get(i + 1).ok_or(format!("Missing value for {arg}."))?;
+                if arg == "-C" { options.context = value.parse::<usize>().map_err(|_| format!("Invalid context: {value}"))?; }
+                if arg == "--start" { options.start = Some(query::to_coordinate(value)?); }
+                if arg == "--end" { options.end = Some(query::to_coordinate(value)?); }
+                i += 1;
+            },
+            _ => positional.push(&args[i]),
+        }
+        i += 1;
+    }
+
+    if positional.len() < 2
+    {
+        return Err(usage);
+    }
+    options.pattern = positional[0].clone();
+    let file = positional[1];
+
+    let regex = grep::compile(&options).map_err(|error| format!("Invalid pattern: {error}"))?;
+    let buffer = load(file)?;
+    for line in grep::grep(&buffer, &regex, &options)
+    {
+        println!("{line}");
+    }
+    return Ok(());
+}
+
+/// the directory and world a `.phext` argument names (`notes/w.phext` is the world `w` in `notes`), so that
+/// it is read and written through FileStorage whether it is kept as `w.phext`, `w.phext.zst` or `w.phext.gz`
+fn world_of(file: &str) -> Option<(storage::FileStorage, String)>
+{
+    let path = Path::new(file);
+    let name = path.file_name()?.to_str()?;
+    let world = Codec::ALL.iter().find_map(|codec| name.strip_suffix(&format!(".phext{}", codec.extension())))?;
+    if !storage::is_world_name(world)
+    {
+        return None;
+    }
+    let root = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    return Some((storage::FileStorage::new(root.to_path_buf()), world.to_string()));
+}
+
+/// whether `file` exists - for a world, in any encoding
+pub(crate) fn exists(file: &str) -> bool
+{
+    return match world_of(file)
+    {
+        Some((files, world)) => files.modified(&world).is_some(),
+        None => Path::new(file).exists(),
+    };
+}
+
+/// the bytes of `file`, decompressed if it is a compressed world
+pub(crate) fn read(file: &str) -> Result<Vec<u8>, String>
+{
+    return match world_of(file)
+    {
+        Some((files, world)) => Ok(files.load(&world).map_err(|error| format!("unable to read {file}: {error}"))?.0),
+        None => fs::read(file).map_err(|error| format!("unable to read {file}: {error}")),
+    };
+}
+
+pub(crate) fn load(file: &str) -> Result<String, String>
+{
+    return String::from_utf8(read(file)?).map_err(|_| format!("unable to read {file}: it is not valid UTF-8 (see fsck)"));
+}
+
+/// replaces `file` via a staging file - a world keeps the encoding it is stored in
+pub(crate) fn save(file: &str, buffer: &str) -> Result<(), String>
+{
+    if let Some((files, world)) = world_of(file)
+    {
+        return files.store(&world, buffer, &Version::Any).map_err(|error| format!("unable to write {file}: {error}"));
+    }
+    let staging = file.to_owned() + ".tmp";
+    fs::write(&staging, buffer).map_err(|error| format!("unable to write {staging}: {error}"))?;
+    fs::rename(&staging, file).map_err(|error| format!("unable to replace {file}: {error}"))?;
+    return Ok(());
+}
+
+fn scroll_arguments(name: &str, args: &[String]) -> Result<(String, phext::Coordinate), String>
+{
+    if args.len() < 2
+    {
+        let usage = find_command(name).map(|command| command.usage).unwrap_or_default();
+        return Err(format!("Usage: phext {usage}"));
+    }
+    return Ok((args[0].clone(), query::to_coordinate(&args[1])?));
+}
+
+fn print_report(file: &str, report: &fsck::Report) -> String
+{
+    for finding in report.findings.iter()
+    {
+        println!("{}: {} ({}): {}", finding.severity.name(), finding.description, finding.count, finding.coordinates.join(" "));
+    }
+    return format!("{file}: {} error(s), {} warning(s)", report.errors, report.warnings);
+}
+
+fn run_fsck(args: &[String]) -> Result<(), String>
+{
+    let mut repair: Option<&String> = None;
+    let mut positional: Vec<&String> = Vec::new();
+    let mut i = 0;
+    while i < args.len()
+    {
+        if args[i] == "--repair"
+        {
+            repair = Some(args.get(i + 1).ok_or("Missing value for --repair.".to_string())?);
+            i += 1;
+        }
+        else
+        {
+            positional.push(&args[i]);
+        }
+        i += 1;
+    }
+    let file = positional.first().ok_or("Missing file parameter for fsck.".to_string())?.as_str();
+    let bytes = read(file)?;
+
+    let report = fsck::check(&bytes);
+    let summary = print_report(file, &report);
+    let output = match repair
+    {
+        Some(output) => output.as_str(),
+        None if report.errors > 0 => return Err(summary),
+        None =>
+        {
+            println!("{summary}");
+            return Ok(());
+        }
+    };
+    println!("{summary}");
+
+    let repaired = fsck::repair(&bytes);
+    save(output, &repaired)?;
+    println!("Wrote a repaired copy to {output}");
+    let report = fsck::check(repaired.as_bytes());
+    let summary = print_report(output, &report);
+    if report.errors > 0
+    {
+        return Err(summary);
+    }
+    println!("{summary}");
+    return Ok(());
+}
+
+fn run_to_json(args: &[String]) -> Result<(), String>
+{
+    let mut layout = document::Layout::Flat;
+    let mut positional: Vec<&String> = Vec::new();
+    let mut i = 0;
+    while i < args.len()
+    {
+        match args[i].as_str()
+        {
+            "--nested" => layout = document::Layout::Nested,
+            _ => positional.push(&args[i]),
+        }
+        i += 1;
+    }
+    let file = positional.first().ok_or("Missing file parameter for to-json.".to_string())?;
+    let output = document::export(&load(file)?, layout)?;
+    match positional.get(1)
+    {
+        Some(target) => save(target, &output)?,
+        None => println!("{output}"),
+    }
+    return Ok(());
+}
+
+fn run_from_json(args: &[String]) -> Result<(), String>
+{
+    if args.len() < 2
+    {
+        return Err("Usage: phext from-json <document> <phext>".to_string());
+    }
+    let buffer = document::import(&load(&args[0])?)?;
+    save(&args[1], &buffer)?;
+    println!("{}: {} scrolls", args[1], subspace::scrolls(&buffer).count());
+    return Ok(());
+}
+
+fn run_to_markdown(args: &[String]) -> Result<(), String>
+{
+    let usage = "Usage: ".to_string() + find_command("to-markdown").map(|command| command.usage).unwrap_or_default();
+    let mut start: Option<phext::Coordinate> = None;
+    let mut end: Option<phext::Coordinate> = None;
+    let mut positional: Vec<&String> = Vec::new();
+    let mut i = 0;
+    while i < args.len()
+    {
+        let arg = args[i].as_str();
+        match arg
+        {
+            "--start" | "--end" =>
+            {
+                let value = args.get(i + 1).ok_or(format!("Missing value for {arg}."))?;
+                if arg == "--start" { start = Some(query::to_coordinate(value)?); }
+                if arg == "--end" { end = Some(query::to_coordinate(value)?); }
+                i += 1;
+            },
+            _ => positional.push(&args[i]),
+        }
+        i += 1;
+    }
+    if positional.len() < 2
+    {
+        return Err(usage);
+    }
+
+    let range = match (start, end)
+    {
+        (None, None) => None,
+        _ => Some(phext::Range {
+            start: start.unwrap_or(phext::default_coordinate()),
+            end: end.unwrap_or(phext::to_coordinate("99.99.99/99.99.99/99.99.99")),
+        }),
+    };
+    let written = markdown::export(&load(positional[0])?, range, Path::new(positional[1]))?;
+    println!("Wrote {written} scrolls to {}", positional[1]);
+    return Ok(());
+}
+
+fn run_from_markdown(args: &[String]) -> Result<(), String>
+{
+    let mut merge = false;
+    let mut positional: Vec<&String> = Vec::new();
+    let mut i = 0;
+    while i < args.len()
+    {
+        match args[i].as_str()
+        {
+            "--merge" => merge = true,
+            _ => positional.push(&args[i]),
+        }
+        i += 1;
+    }
+    if positional.len() < 2
+    {
+        return Err("Usage: phext from-markdown [--merge] <directory> <phext>".to_string());
+    }
+    let (directory, file) = (positional[0], positional[1]);
+    let base = match merge && exists(file)
+    {
+        true => load(file)?,
+        false => String::new(),
+    };
+    let buffer = markdown::import(Path::new(directory), &base)?;
+    save(file, &buffer)?;
+    println!("{file}: {} scrolls", subspace::scrolls(&buffer).count());
+    return Ok(());
+}
+
+fn run_site(args: &[String]) -> Result<(), String>
+{
+    if args.len() < 2
+    {
+        return Err("Usage: phext site <phext> <directory>".to_string());
+    }
+    let world = match world_of(&args[0])
+    {
+        Some((_, world)) => world,
+        None => Path::new(&args[0]).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default(),
+    };
+    let pages = site::generate(&world, &load(&args[0])?, Path::new(&args[1]))?;
+    println!("Wrote {pages} pages to {}", args[1]);
+    return Ok(());
+}
+
+fn run_cat(args: &[String]) -> Result<(), String>
+{
+    let (file, coord) = scroll_arguments("cat", args)?;
+    let buffer = load(&file)?;
+    let mut stdout = std::io::stdout();
+    stdout.write_all(subspace::fetch(&buffer, coord).as_bytes()).map_err(|error| error.to_string())?;
+    stdout.flush().map_err(|error| error.to_string())?;
+    return Ok(());
+}
+
+fn run_put(args: &[String]) -> Result<(), String>
+{
+    let (file, coord) = scroll_arguments("put", args)?;
+    let scroll = read_stdin()?;
+    if archive::has_delimiters(scroll.as_bytes())
+    {
+        return Err("Scrolls may not contain phext delimiters".to_string());
+    }
+
+    let buffer = match exists(&file)
+    {
+        true => load(&file)?,
+        false => String::new(),
+    };
+    save(&file, &subspace::replace(&buffer, coord, &scroll))?;
+    println!("{coord}: {} bytes", scroll.len());
+    return Ok(());
+}
+
+fn run_rm(args: &[String]) -> Result<(), String>
+{
+    let (file, coord) = scroll_arguments("rm", args)?;
+    let buffer = load(&file)?;
+    if subspace::fetch(&buffer, coord).is_empty()
+    {
+        return Err(format!("No scroll found at {coord}"));
+    }
+    save(&file, &subspace::replace(&buffer, coord, ""))?;
+    return Ok(());
+}
+
+fn run_ls(args: &[String]) -> Result<(), String>
+{
+    if args.is_empty()
+    {
+        return Err("Usage: phext ls <phext> [prefix]".to_string());
+    }
+    let address = args.get(1).map(|arg| arg.as_str()).unwrap_or("");
+    let prefix = query::to_prefix(address).map_err(|_| format!("Invalid prefix: {address}"))?;
+    let buffer = load(&args[0])?;
+    for scroll in subspace::scrolls(&buffer).filter(|scroll| prefix.matches(scroll.coord))
+    {
+        println!("{}\t{}\t{}", scroll.coord, scroll.content.len(), toc::title(scroll.content));
+    }
+    return Ok(());
+}
+
+fn run_tree(args: &[String]) -> Result<(), String>
+{
+    if args.is_empty()
+    {
+        return Err("Usage: phext tree <phext>".to_string());
+    }
+    let buffer = load(&args[0])?;
+    let mut previous: Option<[usize; 9]> = None;
+    for scroll in subspace::scrolls(&buffer)
+    {
+        let dimensions = query::dimensions(scroll.coord);
+        // print every level beneath the first dimension that differs from the previous scroll
+        let first = match previous
+        {
+            Some(prior) => prior.iter().zip(dimensions.iter()).position(|(left, right)| left != right).unwrap_or(8),
+            None => 0,
+        };
+        for (level, value) in dimensions.iter().enumerate().take(8).skip(first)
+        {
+            println!("{}{} {}", "  ".repeat(level), stats::DIMENSION_NAMES[level], value);
+        }
+        println!("{}scroll {} ({} bytes): {}", "  ".repeat(8), dimensions[8], scroll.content.len(), toc::title(scroll.content));
+        previous = Some(dimensions);
+    }
+    return Ok(());
+}
+
+fn read_stdin() -> Result<String, String>
+{
+    let mut content = String::new();
+    std::io::stdin().read_to_string(&mut content).map_err(|error| format!("unable to read stdin: {error}"))?;
+    return Ok(content);
+}
+
+fn run_remote(args: &[String]) -> Result<(), String>
+{
+    let usage = "Usage: phext remote <url> <operation> <world> [args...]";
+    if args.len() < 3
+    {
+        return Err(usage.to_string());
+    }
+    let client = remote::Remote::new(&args[0]);
+    let operation = args[1].as_str();
+    let world = args[2].as_str();
+    let rest: Vec<&str> = args[3..].iter().map(|arg| arg.as_str()).collect();
+    match (operation, rest.as_slice())
+    {
+        ("select", []) => print!("{}", client.select_phext(world)?),
+        ("select", [coord]) => print!("{}", client.select(world, coord)?),
+        ("insert", []) => client.insert_phext(world, &read_stdin()?)?,
+        ("insert", [coord]) => client.insert(world, coord, &read_stdin()?)?,
+        ("update", []) => client.update_phext(world, &read_stdin()?)?,
+        ("update", [coord]) => client.update(world, coord, &read_stdin()?)?,
+        ("delete", []) => client.delete_phext(world)?,
+        ("delete", [coord]) => client.delete(world, coord)?,
+        ("replace", [start, end]) => client.replace(world, start, end, &read_stdin()?)?,
+        ("merge", [mother, father]) => client.merge(world, mother, father)?,
+        ("subtract", [other]) => client.subtract(world, other)?,
+        ("normalize", []) => client.normalize(world, &client.select_phext(world)?)?,
+        ("expand", []) => client.expand(world, &client.select_phext(world)?)?,
+        ("contract", []) => client.contract(world, &client.select_phext(world)?)?,
+        _ => return Err(format!("Unknown remote operation: {}\n{usage}", args[1..].join(" "))),
+    }
+    return Ok(());
+}
+
+fn run_shell(args: &[String]) -> Result<(), String>
+{
+    if args.first().map(|arg| arg.as_str()) == Some("--script")
+    {
+        let script = args.get(1).ok_or("Missing value for --script.".to_string())?;
+        return shell::run_script(&load(script)?, &mut std::io::stdout());
+    }
+    return shell::run_interactive(args.first().map(|arg| arg.as_str()));
+}
+
+fn run_help(args: &[String]) -> Result<(), String>
+{
+    print_version();
+    if let Some(name) = args.first()
+    {
+        let command = find_command(name).ok_or(format!("Unknown command: {name}"))?;
+        println!("Usage: phext {}", command.usage);
+        println!("{}", command.summary);
+        return Ok(());
+    }
+
+    run_example();
+    return Ok(());
+}
+
+fn run_example() {
+    let buffer = "Quick Example\x17Second Scroll\x18Second Section\x19Section Chapter";
+    let scroll1 = phext::fetch(buffer, phext::to_coordinate("1.1.1/1.1.1/1.1.1"));
+    let scroll2 = phext::fetch(buffer, phext::to_coordinate("1.1.1/1.1.1/1.1.2"));
+    let scroll3 = phext::fetch(buffer, phext::to_coordinate("1.1.1/1.1.1/1.2.1"));
+    let scroll4 = phext::fetch(buffer, phext::to_coordinate("1.1.1/1.1.1/2.1.1"));
+
+    println!("The Reference Phext has 4 scrolls, listed below:");
+    println!("* {scroll1}");
+    println!("* {scroll2}");
+    println!("* {scroll3}");
+    println!("* {scroll4}");
+}
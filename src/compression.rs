@@ -0,0 +1,154 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Phext Compression
+///
+/// Plain-text worlds compress very well, so a data root can keep them on disk as `<world>.phext.zst` or
+/// `<world>.phext.gz`, decompressing them as they are read. A `Compression` policy decides which worlds are
+/// stored compressed, by size and by how long they have been idle. The same codecs are offered to HTTP
+/// clients through Content-Encoding negotiation.
+/// ----------------------------------------------------------------------------------------------------------
+use std::io::{Read, Write};
+use std::time::Duration;
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum Codec
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Codec {
+  #[default]
+  Plain,
+  Zstd,
+  Gzip,
+}
+
+impl Codec {
+  /// every codec, in the order a world's files are looked for
+  pub const ALL: [Codec; 3] = [Codec::Plain, Codec::Zstd, Codec::Gzip];
+
+  pub fn parse(name: &str) -> Result<Codec, String> {
+    return match name {
+      "none" | "plain" => Ok(Codec::Plain),
+      "zstd" | "zst" => Ok(Codec::Zstd),
+      "gzip" | "gz" => Ok(Codec::Gzip),
+      _ => Err(format!("Unknown compression: {} (expected zstd, gzip or none)", name)),
+    };
+  }
+
+  pub fn name(&self) -> &'static str {
+    return match self {
+      Codec::Plain => "none",
+      Codec::Zstd => "zstd",
+      Codec::Gzip => "gzip",
+    };
+  }
+
+  /// the suffix added to `<world>.phext` when a world is stored with this codec
+  pub fn extension(&self) -> &'static str {
+    return match self {
+      Codec::Plain => "",
+      Codec::Zstd => ".zst",
+      Codec::Gzip => ".gz",
+    };
+  }
+
+  /// the Content-Encoding this codec is sent with, if any
+  pub fn content_encoding(&self) -> Option<&'static str> {
+    return match self {
+      Codec::Plain => None,
+      Codec::Zstd => Some("zstd"),
+      Codec::Gzip => Some("gzip"),
+    };
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn encode
+/// ----------------------------------------------------------------------------------------------------------
+pub fn encode(bytes: &[u8], codec: Codec) -> Result<Vec<u8>, String> {
+  let failed = |error: std::io::Error| format!("unable to compress with {}: {}", codec.name(), error);
+  return match codec {
+    Codec::Plain => Ok(bytes.to_vec()),
+    Codec::Zstd => zstd::encode_all(bytes, 0).map_err(failed),
+    Codec::Gzip => {
+      let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+      encoder.write_all(bytes).map_err(failed)?;
+      encoder.finish().map_err(failed)
+    },
+  };
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn decode
+/// ----------------------------------------------------------------------------------------------------------
+pub fn decode(bytes: &[u8], codec: Codec) -> Result<Vec<u8>, String> {
+  let failed = |error: std::io::Error| format!("unable to decompress {}: {}", codec.name(), error);
+  return match codec {
+    Codec::Plain => Ok(bytes.to_vec()),
+    Codec::Zstd => zstd::decode_all(bytes).map_err(failed),
+    Codec::Gzip => {
+      let mut decoded = Vec::new();
+      flate2::read::GzDecoder::new(bytes).read_to_end(&mut decoded).map_err(failed)?;
+      Ok(decoded)
+    },
+  };
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Compression
+///
+/// Which worlds to store compressed: those of at least `min_bytes` (uncompressed) that have gone unchanged
+/// for at least `idle`, when set. A world that is written is no longer idle, so with an idle time it is
+/// stored plain until a later sweep finds it idle again.
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Compression {
+  pub codec: Codec,
+  pub min_bytes: usize,
+  pub idle: Option<Duration>,
+}
+
+impl Compression {
+  /// the codec for a world of `bytes` that has been unchanged for `idle_for`
+  pub fn choose(&self, bytes: usize, idle_for: Duration) -> Codec {
+    let idle = self.idle.map(|idle| idle_for >= idle).unwrap_or(true);
+    return match bytes >= self.min_bytes && idle {
+      true => self.codec,
+      false => Codec::Plain,
+    };
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn negotiate
+///
+/// picks the codec to answer a request with from its Accept-Encoding header, honouring q-values (and `*`).
+/// Prefers zstd over gzip when the client accepts both equally, and falls back to no encoding.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn negotiate(accept_encoding: &str) -> Codec {
+  let mut weights: Vec<(String, f32)> = Vec::new();
+  for item in accept_encoding.split(',') {
+    let mut parts = item.split(';');
+    let name = parts.next().unwrap_or_default().trim().to_lowercase();
+    let weight = parts
+      .filter_map(|parameter| parameter.trim().strip_prefix("q="))
+      .find_map(|value| value.trim().parse::<f32>().ok())
+      .unwrap_or(1.0);
+    if !name.is_empty() {
+      weights.push((name, weight));
+    }
+  }
+  let weight = |name: &str| {
+    let named = weights.iter().find(|(candidate, _)| candidate == name);
+    return named.or(weights.iter().find(|(candidate, _)| candidate == "*")).map(|(_, weight)| *weight).unwrap_or(0.0);
+  };
+
+  let mut best = Codec::Plain;
+  let mut best_weight = 0.0;
+  for codec in [Codec::Zstd, Codec::Gzip] {
+    let candidate = weight(codec.content_encoding().unwrap_or_default());
+    if candidate > best_weight {
+      best = codec;
+      best_weight = candidate;
+    }
+  }
+  return best;
+}
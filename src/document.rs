@@ -0,0 +1,233 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Phext JSON Documents
+///
+/// Converts worlds to and from JSON, so services that can't link libphext can still read and write them.
+/// The flat layout is an array of `{coordinate, content}` objects in subspace order; the nested layout is an
+/// object keyed by each dimension in turn (library, shelf, series, collection, volume, book, chapter, section,
+/// scroll) with scroll content at the leaves. Either layout converts back to the exact buffer it came from.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use rocket::serde::json::{self, Value};
+use rocket::serde::ser::SerializeMap;
+use rocket::serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
+use crate::{archive, query, subspace};
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum Layout
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Layout {
+  Flat,
+  Nested,
+}
+
+impl Layout {
+  pub fn parse(name: &str) -> Result<Layout, String> {
+    return match name {
+      "flat" => Ok(Layout::Flat),
+      "nested" => Ok(Layout::Nested),
+      _ => Err(format!("Unknown JSON layout: {} (expected flat or nested)", name)),
+    };
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Entry
+///
+/// One scroll in the flat layout
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Entry {
+  pub coordinate: String,
+  pub content: String,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum Node
+///
+/// One level of the nested layout, serialized with its keys in numeric order
+/// ----------------------------------------------------------------------------------------------------------
+enum Node<'a> {
+  Scroll(&'a str),
+  Branch(Vec<(usize, Node<'a>)>),
+}
+
+impl<'a> Node<'a> {
+  fn insert(&mut self, path: &[usize], content: &'a str) {
+    let Node::Branch(children) = self else {
+      return;
+    };
+    if path.len() == 1 {
+      children.push((path[0], Node::Scroll(content)));
+      return;
+    }
+    if children.last().map(|(key, _)| *key) != Some(path[0]) {
+      children.push((path[0], Node::Branch(Vec::new())));
+    }
+    if let Some((_, child)) = children.last_mut() {
+      child.insert(&path[1..], content);
+    }
+  }
+}
+
+impl Serialize for Node<'_> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    return match self {
+      Node::Scroll(content) => serializer.serialize_str(content),
+      Node::Branch(children) => {
+        let mut map = serializer.serialize_map(Some(children.len()))?;
+        for (key, child) in children {
+          map.serialize_entry(&key.to_string(), child)?;
+        }
+        map.end()
+      },
+    };
+  }
+}
+
+/// delimiters sort by byte value, except the library break (0x01), which is the highest dimension
+fn rank(delimiter: u8) -> u8 {
+  return match delimiter == phext::LIBRARY_BREAK as u8 {
+    true => u8::MAX,
+    false => delimiter,
+  };
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn scrolls
+///
+/// lists the scrolls needed to rebuild `buffer` exactly: every populated scroll, plus the empty ones that
+/// `subspace::assemble` can't infer - a lower break followed by a higher one (ex: `\x17\x18`), and a
+/// trailing delimiter. Fails if the buffer can't be reproduced, which happens when it has coordinates
+/// beyond 99.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn scrolls(buffer: &str) -> Result<Vec<subspace::Scroll<'_>>, String> {
+  let bytes = buffer.as_bytes();
+  let mut result = Vec::new();
+  let mut coord = phext::default_coordinate();
+  let mut start = 0;
+  let mut before: Option<u8> = None;
+  for index in 0..=bytes.len() {
+    let after = match bytes.get(index) {
+      Some(byte) if archive::has_delimiters(&[*byte]) => Some(*byte),
+      Some(_) => continue,
+      None => None,
+    };
+    let content = &buffer[start..index];
+    let needed = match (before, after) {
+      _ if !content.is_empty() => true,
+      (Some(before), Some(after)) => rank(before) < rank(after),
+      (Some(_), None) => true,
+      (None, _) => false,
+    };
+    if needed {
+      result.push(subspace::Scroll { coord, content, offset: start });
+    }
+    if let Some(delimiter) = after {
+      subspace::advance(&mut coord, delimiter);
+      before = after;
+      start = index + 1;
+    }
+  }
+
+  if subspace::assemble(phext::default_coordinate(), result.iter().copied()) != buffer {
+    return Err("This world has coordinates beyond 99, which can't be represented exactly (see fsck)".to_string());
+  }
+  return Ok(result);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn export
+///
+/// converts `buffer` to a JSON document in the given layout
+/// ----------------------------------------------------------------------------------------------------------
+pub fn export(buffer: &str, layout: Layout) -> Result<String, String> {
+  let scrolls = scrolls(buffer)?;
+  let result = match layout {
+    Layout::Flat => {
+      let entries: Vec<Entry> = scrolls.iter().map(|scroll| Entry {
+        coordinate: scroll.coord.to_string(),
+        content: scroll.content.to_string(),
+      }).collect();
+      json::to_string(&entries)
+    },
+    Layout::Nested => {
+      let mut root = Node::Branch(Vec::new());
+      for scroll in &scrolls {
+        root.insert(&query::dimensions(scroll.coord), scroll.content);
+      }
+      json::to_string(&root)
+    },
+  };
+  return result.map_err(|error| format!("unable to serialize JSON: {}", error));
+}
+
+fn collect_nested(value: &Value, path: &mut Vec<usize>, scrolls: &mut BTreeMap<[usize; 9], String>) -> Result<(), String> {
+  let address = path.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(".");
+  if path.len() == 9 {
+    let content = value.as_str().ok_or(format!("Expected scroll content (a string) at {}", address))?;
+    let dimensions: [usize; 9] = path.as_slice().try_into().expect("nine dimensions");
+    if scrolls.insert(dimensions, content.to_string()).is_some() {
+      return Err(format!("Duplicate coordinate: {}", archive::to_coordinate(&dimensions)));
+    }
+    return Ok(());
+  }
+
+  let children = value.as_object().ok_or(format!("Expected an object keyed by dimension at {}", match path.len() {
+    0 => "the top level".to_string(),
+    _ => address,
+  }))?;
+  for (key, child) in children {
+    let dimension: usize = key.parse().ok()
+      .filter(|value| *value >= 1 && *value < phext::COORDINATE_MAXIMUM)
+      .ok_or(format!("Invalid dimension key: {} (expected 1 to {})", key, phext::COORDINATE_MAXIMUM - 1))?;
+    path.push(dimension);
+    collect_nested(child, path, scrolls)?;
+    path.pop();
+  }
+  return Ok(());
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn import_value
+///
+/// converts a parsed JSON document (either layout) back into a phext buffer. Entries may appear in any order,
+/// but coordinates must be unique and scrolls may not contain delimiters.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn import_value(value: Value) -> Result<String, String> {
+  let mut scrolls: BTreeMap<[usize; 9], String> = BTreeMap::new();
+  match value {
+    Value::Array(_) => {
+      let entries: Vec<Entry> = json::from_value(value).map_err(|error| format!("Invalid JSON document: {}", error))?;
+      for entry in entries {
+        let dimensions = query::dimensions(query::to_coordinate(&entry.coordinate)?);
+        if scrolls.insert(dimensions, entry.content).is_some() {
+          return Err(format!("Duplicate coordinate: {}", entry.coordinate));
+        }
+      }
+    },
+    _ => collect_nested(&value, &mut Vec::new(), &mut scrolls)?,
+  }
+
+  let mut ordered = Vec::new();
+  for (dimensions, content) in &scrolls {
+    let coord = archive::to_coordinate(dimensions);
+    if archive::has_delimiters(content.as_bytes()) {
+      return Err(format!("The scroll at {} contains phext delimiters", coord));
+    }
+    ordered.push(subspace::Scroll { coord, content: content.as_str(), offset: 0 });
+  }
+  return Ok(subspace::assemble(phext::default_coordinate(), ordered.into_iter()));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn import
+///
+/// parses `text` as a JSON document and converts it into a phext buffer
+/// ----------------------------------------------------------------------------------------------------------
+pub fn import(text: &str) -> Result<String, String> {
+  let value: Value = json::from_str(text).map_err(|error| format!("Invalid JSON document: {}", error))?;
+  return import_value(value);
+}
@@ -0,0 +1,106 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Pack Filters
+///
+/// Decides which paths `pack` skips: a few built-in rules, a `.phextignore` file (and optionally the
+/// `.gitignore`) at the root of the tree using gitignore semantics, and any --include/--exclude globs.
+/// ----------------------------------------------------------------------------------------------------------
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Component, Path, PathBuf};
+
+/// name of the ignore file read from the root of a packed tree
+pub const PHEXT_IGNORE: &str = ".phextignore";
+
+/// rules applied before any ignore file, so a `.phextignore` can re-include them with `!`
+const DEFAULT_RULES: [&str; 3] = [".git/", "Cargo.lock", ".gitignore"];
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct FilterOptions
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Default, Clone)]
+pub struct FilterOptions {
+  pub gitignore: bool,
+  pub includes: Vec<String>,
+  pub excludes: Vec<String>,
+  pub output: Option<String>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct PackFilter
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug)]
+pub struct PackFilter {
+  rules: Gitignore,
+  includes: Option<GlobSet>,
+  excludes: GlobSet,
+  output: Option<String>,
+}
+
+fn build_globs(patterns: &[String]) -> Result<GlobSet, String> {
+  let mut builder = GlobSetBuilder::new();
+  for pattern in patterns {
+    builder.add(Glob::new(pattern).map_err(|error| format!("invalid glob {}: {}", pattern, error))?);
+  }
+  return builder.build().map_err(|error| error.to_string());
+}
+
+fn matches_glob(globs: &GlobSet, path: &Path) -> bool {
+  return globs.is_match(path) || path.file_name().is_some_and(|name| globs.is_match(name));
+}
+
+impl PackFilter {
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn new
+  ///
+  /// loads the ignore files found at `root` and compiles the include/exclude globs
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn new(root: &Path, options: &FilterOptions) -> Result<PackFilter, String> {
+    let mut builder = GitignoreBuilder::new(root);
+    for rule in DEFAULT_RULES {
+      builder.add_line(None, rule).map_err(|error| error.to_string())?;
+    }
+    let mut files = vec![];
+    if options.gitignore {
+      files.push(root.join(".gitignore"));
+    }
+    files.push(root.join(PHEXT_IGNORE));
+    for file in files.iter().filter(|file| file.is_file()) {
+      if let Some(error) = builder.add(file) {
+        return Err(format!("unable to read {}: {}", file.display(), error));
+      }
+    }
+    let rules = builder.build().map_err(|error| error.to_string())?;
+
+    let includes = match options.includes.len() {
+      0 => None,
+      _ => Some(build_globs(&options.includes)?),
+    };
+    let excludes = build_globs(&options.excludes)?;
+
+    return Ok(PackFilter { rules, includes, excludes, output: options.output.clone() });
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn is_ignored
+  ///
+  /// determines if `path` (relative to the packed root) should be skipped. Includes only restrict files, so
+  /// that directories are still searched for files that match.
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+    let is_output = self.output.as_deref().is_some_and(|output| {
+      return Path::new(output).components().filter(|component| !matches!(component, Component::CurDir)).collect::<PathBuf>() == path;
+    });
+    if is_output {
+      return true;
+    }
+    if self.rules.matched(path, is_dir).is_ignore() || matches_glob(&self.excludes, path) {
+      return true;
+    }
+    if let Some(includes) = &self.includes {
+      if !is_dir && !matches_glob(includes, path) {
+        return true;
+      }
+    }
+    return false;
+  }
+}
@@ -0,0 +1,231 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Phext Validation
+///
+/// Checks a world for problems that other tools trip over: bytes that aren't UTF-8, control characters that
+/// aren't phext delimiters, delimiter runs that `normalize` would collapse, coordinates beyond what the editor
+/// or libphext can address, and manifest scrolls whose entries no longer match the scrolls they describe.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use rocket::serde::Serialize;
+use std::collections::BTreeSet;
+use crate::{archive, query, stats, subspace};
+
+/// number of example coordinates reported for each finding
+const FINDING_EXAMPLES: usize = 10;
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum Severity
+///
+/// Errors make `fsck` fail; warnings describe content that works but should be cleaned up
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum Severity {
+  Error,
+  Warning,
+}
+
+impl Severity {
+  pub fn name(&self) -> &'static str {
+    return match self {
+      Severity::Error => "error",
+      Severity::Warning => "warning",
+    };
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Finding
+///
+/// One class of problem, with a count and a few of the coordinates it occurs at
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Finding {
+  pub severity: Severity,
+  pub kind: String,
+  pub description: String,
+  pub count: usize,
+  pub coordinates: Vec<String>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Report
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Report {
+  pub bytes: usize,
+  pub scrolls: usize,
+  pub errors: usize,
+  pub warnings: usize,
+  pub findings: Vec<Finding>,
+}
+
+impl Finding {
+  fn new(severity: Severity, kind: &str, description: &str) -> Finding {
+    return Finding { severity, kind: kind.to_string(), description: description.to_string(), count: 0, coordinates: vec![] };
+  }
+
+  fn record(&mut self, coordinate: String, occurrences: usize) {
+    if occurrences == 0 {
+      return;
+    }
+    self.count += occurrences;
+    if self.coordinates.len() < FINDING_EXAMPLES && !self.coordinates.contains(&coordinate) {
+      self.coordinates.push(coordinate);
+    }
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn invalid_utf8
+///
+/// finds the offset of every invalid UTF-8 sequence in `bytes`
+/// ----------------------------------------------------------------------------------------------------------
+fn invalid_utf8(bytes: &[u8]) -> Vec<usize> {
+  let mut offsets = Vec::new();
+  let mut start = 0;
+  while start < bytes.len() {
+    match std::str::from_utf8(&bytes[start..]) {
+      Ok(_) => break,
+      Err(error) => {
+        let offset = start + error.valid_up_to();
+        offsets.push(offset);
+        start = offset + error.error_len().unwrap_or(bytes.len() - offset);
+      },
+    }
+  }
+  return offsets;
+}
+
+fn check_manifest(text: &str, findings: &mut Vec<Finding>) {
+  let manifest_coordinate = phext::to_coordinate(archive::MANIFEST_COORDINATE);
+  let manifest = subspace::fetch(text, manifest_coordinate);
+  if !archive::is_manifest(manifest) {
+    return;
+  }
+  let entries = match archive::parse_manifest(manifest) {
+    Ok(manifest) => manifest.entries,
+    Err(error) => {
+      let mut unreadable = Finding::new(Severity::Error, "manifest", &format!("unreadable manifest ({})", error));
+      unreadable.record(manifest_coordinate.to_string(), 1);
+      findings.push(unreadable);
+      return;
+    },
+  };
+
+  let mut unsafe_paths = Finding::new(Severity::Error, "unsafe-path", "manifest paths that would escape the unpack directory");
+  let mut duplicates = Finding::new(Severity::Error, "duplicate", "manifest entries that share a coordinate or path");
+  let mut checksums = Finding::new(Severity::Error, "checksum", "scrolls that no longer match their manifest checksum");
+  let mut encodings = Finding::new(Severity::Error, "encoding", "base64 scrolls that can't be decoded");
+  let mut coordinates = BTreeSet::new();
+  let mut paths = BTreeSet::new();
+  for entry in &entries {
+    let content = subspace::fetch(text, entry.coordinate);
+    unsafe_paths.record(entry.coordinate.to_string(), !archive::is_safe_path(&entry.path) as usize);
+    let repeated = !coordinates.insert(query::dimensions(entry.coordinate)) | !paths.insert(entry.path.as_str());
+    duplicates.record(entry.coordinate.to_string(), repeated as usize);
+    checksums.record(entry.coordinate.to_string(), (phext::checksum(content) != entry.hash) as usize);
+    encodings.record(entry.coordinate.to_string(), archive::decode(entry.encoding, content).is_err() as usize);
+  }
+  findings.extend([unsafe_paths, duplicates, checksums, encodings].into_iter().filter(|finding| finding.count > 0));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn check
+///
+/// validates the raw bytes of a world, returning every problem found (an empty list of errors means the world
+/// is safe to load)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn check(bytes: &[u8]) -> Report {
+  let mut findings: Vec<Finding> = Vec::new();
+
+  let mut utf8 = Finding::new(Severity::Error, "utf8", "invalid UTF-8 sequences");
+  let mut coord = phext::default_coordinate();
+  let mut position = 0;
+  for offset in invalid_utf8(bytes) {
+    for byte in &bytes[position..offset] {
+      subspace::advance(&mut coord, *byte);
+    }
+    position = offset;
+    utf8.record(coord.to_string(), 1);
+  }
+  if utf8.count > 0 {
+    findings.push(utf8);
+  }
+
+  // delimiters are ASCII, so replacing invalid sequences leaves every coordinate where it was
+  let text = String::from_utf8_lossy(bytes);
+  let world = stats::analyze(&text);
+  for anomaly in world.anomalies {
+    let severity = match anomaly.kind.as_str() {
+      "control" => Severity::Error,
+      _ => Severity::Warning,
+    };
+    findings.push(Finding { severity, kind: anomaly.kind, description: anomaly.description, count: anomaly.count, coordinates: anomaly.coordinates });
+  }
+
+  let mut overflow = Finding::new(Severity::Error, "overflow", "scrolls beyond coordinate 99, which libphext folds onto 99 (and the editor lets you address up to 1000)");
+  // libphext stops counting at 99, so walk the delimiters with counters of our own
+  let mut dimensions = [1; 9];
+  let mut populated = false;
+  for byte in text.bytes().chain(std::iter::once(phext::LIBRARY_BREAK as u8)) {
+    let level = stats::delimiter_name(byte).and_then(|name| stats::DIMENSION_NAMES.iter().position(|dimension| *dimension == name));
+    let Some(level) = level else {
+      populated = true;
+      continue;
+    };
+    if populated && dimensions.iter().any(|value| *value >= phext::COORDINATE_MAXIMUM) {
+      overflow.record(stats::format_prefix(&dimensions), 1);
+    }
+    dimensions[level] += 1;
+    dimensions[level + 1..].fill(1);
+    populated = false;
+  }
+  if overflow.count > 0 {
+    findings.push(overflow);
+  }
+
+  check_manifest(&text, &mut findings);
+
+  return Report {
+    bytes: bytes.len(),
+    scrolls: world.scrolls,
+    errors: findings.iter().filter(|finding| finding.severity == Severity::Error).count(),
+    warnings: findings.iter().filter(|finding| finding.severity == Severity::Warning).count(),
+    findings,
+  };
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn repair
+///
+/// produces a fixed copy of a world: invalid UTF-8 becomes U+FFFD, stray control characters are dropped,
+/// line endings become LF, and redundant delimiters are collapsed. Manifest checksums that matched before
+/// the repair are updated to match the repaired scrolls; ones that were already wrong are left alone.
+/// Coordinates beyond the editor's reach can't be fixed automatically, so they are still reported by `check`.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn repair(bytes: &[u8]) -> String {
+  let text = String::from_utf8_lossy(bytes);
+  let cleaned: String = text.replace("\r\n", "\n").chars()
+    .filter(|c| !(c.is_ascii() && stats::is_stray_control(*c as u8)))
+    .collect();
+  let repaired = subspace::assemble(phext::default_coordinate(), subspace::scrolls(&cleaned));
+
+  let manifest_coordinate = phext::to_coordinate(archive::MANIFEST_COORDINATE);
+  let mut recorded = match archive::parse_manifest(subspace::fetch(&text, manifest_coordinate)) {
+    Ok(recorded) => recorded,
+    Err(_) => return repaired,
+  };
+  for entry in recorded.entries.iter_mut() {
+    if phext::checksum(subspace::fetch(&text, entry.coordinate)) == entry.hash {
+      entry.hash = phext::checksum(subspace::fetch(&repaired, entry.coordinate));
+    }
+  }
+  let manifest = archive::format_manifest(&recorded);
+  return subspace::assemble(phext::default_coordinate(), subspace::scrolls(&repaired).map(|scroll| match scroll.coord == manifest_coordinate {
+    true => subspace::Scroll { content: manifest.as_str(), ..scroll },
+    false => scroll,
+  }));
+}
@@ -0,0 +1,149 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Phext Grep
+///
+/// Exact (regex or literal) matching over a phext buffer, reported as `coordinate:line:column:text` so that
+/// every hit can be traced back to its scroll. Scrolls are scanned one at a time, borrowing directly from
+/// the buffer, so matches are produced as the scan proceeds.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use regex::{Regex, RegexBuilder};
+use crate::subspace;
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct GrepOptions
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Default, Clone)]
+pub struct GrepOptions {
+  pub pattern: String,
+  pub literal: bool,
+  pub ignore_case: bool,
+  pub context: usize,
+  pub start: Option<phext::Coordinate>,
+  pub end: Option<phext::Coordinate>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum LineKind
+///
+/// Matching lines carry the (1-based, character) column of their first match; context lines are printed
+/// around them, and separators divide non-adjacent groups of lines
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LineKind {
+  Match(usize),
+  Context,
+  Separator,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct GrepLine
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct GrepLine<'a> {
+  pub coord: phext::Coordinate,
+  pub line: usize,
+  pub kind: LineKind,
+  pub text: &'a str,
+}
+
+impl std::fmt::Display for GrepLine<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    return match self.kind {
+      LineKind::Match(column) => write!(f, "{}:{}:{}:{}", self.coord, self.line, column, self.text),
+      LineKind::Context => write!(f, "{}-{}-{}", self.coord, self.line, self.text),
+      LineKind::Separator => write!(f, "--"),
+    };
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn compile
+///
+/// builds the matcher described by `options`, escaping the pattern for literal searches
+/// ----------------------------------------------------------------------------------------------------------
+pub fn compile(options: &GrepOptions) -> Result<Regex, regex::Error> {
+  let mut pattern = options.pattern.clone();
+  if options.literal {
+    pattern = regex::escape(&pattern);
+  }
+  return RegexBuilder::new(&pattern).case_insensitive(options.ignore_case).build();
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn in_bounds
+///
+/// applies the optional coordinate-range filter from `options`
+/// ----------------------------------------------------------------------------------------------------------
+pub fn in_bounds(coord: phext::Coordinate, options: &GrepOptions) -> bool {
+  if let Some(start) = options.start {
+    if coord < start { return false; }
+  }
+  if let Some(end) = options.end {
+    if coord > end { return false; }
+  }
+  return true;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn grep_scroll
+///
+/// finds the matching lines (and their context) within a single scroll
+/// ----------------------------------------------------------------------------------------------------------
+pub fn grep_scroll<'a>(scroll: subspace::Scroll<'a>, regex: &Regex, context: usize) -> Vec<GrepLine<'a>> {
+  let lines: Vec<&'a str> = scroll.content.split('\n').map(|line| line.trim_end_matches('\r')).collect();
+  let mut columns: Vec<Option<usize>> = vec![None; lines.len()];
+  for (i, line) in lines.iter().enumerate() {
+    if let Some(found) = regex.find(line) {
+      columns[i] = Some(line[..found.start()].chars().count() + 1);
+    }
+  }
+
+  let mut output: Vec<GrepLine<'a>> = Vec::new();
+  let mut last_printed: Option<usize> = None;
+  for (i, column) in columns.iter().enumerate() {
+    if column.is_none() {
+      continue;
+    }
+    let first = i.saturating_sub(context);
+    let last = std::cmp::min(i + context, lines.len() - 1);
+    let from = match last_printed {
+      Some(printed) if printed >= first => printed + 1,
+      Some(_) if context > 0 => {
+        output.push(GrepLine { coord: scroll.coord, line: 0, kind: LineKind::Separator, text: "" });
+        first
+      },
+      _ => first,
+    };
+    for j in from..=last {
+      let kind = match columns[j] {
+        Some(column) => LineKind::Match(column),
+        None => LineKind::Context,
+      };
+      output.push(GrepLine { coord: scroll.coord, line: j + 1, kind, text: lines[j] });
+    }
+    last_printed = Some(std::cmp::max(last, last_printed.unwrap_or(0)));
+  }
+
+  return output;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn grep
+///
+/// lazily scans `buffer` scroll by scroll, yielding matching lines as each scroll is visited
+/// ----------------------------------------------------------------------------------------------------------
+pub fn grep<'a>(buffer: &'a str, regex: &'a Regex, options: &'a GrepOptions) -> impl Iterator<Item = GrepLine<'a>> + 'a {
+  let mut previous: Option<phext::Coordinate> = None;
+  return subspace::scrolls(buffer)
+    .filter(move |scroll| in_bounds(scroll.coord, options))
+    .flat_map(move |scroll| {
+      let mut lines = grep_scroll(scroll, regex, options.context);
+      if options.context > 0 && !lines.is_empty() && previous.is_some() {
+        lines.insert(0, GrepLine { coord: scroll.coord, line: 0, kind: LineKind::Separator, text: "" });
+      }
+      if !lines.is_empty() {
+        previous = Some(scroll.coord);
+      }
+      return lines;
+    });
+}
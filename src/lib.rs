@@ -0,0 +1,32 @@
+#![allow(clippy::needless_return)]
+/// ----------------------------------------------------------------------------------------------------------
+/// hello-phext
+///
+/// The phext toolkit behind the `phext` binary: the Rocket API server (`server`), the command line
+/// (`cli`), and the subspace, query, search and archive modules they share.
+/// ----------------------------------------------------------------------------------------------------------
+#[macro_use] extern crate rocket;
+extern crate libphext;
+
+pub mod subspace;
+pub mod query;
+pub mod toc;
+pub mod stats;
+pub mod search;
+pub mod grep;
+pub mod rewrite;
+pub mod archive;
+pub mod backup;
+pub mod compression;
+pub mod document;
+pub mod markdown;
+pub mod site;
+pub mod filter;
+pub mod fsck;
+pub mod shell;
+pub mod objects;
+pub mod storage;
+pub mod sync;
+pub mod server;
+pub mod remote;
+pub mod cli;
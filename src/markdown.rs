@@ -0,0 +1,143 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Phext Markdown Folders
+///
+/// Exports a world (or a range of it) as a tree of Markdown files, one per scroll, so it can be edited with
+/// ordinary Markdown tooling. Directories follow the first eight dimensions (library/shelf/.../section), each
+/// file is named after its scroll, and a YAML front-matter block records the full coordinate. Importing reads
+/// the coordinate back from the front matter, so files can be renamed or moved without losing their place.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::{archive, query, subspace};
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn relative_path
+///
+/// the file a scroll is exported to, ex: 01/01/01/01/01/01/01/02/03.md for 1.1.1/1.1.1/1.2.3 (the numbers are
+/// zero-padded so that file browsers list them in subspace order)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn relative_path(coord: phext::Coordinate) -> PathBuf {
+  let dimensions = query::dimensions(coord);
+  let mut path: PathBuf = dimensions[..8].iter().map(|value| format!("{:02}", value)).collect();
+  path.push(format!("{:02}.md", dimensions[8]));
+  return path;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn format_scroll
+///
+/// renders a scroll as a Markdown document with its coordinate in the front matter
+/// ----------------------------------------------------------------------------------------------------------
+pub fn format_scroll(coord: phext::Coordinate, content: &str) -> String {
+  return format!("---\ncoordinate: {}\n---\n{}", coord, content);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn parse_scroll
+///
+/// splits a Markdown document into the coordinate named by its front matter and the scroll content after it
+/// ----------------------------------------------------------------------------------------------------------
+pub fn parse_scroll(text: &str) -> Result<(phext::Coordinate, &str), String> {
+  let mut lines = text.split_inclusive('\n');
+  let opening = lines.next().unwrap_or_default();
+  if opening.trim_end() != "---" {
+    return Err("missing front matter (expected a leading --- line)".to_string());
+  }
+  let mut coordinate: Option<phext::Coordinate> = None;
+  let mut offset = opening.len();
+  for line in lines {
+    offset += line.len();
+    let trimmed = line.trim_end();
+    if trimmed == "---" {
+      let coord = coordinate.ok_or("the front matter has no coordinate".to_string())?;
+      return Ok((coord, &text[offset..]));
+    }
+    if let Some(value) = trimmed.strip_prefix("coordinate:") {
+      let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+      coordinate = Some(query::to_coordinate(value)?);
+    }
+  }
+  return Err("unterminated front matter (expected a closing --- line)".to_string());
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn export
+///
+/// writes every populated scroll of `buffer` (limited to `range` when given) beneath `directory`, which must
+/// be empty or missing so that stale files can't be imported later. Returns the number of files written.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn export(buffer: &str, range: Option<phext::Range>, directory: &Path) -> Result<usize, String> {
+  if fs::read_dir(directory).is_ok_and(|mut entries| entries.next().is_some()) {
+    return Err(format!("{} already exists and is not empty", directory.display()));
+  }
+
+  let mut written = 0;
+  for scroll in subspace::scrolls(buffer) {
+    if range.is_some_and(|range| !subspace::in_range(scroll.coord, range)) {
+      continue;
+    }
+    let destination = directory.join(relative_path(scroll.coord));
+    if let Some(parent) = destination.parent() {
+      fs::create_dir_all(parent).map_err(|error| format!("unable to create {}: {}", parent.display(), error))?;
+    }
+    fs::write(&destination, format_scroll(scroll.coord, scroll.content))
+      .map_err(|error| format!("unable to write {}: {}", destination.display(), error))?;
+    written += 1;
+  }
+  return Ok(written);
+}
+
+fn scan(directory: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+  let entries = fs::read_dir(directory).map_err(|error| format!("unable to read {}: {}", directory.display(), error))?;
+  let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+  paths.sort();
+  for path in paths {
+    if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) {
+      continue;
+    }
+    if path.is_dir() {
+      scan(&path, files)?;
+    } else if path.extension().is_some_and(|extension| extension == "md") {
+      files.push(path);
+    }
+  }
+  return Ok(());
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn import
+///
+/// reads every Markdown file beneath `directory` and places it at the coordinate in its front matter. The
+/// imported scrolls replace the ones at the same coordinates in `base` (an empty file clears its scroll), and
+/// everything else in `base` is kept - pass an empty base to build a world from the folder alone.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn import(directory: &Path, base: &str) -> Result<String, String> {
+  let mut files = Vec::new();
+  scan(directory, &mut files)?;
+
+  let mut scrolls: BTreeMap<[usize; 9], String> = subspace::scrolls(base)
+    .map(|scroll| (query::dimensions(scroll.coord), scroll.content.to_string()))
+    .collect();
+  let mut sources: BTreeMap<[usize; 9], &Path> = BTreeMap::new();
+  for file in &files {
+    let text = fs::read_to_string(file).map_err(|error| format!("unable to read {}: {}", file.display(), error))?;
+    let (coord, content) = parse_scroll(&text).map_err(|error| format!("{}: {}", file.display(), error))?;
+    if archive::has_delimiters(content.as_bytes()) {
+      return Err(format!("{}: scrolls may not contain phext delimiters", file.display()));
+    }
+    let dimensions = query::dimensions(coord);
+    if let Some(previous) = sources.insert(dimensions, file) {
+      return Err(format!("{} and {} are both at {}", previous.display(), file.display(), coord));
+    }
+    match content.len() {
+      0 => scrolls.remove(&dimensions),
+      _ => scrolls.insert(dimensions, content.to_string()),
+    };
+  }
+
+  return Ok(subspace::assemble(phext::default_coordinate(), scrolls.iter().map(|(dimensions, content)| {
+    return subspace::Scroll { coord: archive::to_coordinate(dimensions), content: content.as_str(), offset: 0 };
+  })));
+}
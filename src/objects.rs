@@ -0,0 +1,608 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Object Storage
+///
+/// Keeps worlds in an S3-compatible bucket (AWS S3, MinIO, R2, ...), so that several servers behind a load
+/// balancer can share them without a shared disk. A world is stored either as a single object
+/// (`<prefix><world>.phext`), or as one object per book plus a manifest (`<prefix><world>/manifest.json`)
+/// that is replaced last, so that editing a large world only uploads the books that changed. Edits are
+/// conditional on the version they were based on: a writer that fell behind another one fails its write
+/// instead of silently discarding the other writer's changes.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use rocket::serde::json;
+use rocket::serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::storage::{Storage, StorageError, Version};
+use crate::{archive, document, query, subspace};
+
+/// the object holding a book-layout world's list of books
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// the object metadata recording when a world last changed (nanoseconds since the epoch)
+const MODIFIED_HEADER: &str = "x-amz-meta-modified";
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum Granularity
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Granularity {
+  World,
+  Book,
+}
+
+impl Granularity {
+  pub fn parse(name: &str) -> Result<Granularity, String> {
+    return match name {
+      "world" => Ok(Granularity::World),
+      "book" => Ok(Granularity::Book),
+      _ => Err(format!("Unknown object layout: {} (expected world or book)", name)),
+    };
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct ObjectConfig
+///
+/// Where worlds are kept, parsed from a URL like `s3://bucket/prefix?endpoint=http://localhost:9000&layout=book`.
+/// The endpoint and region default to AWS_ENDPOINT_URL and AWS_REGION (then AWS S3 in us-east-1).
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ObjectConfig {
+  pub endpoint: String,
+  pub bucket: String,
+  pub prefix: String,
+  pub region: String,
+  pub granularity: Granularity,
+}
+
+impl ObjectConfig {
+  pub fn parse(url: &str) -> Result<ObjectConfig, String> {
+    let rest = url.strip_prefix("s3://").ok_or(format!("Object storage URLs start with s3:// (got {})", url))?;
+    let (location, options) = rest.split_once('?').unwrap_or((rest, ""));
+    let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+    if bucket.is_empty() {
+      return Err(format!("Missing bucket name in {}", url));
+    }
+    let prefix = prefix.trim_matches('/');
+
+    let mut config = ObjectConfig {
+      endpoint: std::env::var("AWS_ENDPOINT_URL").unwrap_or_default(),
+      bucket: bucket.to_string(),
+      prefix: match prefix.len() {
+        0 => String::new(),
+        _ => prefix.to_string() + "/",
+      },
+      region: std::env::var("AWS_REGION").or(std::env::var("AWS_DEFAULT_REGION")).unwrap_or("us-east-1".to_string()),
+      granularity: Granularity::World,
+    };
+    for option in options.split('&').filter(|option| !option.is_empty()) {
+      let (name, value) = option.split_once('=').ok_or(format!("Invalid object storage option: {}", option))?;
+      match name {
+        "endpoint" => config.endpoint = value.to_string(),
+        "region" => config.region = value.to_string(),
+        "layout" => config.granularity = Granularity::parse(value)?,
+        _ => return Err(format!("Unknown object storage option: {} (expected endpoint, region or layout)", name)),
+      }
+    }
+    if config.endpoint.is_empty() {
+      config.endpoint = format!("https://s3.{}.amazonaws.com", config.region);
+    }
+    config.endpoint = config.endpoint.trim_end_matches('/').to_string();
+    return Ok(config);
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Credentials
+///
+/// An access key pair for signing requests (and the session token issued with temporary keys)
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Clone)]
+pub struct Credentials {
+  pub access_key: String,
+  pub secret_key: String,
+  pub session_token: Option<String>,
+}
+
+impl Credentials {
+  /// reads AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and AWS_SESSION_TOKEN - requests are sent unsigned without them
+  pub fn from_env() -> Option<Credentials> {
+    return Some(Credentials {
+      access_key: std::env::var("AWS_ACCESS_KEY_ID").ok()?,
+      secret_key: std::env::var("AWS_SECRET_ACCESS_KEY").ok()?,
+      session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+    });
+  }
+}
+
+impl std::fmt::Debug for Credentials {
+  fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    return write!(formatter, "Credentials {{ access_key: {:?}, .. }}", self.access_key);
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct SignedRequest
+///
+/// The parts of a request covered by an AWS Signature Version 4. The path and query must already be
+/// percent-encoded, and `headers` holds every signed header except host, with lowercase names.
+/// ----------------------------------------------------------------------------------------------------------
+pub struct SignedRequest<'a> {
+  pub method: &'a str,
+  pub host: &'a str,
+  pub path: &'a str,
+  pub query: &'a [(String, String)],
+  pub headers: &'a [(String, String)],
+  pub payload_hash: &'a str,
+  pub amz_date: &'a str,
+}
+
+fn hex(bytes: &[u8]) -> String {
+  return bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+}
+
+fn sha256(bytes: &[u8]) -> String {
+  return hex(&hmac_sha256::Hash::hash(bytes));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn authorization
+///
+/// the Authorization header for `request`, signed for S3 in `region`
+/// ----------------------------------------------------------------------------------------------------------
+pub fn authorization(request: &SignedRequest, credentials: &Credentials, region: &str) -> String {
+  let mut headers: Vec<(String, String)> = request.headers.to_vec();
+  headers.push(("host".to_string(), request.host.to_string()));
+  headers.sort();
+  let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value.trim())).collect();
+  let signed_headers = headers.iter().map(|(name, _)| name.as_str()).collect::<Vec<&str>>().join(";");
+  let mut query = request.query.to_vec();
+  query.sort();
+  let canonical_query = query.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<String>>().join("&");
+  let canonical = format!("{}\n{}\n{}\n{}\n{}\n{}",
+    request.method, request.path, canonical_query, canonical_headers, signed_headers, request.payload_hash);
+
+  let date = &request.amz_date[..8];
+  let scope = format!("{}/{}/s3/aws4_request", date, region);
+  let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", request.amz_date, scope, sha256(canonical.as_bytes()));
+  let mut key = hmac_sha256::HMAC::mac(date, format!("AWS4{}", credentials.secret_key));
+  for part in [region, "s3", "aws4_request"] {
+    key = hmac_sha256::HMAC::mac(part, key);
+  }
+  let signature = hex(&hmac_sha256::HMAC::mac(string_to_sign, key));
+  return format!("AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}", credentials.access_key, scope, signed_headers, signature);
+}
+
+/// formats `time` as an ISO 8601 basic timestamp in UTC (ex: 20130524T000000Z)
+fn amz_date(time: SystemTime) -> String {
+  let seconds = time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+  // civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+  let days = (seconds / 86400) as i64 + 719468;
+  let era = days.div_euclid(146097);
+  let day_of_era = days.rem_euclid(146097);
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+  let second_of_day = seconds % 86400;
+  return format!("{:04}{:02}{:02}T{:02}{:02}{:02}Z", year, month, day, second_of_day / 3600, second_of_day / 60 % 60, second_of_day % 60);
+}
+
+/// percent-encodes `text` as SigV4 requires, keeping slashes when encoding an object key
+fn encode(text: &str, keep_slashes: bool) -> String {
+  let mut encoded = String::new();
+  for byte in text.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+      b'/' if keep_slashes => encoded.push('/'),
+      _ => encoded += &format!("%{:02X}", byte),
+    }
+  }
+  return encoded;
+}
+
+fn xml_unescape(text: &str) -> String {
+  return text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&");
+}
+
+/// the text of every `<tag>` element in `xml`
+fn xml_values<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+  let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+  let mut values = Vec::new();
+  let mut rest = xml;
+  while let Some(start) = rest.find(&open) {
+    rest = &rest[start + open.len()..];
+    let Some(end) = rest.find(&close) else {
+      break;
+    };
+    values.push(&rest[..end]);
+    rest = &rest[end + close.len()..];
+  }
+  return values;
+}
+
+fn now_nanos() -> u64 {
+  return SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_nanos() as u64).unwrap_or(0);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Object
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug)]
+struct Object {
+  body: Vec<u8>,
+  etag: String,
+  modified: Option<SystemTime>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum Condition
+///
+/// The precondition on a write: none, the object still has the given ETag, or the object doesn't exist yet
+/// ----------------------------------------------------------------------------------------------------------
+enum Condition<'a> {
+  Always,
+  Matches(&'a str),
+  Absent,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Bucket
+///
+/// A minimal S3 client: path-style GET, HEAD, PUT, DELETE and ListObjectsV2 requests against one bucket
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug)]
+struct Bucket {
+  config: ObjectConfig,
+  credentials: Option<Credentials>,
+  agent: ureq::Agent,
+}
+
+impl Bucket {
+  fn send(&self, method: &str, key: &str, query: &[(String, String)], extra: &[(String, String)], body: &[u8]) -> Result<ureq::Response, Box<ureq::Error>> {
+    let path = match key.len() {
+      0 => format!("/{}", encode(&self.config.bucket, false)),
+      _ => format!("/{}/{}", encode(&self.config.bucket, false), encode(key, true)),
+    };
+    let mut url = self.config.endpoint.clone() + &path;
+    if !query.is_empty() {
+      url += "?";
+      url += &query.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<String>>().join("&");
+    }
+
+    let payload_hash = sha256(body);
+    let amz_date = amz_date(SystemTime::now());
+    let mut headers: Vec<(String, String)> = extra.to_vec();
+    headers.push(("x-amz-content-sha256".to_string(), payload_hash.clone()));
+    headers.push(("x-amz-date".to_string(), amz_date.clone()));
+    let mut request = self.agent.request(method, &url);
+    if let Some(credentials) = &self.credentials {
+      if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token".to_string(), token.clone()));
+      }
+      let host = self.config.endpoint.split("://").last().unwrap_or_default().split('/').next().unwrap_or_default();
+      let signed = SignedRequest { method, host, path: &path, query, headers: &headers, payload_hash: &payload_hash, amz_date: &amz_date };
+      request = request.set("Authorization", &authorization(&signed, credentials, &self.config.region));
+    }
+    for (name, value) in &headers {
+      request = request.set(name, value);
+    }
+    let result = match method {
+      "PUT" => request.send_bytes(body),
+      _ => request.call(),
+    };
+    return result.map_err(Box::new);
+  }
+
+  fn describe(&self, method: &str, key: &str, error: ureq::Error) -> StorageError {
+    let target = format!("{} s3://{}/{}", method, self.config.bucket, key);
+    return StorageError::Unavailable(match error {
+      ureq::Error::Status(status, response) => {
+        let body = response.into_string().unwrap_or_default();
+        format!("{} returned {}: {}", target, status, body.trim())
+      },
+      error => format!("{}: {}", target, error),
+    });
+  }
+
+  fn object(&self, key: &str, response: ureq::Response) -> Result<Object, StorageError> {
+    let etag = response.header("ETag").ok_or(StorageError::Unavailable(format!("s3://{}/{} has no ETag", self.config.bucket, key)))?.to_string();
+    let modified = response.header(MODIFIED_HEADER).and_then(|value| value.parse().ok()).map(|nanos| UNIX_EPOCH + Duration::from_nanos(nanos));
+    let mut body = Vec::new();
+    response.into_reader().read_to_end(&mut body)
+      .map_err(|error| StorageError::Unavailable(format!("unable to read s3://{}/{}: {}", self.config.bucket, key, error)))?;
+    return Ok(Object { body, etag, modified });
+  }
+
+  /// fetches `key` (or just its headers, for HEAD), returning None if it doesn't exist
+  fn get(&self, method: &str, key: &str) -> Result<Option<Object>, StorageError> {
+    return match self.send(method, key, &[], &[], &[]).map_err(|error| *error) {
+      Ok(response) => Ok(Some(self.object(key, response)?)),
+      Err(ureq::Error::Status(404, _)) => Ok(None),
+      Err(error) => Err(self.describe(method, key, error)),
+    };
+  }
+
+  /// stores `body` at `key`, returning the new ETag
+  fn put(&self, key: &str, body: &[u8], condition: Condition, modified: Option<u64>) -> Result<String, StorageError> {
+    let mut headers = Vec::new();
+    match condition {
+      Condition::Always => {},
+      Condition::Matches(etag) => headers.push(("if-match".to_string(), etag.to_string())),
+      Condition::Absent => headers.push(("if-none-match".to_string(), "*".to_string())),
+    }
+    if let Some(modified) = modified {
+      headers.push((MODIFIED_HEADER.to_string(), modified.to_string()));
+    }
+    return match self.send("PUT", key, &[], &headers, body).map_err(|error| *error) {
+      Ok(response) => Ok(response.header("ETag").unwrap_or_default().to_string()),
+      Err(ureq::Error::Status(409 | 412, _)) => {
+        Err(StorageError::Conflict(format!("s3://{}/{} was changed by another writer - reload and try again", self.config.bucket, key)))
+      },
+      Err(error) => Err(self.describe("PUT", key, error)),
+    };
+  }
+
+  fn delete(&self, key: &str) -> Result<(), StorageError> {
+    return match self.send("DELETE", key, &[], &[], &[]).map_err(|error| *error) {
+      Ok(_) | Err(ureq::Error::Status(404, _)) => Ok(()),
+      Err(error) => Err(self.describe("DELETE", key, error)),
+    };
+  }
+
+  /// lists every key beginning with `prefix`, following continuation tokens
+  fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+    let mut keys = Vec::new();
+    let mut token: Option<String> = None;
+    loop {
+      let mut query = vec![("list-type".to_string(), "2".to_string()), ("prefix".to_string(), encode(prefix, false))];
+      if let Some(token) = &token {
+        query.push(("continuation-token".to_string(), encode(token, false)));
+      }
+      query.sort();
+      let response = self.send("GET", "", &query, &[], &[]).map_err(|error| self.describe("LIST", prefix, *error))?;
+      let xml = response.into_string()
+        .map_err(|error| StorageError::Unavailable(format!("unable to list s3://{}/{}: {}", self.config.bucket, prefix, error)))?;
+      keys.extend(xml_values(&xml, "Key").into_iter().map(xml_unescape));
+      token = match xml_values(&xml, "IsTruncated").first() == Some(&"true") {
+        true => xml_values(&xml, "NextContinuationToken").first().map(|value| xml_unescape(value)),
+        false => None,
+      };
+      if token.is_none() {
+        return Ok(keys);
+      }
+    }
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct BookEntry
+///
+/// One book of a book-layout world: its coordinate (library.shelf.series/collection.volume.book), the object
+/// holding its scrolls, and that object's size and CRC-32
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct BookEntry {
+  book: String,
+  object: String,
+  bytes: usize,
+  crc32: u32,
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct BookManifest {
+  modified: u64,
+  books: Vec<BookEntry>,
+}
+
+fn book_label(book: &[usize; 6]) -> String {
+  return format!("{}.{}.{}/{}.{}.{}", book[0], book[1], book[2], book[3], book[4], book[5]);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn split_books
+///
+/// divides `buffer` into the buffers of its books, each starting from chapter 1, section 1, scroll 1. Keeps
+/// the empty scrolls `document::scrolls` says are needed, so `join_books` rebuilds the buffer exactly.
+/// ----------------------------------------------------------------------------------------------------------
+fn split_books(buffer: &str) -> Result<Vec<([usize; 6], String)>, String> {
+  let mut books: Vec<([usize; 6], Vec<subspace::Scroll>)> = Vec::new();
+  for scroll in document::scrolls(buffer)? {
+    let dimensions = query::dimensions(scroll.coord);
+    let book: [usize; 6] = dimensions[..6].try_into().expect("six dimensions");
+    if books.last().map(|(last, _)| *last != book).unwrap_or(true) {
+      books.push((book, Vec::new()));
+    }
+    let coord = archive::to_coordinate(&[1, 1, 1, 1, 1, 1, dimensions[6], dimensions[7], dimensions[8]]);
+    if let Some((_, scrolls)) = books.last_mut() {
+      scrolls.push(subspace::Scroll { coord, content: scroll.content, offset: 0 });
+    }
+  }
+  return Ok(books.into_iter().map(|(book, scrolls)| (book, subspace::assemble(phext::default_coordinate(), scrolls.into_iter()))).collect());
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn join_books
+///
+/// reverses `split_books`. A book with an empty buffer still holds its (empty) first scroll - it wouldn't
+/// have been stored otherwise.
+/// ----------------------------------------------------------------------------------------------------------
+fn join_books(books: &[([usize; 6], String)]) -> Result<String, String> {
+  let mut scrolls: Vec<subspace::Scroll> = Vec::new();
+  for (book, buffer) in books {
+    let local = document::scrolls(buffer)?;
+    let place = |coord: phext::Coordinate| {
+      let dimensions = query::dimensions(coord);
+      return archive::to_coordinate(&[book[0], book[1], book[2], book[3], book[4], book[5], dimensions[6], dimensions[7], dimensions[8]]);
+    };
+    if local.is_empty() {
+      scrolls.push(subspace::Scroll { coord: place(phext::default_coordinate()), content: "", offset: 0 });
+    }
+    for scroll in local {
+      scrolls.push(subspace::Scroll { coord: place(scroll.coord), content: scroll.content, offset: 0 });
+    }
+  }
+  return Ok(subspace::assemble(phext::default_coordinate(), scrolls.into_iter()));
+}
+
+fn parse_book(label: &str) -> Result<[usize; 6], String> {
+  let values: Vec<usize> = label.split(['.', '/']).filter_map(|value| value.parse().ok()).collect();
+  return values.try_into().map_err(|_| format!("Invalid book in manifest: {}", label));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct ObjectStorage
+///
+/// Worlds kept in an S3-compatible bucket. A world's version is the ETag of its head object (the world
+/// itself, or its manifest), and a store expecting a version sends it as an If-Match (or If-None-Match)
+/// condition, so the bucket refuses a write based on a stale read.
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug)]
+pub struct ObjectStorage {
+  bucket: Bucket,
+}
+
+impl ObjectStorage {
+  /// connects to the bucket described by `config`, signing requests with the credentials in the environment
+  pub fn open(config: ObjectConfig) -> ObjectStorage {
+    return ObjectStorage::with_credentials(config, Credentials::from_env());
+  }
+
+  pub fn with_credentials(config: ObjectConfig, credentials: Option<Credentials>) -> ObjectStorage {
+    return ObjectStorage { bucket: Bucket { config, credentials, agent: ureq::Agent::new() } };
+  }
+
+  /// the object that decides whether `world` exists and which version it is at
+  fn head_key(&self, world: &str) -> String {
+    let config = &self.bucket.config;
+    return match config.granularity {
+      Granularity::World => format!("{}{}.phext", config.prefix, world),
+      Granularity::Book => format!("{}{}/{}", config.prefix, world, MANIFEST_NAME),
+    };
+  }
+
+  fn conflict(&self, world: &str) -> StorageError {
+    return StorageError::Conflict(format!("s3://{}/{} was changed by another writer - reload and try again", self.bucket.config.bucket, self.head_key(world)));
+  }
+
+  /// writes the head object of `world`, provided `condition` still holds
+  fn commit(&self, world: &str, body: &[u8], condition: Condition, modified: u64) -> Result<(), StorageError> {
+    return self.bucket.put(&self.head_key(world), body, condition, Some(modified)).map(|_| ());
+  }
+
+  fn manifest(&self, world: &str, object: &Object) -> Result<BookManifest, StorageError> {
+    return json::from_slice(&object.body).map_err(|error| StorageError::Unavailable(format!("Invalid manifest for {}: {}", world, error)));
+  }
+}
+
+impl Storage for ObjectStorage {
+  fn load(&self, world: &str) -> Result<(Vec<u8>, Version), StorageError> {
+    let Some(head) = self.bucket.get("GET", &self.head_key(world))? else {
+      return Err(StorageError::NotFound(format!("Unable to find {}", world)));
+    };
+    let version = Version::Tag(head.etag.clone());
+    if self.bucket.config.granularity == Granularity::World {
+      return Ok((head.body, version));
+    }
+
+    let mut books = Vec::new();
+    for entry in self.manifest(world, &head)?.books {
+      let damaged = |reason: &str| StorageError::Unavailable(format!("{} is damaged: {} {}", world, entry.object, reason));
+      let object = self.bucket.get("GET", &entry.object)?.ok_or(damaged("is missing"))?;
+      if object.body.len() != entry.bytes || crc32fast::hash(&object.body) != entry.crc32 {
+        return Err(damaged("does not match its checksum"));
+      }
+      let buffer = String::from_utf8(object.body).map_err(|_| damaged("is not valid UTF-8"))?;
+      books.push((parse_book(&entry.book).map_err(StorageError::Unavailable)?, buffer));
+    }
+    return Ok((join_books(&books).map_err(StorageError::Unavailable)?.into_bytes(), version));
+  }
+
+  /// a book-layout world uploads its changed books first, then replaces the manifest that points at them,
+  /// and finally deletes the books the old manifest no longer shares with the new one. The manifest is
+  /// replaced on condition that it is still the one the books were compared against, whatever `expected` is.
+  fn store(&self, world: &str, buffer: &str, expected: &Version) -> Result<(), StorageError> {
+    let modified = now_nanos();
+    if self.bucket.config.granularity == Granularity::World {
+      document::scrolls(buffer).map_err(StorageError::Invalid)?;
+      let condition = match expected {
+        Version::Any => Condition::Always,
+        Version::Absent => Condition::Absent,
+        Version::Tag(etag) => Condition::Matches(etag),
+      };
+      return self.commit(world, buffer.as_bytes(), condition, modified);
+    }
+
+    let books = split_books(buffer).map_err(StorageError::Invalid)?;
+    let head = self.bucket.get("GET", &self.head_key(world))?;
+    let previous = match &head {
+      Some(object) => self.manifest(world, object)?,
+      None => BookManifest::default(),
+    };
+    let current = head.map(|object| object.etag);
+    let unchanged = match expected {
+      Version::Any => true,
+      Version::Absent => current.is_none(),
+      Version::Tag(etag) => current.as_ref() == Some(etag),
+    };
+    if !unchanged {
+      return Err(self.conflict(world));
+    }
+    let mut manifest = BookManifest { modified, books: Vec::new() };
+    for (book, buffer) in &books {
+      let label = book_label(book);
+      let (bytes, crc32) = (buffer.len(), crc32fast::hash(buffer.as_bytes()));
+      let unchanged = previous.books.iter().find(|entry| entry.book == label && entry.bytes == bytes && entry.crc32 == crc32);
+      let entry = match unchanged {
+        Some(entry) => entry.clone(),
+        None => {
+          let object = format!("{}{}/{}/{}-{:08x}.phext", self.bucket.config.prefix, world, label, modified, crc32);
+          self.bucket.put(&object, buffer.as_bytes(), Condition::Always, None)?;
+          BookEntry { book: label, object, bytes, crc32 }
+        },
+      };
+      manifest.books.push(entry);
+    }
+
+    let body = json::to_string(&manifest).map_err(|error| StorageError::Unavailable(format!("unable to serialize manifest: {}", error)))?;
+    let condition = match &current {
+      Some(etag) => Condition::Matches(etag),
+      None => Condition::Absent,
+    };
+    let committed = self.commit(world, body.as_bytes(), condition, modified);
+    let (live, dead) = match committed {
+      Ok(_) => (&manifest, &previous),
+      Err(_) => (&previous, &manifest),
+    };
+    for entry in &dead.books {
+      if !live.books.iter().any(|kept| kept.object == entry.object) {
+        let _ = self.bucket.delete(&entry.object);
+      }
+    }
+    return committed;
+  }
+
+  fn modified(&self, world: &str) -> Option<SystemTime> {
+    return self.bucket.get("HEAD", &self.head_key(world)).ok()??.modified;
+  }
+
+  fn worlds(&self) -> Result<Vec<String>, StorageError> {
+    let config = &self.bucket.config;
+    let suffix = match config.granularity {
+      Granularity::World => ".phext".to_string(),
+      Granularity::Book => format!("/{}", MANIFEST_NAME),
+    };
+    let mut worlds: Vec<String> = self.bucket.list(&config.prefix)?.iter()
+      .filter_map(|key| key.strip_prefix(&config.prefix)?.strip_suffix(&suffix))
+      .filter(|world| !world.is_empty() && !world.contains('/'))
+      .map(|world| world.to_string())
+      .collect();
+    worlds.sort();
+    return Ok(worlds);
+  }
+}
@@ -0,0 +1,169 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Coordinate Queries
+///
+/// Coordinate patterns extend the canonical address format (z3.z2.z1/y3.y2.y1/x3.x2.x1) so that each of the
+/// nine dimensions may be an exact value (3), an inclusive range (2-5), or a wildcard (*).
+///
+/// examples:
+///   1.1.1/*.1.1/3.*.1  - scroll 1 of every section in chapter 3, across every collection
+///   *.*.*/*.*.*/1.1.1  - the first scroll of every book
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use crate::subspace;
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum DimensionPattern
+///
+/// Matches a single dimension of a phext coordinate
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DimensionPattern {
+  Any,
+  Between(usize, usize),
+}
+
+impl DimensionPattern {
+  pub fn matches(&self, value: usize) -> bool {
+    return match self {
+      DimensionPattern::Any => true,
+      DimensionPattern::Between(low, high) => value >= *low && value <= *high,
+    };
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct CoordinatePattern
+///
+/// Nine dimension patterns, ordered from library (z3) down to scroll (x1)
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CoordinatePattern {
+  pub dimensions: [DimensionPattern; 9],
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn dimensions
+///
+/// flattens a coordinate into its nine dimensions, ordered from library down to scroll
+/// ----------------------------------------------------------------------------------------------------------
+pub fn dimensions(coord: phext::Coordinate) -> [usize; 9] {
+  return [coord.z.library, coord.z.shelf, coord.z.series,
+          coord.y.collection, coord.y.volume, coord.y.book,
+          coord.x.chapter, coord.x.section, coord.x.scroll];
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn parse_dimension
+///
+/// parses one dimension of a pattern: `*`, `n`, or `low-high`
+/// ----------------------------------------------------------------------------------------------------------
+fn parse_dimension(part: &str) -> Result<DimensionPattern, phext::PhextParseError> {
+  let part = part.trim();
+  let error: phext::PhextParseError = Default::default();
+  if part == "*" {
+    return Ok(DimensionPattern::Any);
+  }
+  if let Some((low, high)) = part.split_once('-') {
+    let low = low.trim().parse::<usize>().map_err(|_| error.clone())?;
+    let high = high.trim().parse::<usize>().map_err(|_| error.clone())?;
+    if low > high {
+      return Err(error);
+    }
+    return Ok(DimensionPattern::Between(low, high));
+  }
+  let value = part.parse::<usize>().map_err(|_| error)?;
+  return Ok(DimensionPattern::Between(value, value));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn to_pattern
+///
+/// translates a pattern string into a `CoordinatePattern`, accepting the same '.', '/', and ';' delimiters
+/// as `phext::to_coordinate`
+/// ----------------------------------------------------------------------------------------------------------
+pub fn to_pattern(address: &str) -> Result<CoordinatePattern, phext::PhextParseError> {
+  let parts = split_address(address);
+  if parts.len() != 9 {
+    return Err(Default::default());
+  }
+
+  return build_pattern(&parts);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn to_prefix
+///
+/// translates a partial address (such as `1.1.1/1.1.2`) into a pattern that matches everything beneath it
+/// ----------------------------------------------------------------------------------------------------------
+pub fn to_prefix(address: &str) -> Result<CoordinatePattern, phext::PhextParseError> {
+  let trimmed = address.trim_end_matches(is_address_break);
+  if trimmed.is_empty() {
+    return Ok(CoordinatePattern { dimensions: [DimensionPattern::Any; 9] });
+  }
+  let parts = split_address(trimmed);
+  if parts.len() > 9 {
+    return Err(Default::default());
+  }
+
+  return build_pattern(&parts);
+}
+
+fn is_address_break(c: char) -> bool {
+  let byte = c as u32;
+  return byte == phext::ADDRESS_MICRO_BREAK as u32 ||
+         byte == phext::ADDRESS_MACRO_BREAK as u32 ||
+         byte == phext::ADDRESS_MACRO_ALT as u32;
+}
+
+fn split_address(address: &str) -> Vec<&str> {
+  return address.split(is_address_break).collect();
+}
+
+fn build_pattern(parts: &[&str]) -> Result<CoordinatePattern, phext::PhextParseError> {
+  let mut result = CoordinatePattern { dimensions: [DimensionPattern::Any; 9] };
+  for (i, part) in parts.iter().enumerate() {
+    result.dimensions[i] = parse_dimension(part)?;
+  }
+
+  return Ok(result);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn to_coordinate
+///
+/// parses a single coordinate in canonical or URL form, rejecting wildcards, ranges and values outside of
+/// 1-99 (which `phext::to_coordinate` would silently accept)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn to_coordinate(address: &str) -> Result<phext::Coordinate, String> {
+  let invalid = format!("Invalid coordinate: {}", address);
+  let pattern = to_pattern(address).map_err(|_| invalid.clone())?;
+  let exact = pattern.dimensions.iter().all(|dimension| match dimension {
+    DimensionPattern::Between(low, high) => low == high && *low >= 1 && *low < phext::COORDINATE_MAXIMUM,
+    DimensionPattern::Any => false,
+  });
+  if !exact {
+    return Err(invalid);
+  }
+  return Ok(phext::to_coordinate(&address.replace(';', "/")));
+}
+
+impl CoordinatePattern {
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn matches
+  ///
+  /// determines if every dimension of `coord` satisfies this pattern
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn matches(&self, coord: phext::Coordinate) -> bool {
+    let values = dimensions(coord);
+    return self.dimensions.iter().zip(values.iter()).all(|(pattern, value)| pattern.matches(*value));
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn select
+///
+/// collects the scrolls in `buffer` that match `pattern`, in subspace order
+/// ----------------------------------------------------------------------------------------------------------
+pub fn select<'a>(buffer: &'a str, pattern: &CoordinatePattern) -> Vec<subspace::Scroll<'a>> {
+  return subspace::scrolls(buffer).filter(|scroll| pattern.matches(scroll.coord)).collect();
+}
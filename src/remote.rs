@@ -0,0 +1,224 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Phext REST Client
+///
+/// A typed client for the routes served by `phext serve`, so scripts don't need to build URLs and form bodies
+/// by hand. Coordinates may be given in canonical (`1.1.1/1.1.1/1.1.1`) or URL (`1.1.1;1.1.1;1.1.1`) form, and
+/// failed requests report the status and body returned by the server.
+/// ----------------------------------------------------------------------------------------------------------
+use crate::query;
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Remote
+///
+/// A connection to one phext server, identified by its base URL (ex: http://localhost:8000)
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct Remote {
+  base: String,
+  agent: ureq::Agent,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn url_coordinate
+///
+/// validates `coordinate` and converts it to the URL form used in routes (slashes become semi-colons)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn url_coordinate(coordinate: &str) -> Result<String, String> {
+  return Ok(query::to_coordinate(coordinate)?.to_string().replace('/', ";"));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn url_segment
+///
+/// percent-encodes a world name so that it occupies exactly one path segment
+/// ----------------------------------------------------------------------------------------------------------
+fn url_segment(segment: &str) -> Result<String, String> {
+  if segment.is_empty() {
+    return Err("World names may not be empty".to_string());
+  }
+  let mut encoded = String::new();
+  for byte in segment.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+      _ => encoded += &format!("%{:02X}", byte),
+    }
+  }
+  return Ok(encoded);
+}
+
+impl Remote {
+  pub fn new(base: &str) -> Remote {
+    return Remote { base: base.trim_end_matches('/').to_string(), agent: ureq::Agent::new() };
+  }
+
+  fn route(&self, action: &str, world: &str, rest: &[String]) -> Result<String, String> {
+    let mut url = format!("{}/api/v1/{}/{}", self.base, action, url_segment(world)?);
+    for part in rest {
+      url += "/";
+      url += part;
+    }
+    return Ok(url);
+  }
+
+  fn complete(url: &str, result: Result<ureq::Response, ureq::Error>) -> Result<String, String> {
+    return match result {
+      Ok(response) => response.into_string().map_err(|error| format!("{}: {}", url, error)),
+      Err(ureq::Error::Status(status, response)) => {
+        let body = response.into_string().unwrap_or_default();
+        Err(format!("{} returned {}: {}", url, status, body.trim()))
+      },
+      Err(error) => Err(format!("{}: {}", url, error)),
+    };
+  }
+
+  fn get(&self, url: &str) -> Result<String, String> {
+    return Remote::complete(url, self.agent.get(url).call());
+  }
+
+  fn post(&self, url: &str, content: Option<&str>) -> Result<String, String> {
+    let request = self.agent.post(url);
+    let result = match content {
+      Some(content) => request.send_form(&[("content", content)]),
+      None => request.call(),
+    };
+    return Remote::complete(url, result);
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn select
+  ///
+  /// fetches the scroll at `coordinate` within `world`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn select(&self, world: &str, coordinate: &str) -> Result<String, String> {
+    return self.get(&self.route("select", world, &[url_coordinate(coordinate)?])?);
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn select_phext
+  ///
+  /// fetches all of `world`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn select_phext(&self, world: &str) -> Result<String, String> {
+    return self.get(&self.route("select", world, &[])?);
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn insert
+  ///
+  /// appends `content` to the scroll at `coordinate`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn insert(&self, world: &str, coordinate: &str, content: &str) -> Result<(), String> {
+    self.post(&self.route("insert", world, &[url_coordinate(coordinate)?])?, Some(content))?;
+    return Ok(());
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn insert_phext
+  ///
+  /// appends `content` (a phext) to the end of `world`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn insert_phext(&self, world: &str, content: &str) -> Result<(), String> {
+    self.post(&self.route("insert", world, &[])?, Some(content))?;
+    return Ok(());
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn update
+  ///
+  /// replaces the scroll at `coordinate` with `content`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn update(&self, world: &str, coordinate: &str, content: &str) -> Result<(), String> {
+    self.post(&self.route("update", world, &[url_coordinate(coordinate)?])?, Some(content))?;
+    return Ok(());
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn update_phext
+  ///
+  /// replaces all of `world` with `content`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn update_phext(&self, world: &str, content: &str) -> Result<(), String> {
+    self.post(&self.route("update", world, &[])?, Some(content))?;
+    return Ok(());
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn delete
+  ///
+  /// removes the scroll at `coordinate`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn delete(&self, world: &str, coordinate: &str) -> Result<(), String> {
+    self.post(&self.route("delete", world, &[url_coordinate(coordinate)?])?, None)?;
+    return Ok(());
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn delete_phext
+  ///
+  /// zeroizes all of `world`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn delete_phext(&self, world: &str) -> Result<(), String> {
+    self.post(&self.route("delete", world, &[])?, None)?;
+    return Ok(());
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn replace
+  ///
+  /// replaces every scroll from `start` to `end` (inclusive) with `content`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn replace(&self, world: &str, start: &str, end: &str, content: &str) -> Result<(), String> {
+    self.post(&self.route("replace", world, &[url_coordinate(start)?, url_coordinate(end)?])?, Some(content))?;
+    return Ok(());
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn merge
+  ///
+  /// stores the zipper-merge of `mother` and `father` as `world`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn merge(&self, world: &str, mother: &str, father: &str) -> Result<(), String> {
+    self.get(&self.route("merge", world, &[url_segment(mother)?, url_segment(father)?])?)?;
+    return Ok(());
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn subtract
+  ///
+  /// prunes every coordinate populated in `other` from `world`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn subtract(&self, world: &str, other: &str) -> Result<(), String> {
+    self.get(&self.route("subtract", world, &[url_segment(other)?])?)?;
+    return Ok(());
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn normalize
+  ///
+  /// stores `content` as `world` with its empty scrolls pruned
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn normalize(&self, world: &str, content: &str) -> Result<(), String> {
+    self.post(&self.route("normalize", world, &[])?, Some(content))?;
+    return Ok(());
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn expand
+  ///
+  /// stores `content` as `world` with every delimiter raised by one dimension
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn expand(&self, world: &str, content: &str) -> Result<(), String> {
+    self.post(&self.route("expand", world, &[])?, Some(content))?;
+    return Ok(());
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn contract
+  ///
+  /// stores `content` as `world` with every delimiter lowered by one dimension
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn contract(&self, world: &str, content: &str) -> Result<(), String> {
+    self.post(&self.route("contract", world, &[])?, Some(content))?;
+    return Ok(());
+  }
+}
@@ -0,0 +1,127 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Find and Replace
+///
+/// Bulk search-and-replace over a phext buffer (or a range of it). Matching uses the same options as grep;
+/// replacements are applied within each scroll, so the coordinate layout of the buffer is never disturbed.
+/// ----------------------------------------------------------------------------------------------------------
+use regex::{Captures, Regex};
+use rocket::serde::Serialize;
+use crate::grep;
+use crate::subspace;
+
+/// number of characters of context shown on either side of a replacement
+const SNIPPET_CONTEXT: usize = 40;
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Change
+///
+/// Summarizes the replacements made within a single scroll, previewing the first one
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Change {
+  pub coordinate: String,
+  pub replacements: usize,
+  pub before: String,
+  pub after: String,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Rewrite
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone)]
+pub struct Rewrite {
+  pub buffer: String,
+  pub changes: Vec<Change>,
+}
+
+fn floor_boundary(text: &str, mut index: usize) -> usize {
+  while index > 0 && !text.is_char_boundary(index) {
+    index -= 1;
+  }
+  return index;
+}
+
+fn ceil_boundary(text: &str, mut index: usize) -> usize {
+  if index >= text.len() {
+    return text.len();
+  }
+  while !text.is_char_boundary(index) {
+    index += 1;
+  }
+  return index;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn expand_replacement
+///
+/// produces the text that replaces one match - capture groups ($1, ${name}) are expanded unless literal
+/// ----------------------------------------------------------------------------------------------------------
+fn expand_replacement(captures: &Captures, replacement: &str, literal: bool) -> String {
+  if literal {
+    return replacement.to_string();
+  }
+  let mut expanded = String::new();
+  captures.expand(replacement, &mut expanded);
+  return expanded;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn rewrite_scroll
+///
+/// applies every replacement within `content`, returning the new content along with a preview
+/// ----------------------------------------------------------------------------------------------------------
+fn rewrite_scroll(content: &str, regex: &Regex, replacement: &str, literal: bool) -> Option<(String, usize, String, String)> {
+  let mut count = 0;
+  let mut preview: Option<(String, String)> = None;
+  let mut output = String::new();
+  let mut last = 0;
+  for captures in regex.captures_iter(content) {
+    let found = captures.get(0).expect("regex match");
+    let expanded = expand_replacement(&captures, replacement, literal);
+    if preview.is_none() {
+      let start = floor_boundary(content, found.start().saturating_sub(SNIPPET_CONTEXT));
+      let end = ceil_boundary(content, found.end() + SNIPPET_CONTEXT);
+      let before = content[start..end].to_string();
+      let after = content[start..found.start()].to_string() + &expanded + &content[found.end()..end];
+      preview = Some((before, after));
+    }
+    output.push_str(&content[last..found.start()]);
+    output.push_str(&expanded);
+    last = found.end();
+    count += 1;
+  }
+  if count == 0 {
+    return None;
+  }
+  output.push_str(&content[last..]);
+
+  let (before, after) = preview.unwrap_or_default();
+  return Some((output, count, before, after));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn rewrite
+///
+/// replaces every match of `options.pattern` (optionally limited to options.start..options.end) with
+/// `replacement`, leaving all other bytes of `buffer` untouched
+/// ----------------------------------------------------------------------------------------------------------
+pub fn rewrite(buffer: &str, options: &grep::GrepOptions, regex: &Regex, replacement: &str) -> Rewrite {
+  let mut result = String::new();
+  let mut changes: Vec<Change> = Vec::new();
+  let mut last = 0;
+  for scroll in subspace::scrolls(buffer) {
+    if !grep::in_bounds(scroll.coord, options) {
+      continue;
+    }
+    if let Some((content, replacements, before, after)) = rewrite_scroll(scroll.content, regex, replacement, options.literal) {
+      result.push_str(&buffer[last..scroll.offset]);
+      result.push_str(&content);
+      last = scroll.offset + scroll.content.len();
+      changes.push(Change { coordinate: scroll.coord.to_string(), replacements, before, after });
+    }
+  }
+  result.push_str(&buffer[last..]);
+
+  return Rewrite { buffer: result, changes };
+}
@@ -0,0 +1,293 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Full-Text Search
+///
+/// Maintains an inverted index (term -> coordinate -> occurrences) for each world. Indexes are built the
+/// first time a world is searched, kept current by `refresh_if_indexed` whenever the server writes to that
+/// world, and brought up to date by `search` when the world changed some other way.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use rocket::serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Mutex;
+use crate::subspace;
+
+/// longest snippet (in characters) returned with a search hit
+const SNIPPET_LENGTH: usize = 160;
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct SearchHit
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct SearchHit {
+  pub world: String,
+  pub coordinate: String,
+  pub score: f64,
+  pub snippet: String,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Document
+///
+/// Where one indexed scroll sits in the buffer the index was built from, and a hash of its content
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Document {
+  offset: usize,
+  length: usize,
+  hash: u64,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct WorldIndex
+///
+/// The inverted index for a single world. Scrolls are kept as offsets into the buffer the index was built
+/// from (identified by `checksum`), so searches read content from that buffer rather than from a copy.
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Default)]
+pub struct WorldIndex {
+  checksum: Option<u64>,
+  documents: BTreeMap<String, Document>,
+  postings: HashMap<String, HashMap<String, usize>>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct SearchIndexes
+///
+/// The indexes for every world served from one data root, shared between requests
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Default)]
+pub struct SearchIndexes {
+  indexes: Mutex<BTreeMap<String, WorldIndex>>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn tokenize
+///
+/// splits text into lowercase alphanumeric terms
+/// ----------------------------------------------------------------------------------------------------------
+pub fn tokenize(text: &str) -> Vec<String> {
+  return text.split(|c: char| !c.is_alphanumeric())
+    .filter(|term| !term.is_empty())
+    .map(|term| term.to_lowercase())
+    .collect();
+}
+
+fn hash(text: &str) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  text.hash(&mut hasher);
+  return hasher.finish();
+}
+
+impl WorldIndex {
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn is_current
+  ///
+  /// determines if this index was built from `buffer`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn is_current(&self, buffer: &str) -> bool {
+    return self.checksum == Some(hash(buffer));
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn update
+  ///
+  /// re-indexes only the scrolls whose content differs from what was indexed previously, and moves the
+  /// offsets of the rest to where they sit in `buffer`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn update(&mut self, buffer: &str) {
+    let mut documents: BTreeMap<String, Document> = BTreeMap::new();
+    let mut changed: Vec<(String, &str)> = Vec::new();
+    for scroll in subspace::scrolls(buffer) {
+      let coordinate = scroll.coord.to_string();
+      let document = Document { offset: scroll.offset, length: scroll.content.len(), hash: hash(scroll.content) };
+      if self.documents.get(&coordinate).map(|indexed| indexed.hash) != Some(document.hash) {
+        changed.push((coordinate.clone(), scroll.content));
+      }
+      documents.insert(coordinate, document);
+    }
+
+    let stale: BTreeSet<&String> = self.documents.keys()
+      .filter(|coordinate| !documents.contains_key(*coordinate))
+      .chain(changed.iter().map(|(coordinate, _)| coordinate))
+      .collect();
+    if !stale.is_empty() {
+      self.postings.retain(|_, posting| {
+        posting.retain(|coordinate, _| !stale.contains(coordinate));
+        return !posting.is_empty();
+      });
+    }
+    for (coordinate, content) in &changed {
+      for term in tokenize(content) {
+        *self.postings.entry(term).or_default().entry(coordinate.clone()).or_insert(0) += 1;
+      }
+    }
+    self.documents = documents;
+    self.checksum = Some(hash(buffer));
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn search
+  ///
+  /// ranks scrolls by tf-idf over the query terms, favoring scrolls that contain the exact phrase. `buffer`
+  /// must be the buffer this index was last updated with.
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn search(&self, world: &str, buffer: &str, query: &str) -> Vec<SearchHit> {
+    let terms = tokenize(query);
+    let total = self.documents.len() as f64;
+    let mut scores: HashMap<&str, f64> = HashMap::new();
+    for term in &terms {
+      if let Some(posting) = self.postings.get(term) {
+        let idf = (1.0 + total / posting.len() as f64).ln();
+        for (coordinate, count) in posting {
+          *scores.entry(coordinate.as_str()).or_insert(0.0) += (*count as f64) * idf;
+        }
+      }
+    }
+
+    let phrase = query.trim().to_lowercase();
+    let wanted: BTreeSet<String> = terms.into_iter().collect();
+    let mut hits: Vec<SearchHit> = scores.into_iter().map(|(coordinate, mut score)| {
+      let document = self.documents[coordinate];
+      let content = &buffer[document.offset..document.offset + document.length];
+      if wanted.len() > 1 && content.to_lowercase().contains(&phrase) {
+        score *= 2.0;
+      }
+      return SearchHit { world: world.to_string(), coordinate: coordinate.to_string(), score, snippet: snippet(content, &wanted) };
+    }).collect();
+    sort_hits(&mut hits);
+
+    return hits;
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn sort_hits
+///
+/// orders hits by descending score, breaking ties by world and then coordinate
+/// ----------------------------------------------------------------------------------------------------------
+pub fn sort_hits(hits: &mut [SearchHit]) {
+  hits.sort_by(|left, right| {
+    return right.score.partial_cmp(&left.score).unwrap_or(std::cmp::Ordering::Equal)
+      .then_with(|| left.world.cmp(&right.world))
+      .then_with(|| phext::to_coordinate(&left.coordinate).partial_cmp(&phext::to_coordinate(&right.coordinate)).unwrap_or(std::cmp::Ordering::Equal));
+  });
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn highlight
+///
+/// html-escapes `text`, wrapping each occurrence of a wanted term in a <mark> tag
+/// ----------------------------------------------------------------------------------------------------------
+pub fn highlight(text: &str, wanted: &BTreeSet<String>) -> String {
+  let mut result = String::new();
+  let mut word = String::new();
+  let flush = |word: &mut String, result: &mut String| {
+    if word.is_empty() {
+      return;
+    }
+    if wanted.contains(&word.to_lowercase()) {
+      *result += &format!("<mark>{}</mark>", word);
+    } else {
+      *result += word;
+    }
+    word.clear();
+  };
+  for c in text.chars() {
+    if c.is_alphanumeric() {
+      word.push(c);
+      continue;
+    }
+    flush(&mut word, &mut result);
+    match c {
+      '&' => result += "&amp;",
+      '<' => result += "&lt;",
+      '>' => result += "&gt;",
+      '"' => result += "&quot;",
+      '\'' => result += "&#39;",
+      _ => result.push(c),
+    }
+  }
+  flush(&mut word, &mut result);
+
+  return result;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn first_match
+///
+/// finds the character position of the first wanted term within `line`
+/// ----------------------------------------------------------------------------------------------------------
+fn first_match(line: &str, wanted: &BTreeSet<String>) -> Option<usize> {
+  let mut word = String::new();
+  let mut start = 0;
+  for (i, c) in line.chars().chain(std::iter::once(' ')).enumerate() {
+    if c.is_alphanumeric() {
+      if word.is_empty() {
+        start = i;
+      }
+      word.push(c);
+      continue;
+    }
+    if !word.is_empty() && wanted.contains(&word.to_lowercase()) {
+      return Some(start);
+    }
+    word.clear();
+  }
+  return None;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn snippet
+///
+/// picks the first line of `content` that mentions a wanted term, trimmed to a window around that term
+/// ----------------------------------------------------------------------------------------------------------
+pub fn snippet(content: &str, wanted: &BTreeSet<String>) -> String {
+  for line in content.lines() {
+    let line = line.trim();
+    if let Some(position) = first_match(line, wanted) {
+      let start = position.saturating_sub(SNIPPET_LENGTH / 4);
+      let length = line.chars().count();
+      let excerpt: String = line.chars().skip(start).take(SNIPPET_LENGTH).collect();
+      let mut result = highlight(&excerpt, wanted);
+      if start > 0 {
+        result = "...".to_string() + &result;
+      }
+      if start + SNIPPET_LENGTH < length {
+        result += "...";
+      }
+      return result;
+    }
+  }
+
+  return String::new();
+}
+
+impl SearchIndexes {
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn refresh_if_indexed
+  ///
+  /// keeps an existing index current after a write, without forcing worlds nobody has searched to be indexed
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn refresh_if_indexed(&self, world: &str, buffer: &str) {
+    let mut indexes = self.indexes.lock().expect("search index poisoned");
+    if let Some(index) = indexes.get_mut(world) {
+      index.update(buffer);
+    }
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn search
+  ///
+  /// searches `world` as of `buffer`, first building its index or bringing it up to date with `buffer`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn search(&self, world: &str, buffer: &str, query: &str) -> Vec<SearchHit> {
+    let mut indexes = self.indexes.lock().expect("search index poisoned");
+    let index = indexes.entry(world.to_string()).or_default();
+    if !index.is_current(buffer) {
+      index.update(buffer);
+    }
+    return index.search(world, buffer, query);
+  }
+}
@@ -0,0 +1,2021 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Phext API Hosting
+///
+/// The hello-phext repository provides API access to Phext. Refer to README.md for a list of routes.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use rocket::{Build, Data, Request, Rocket, State};
+use rocket::data::{Limits, ToByteUnit};
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::http::ContentType;
+use rocket::form::Form;
+use rocket::response::{self, Responder, Response};
+use rocket::response::status::BadRequest;
+use rocket::response::stream::TextStream;
+use rocket::serde::Serialize;
+use rocket::serde::json;
+use crate::{archive, backup, compression, document, fsck, grep, query, rewrite, search, stats, storage, subspace, toc};
+use crate::compression::{Codec, Compression};
+use crate::storage::{Storage, StorageError, Version};
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct ServerConfig
+///
+/// Where the server finds its worlds and how they are stored (including which file worlds to keep
+/// compressed), which port it listens on (Rocket.toml / ROCKET_PORT when unset), and whether it accepts writes
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+  pub root: PathBuf,
+  pub storage: storage::Backend,
+  pub compression: Option<Compression>,
+  pub port: Option<u16>,
+  pub read_only: bool,
+}
+
+impl Default for ServerConfig {
+  fn default() -> ServerConfig {
+    return ServerConfig { root: PathBuf::from("."), storage: storage::Backend::File, compression: None, port: None, read_only: false };
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Library
+///
+/// The worlds stored in one data root, along with their search indexes - managed by Rocket and shared by
+/// every route
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug)]
+pub struct Library {
+  pub root: PathBuf,
+  pub read_only: bool,
+  storage: Arc<dyn Storage>,
+  indexes: search::SearchIndexes,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Writable
+///
+/// Request guard for routes that modify a world: fails with 403 Forbidden when the server is read-only
+/// ----------------------------------------------------------------------------------------------------------
+pub struct Writable;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Writable {
+  type Error = ();
+
+  async fn from_request(request: &'r Request<'_>) -> Outcome<Writable, ()> {
+    return match request.rocket().state::<Library>() {
+      Some(library) if !library.read_only => Outcome::Success(Writable),
+      _ => Outcome::Error((Status::Forbidden, ())),
+    };
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct AcceptEncoding
+///
+/// Request guard picking the Content-Encoding (zstd, gzip or none) to send a whole world with, from the
+/// request's Accept-Encoding headers
+/// ----------------------------------------------------------------------------------------------------------
+pub struct AcceptEncoding(pub Codec);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AcceptEncoding {
+  type Error = ();
+
+  async fn from_request(request: &'r Request<'_>) -> Outcome<AcceptEncoding, ()> {
+    let accepted: Vec<&str> = request.headers().get("Accept-Encoding").collect();
+    return Outcome::Success(AcceptEncoding(compression::negotiate(&accepted.join(","))));
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Encoded
+///
+/// A whole world as text, compressed with `codec` - sent with its Content-Encoding, and varying on
+/// Accept-Encoding so caches keep each encoding apart
+/// ----------------------------------------------------------------------------------------------------------
+pub struct Encoded {
+  codec: Codec,
+  body: Vec<u8>,
+}
+
+impl<'r> Responder<'r, 'static> for Encoded {
+  fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+    let mut response = Response::build_from((ContentType::Text, self.body).respond_to(request)?);
+    if let Some(encoding) = self.codec.content_encoding() {
+      response.raw_header("Content-Encoding", encoding);
+    }
+    return response.raw_header("Vary", "Accept-Encoding").ok();
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Subspace
+///
+/// Interface class for passing phext data from Rocket into our API endpoints
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Default, Debug, PartialEq, Eq, FromForm, Responder)]
+struct Subspace {
+    content: String,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct ScrollEntry
+///
+/// JSON representation of a single scroll and its coordinate
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+struct ScrollEntry {
+  coordinate: String,
+  content: String,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct FindReplace
+///
+/// Form parameters for a bulk find/replace - see `find_replace` below
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, FromForm)]
+struct FindReplace {
+  pattern: String,
+  replacement: String,
+  literal: Option<bool>,
+  ignore_case: Option<bool>,
+  start: Option<String>,
+  end: Option<String>,
+  apply: Option<bool>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct RewriteReport
+///
+/// JSON response for a find/replace preview (or the changes that were applied)
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+struct RewriteReport {
+  applied: bool,
+  scrolls: usize,
+  replacements: usize,
+  changes: Vec<rewrite::Change>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn css_rules
+///
+/// Generates CSS styles for injection within a CSS block of text
+/// ----------------------------------------------------------------------------------------------------------
+pub(crate) fn css_rules() -> String {
+  return "
+body {
+  background-color: #232323;
+  color: #efefff;
+  font-family: sans-serif;
+  margin: 0 auto;
+  width: 90%;
+  padding: 10px;
+  border: 4px solid grey;
+}
+
+textarea {
+  background-color: #47579a;
+  color: #fefefe;
+  font-weight: bold;
+  border: 1px solid white;
+  border-radius: 3px;
+  font-size: 1.4em;
+  margin: 10px;
+  width: 60%;
+}
+input {
+  margin: 10px;
+  width: 120px;
+  padding: 8px;
+  border-radius: 3px;
+  border: 2px solid white;
+  background-color: #47579a;
+  color: #fefefe;
+  font-weight: bold;
+}
+input:hover {
+  background-color: #9496a7;
+  cursor: pointer;
+}
+
+input.text {
+  border: 1px solid grey;
+  width: 150px;
+}
+input.text:hover {
+  cursor: auto;
+}
+
+a, a:visited {
+  color: #d0d0ff;
+  text-decoration: none;
+}
+a:hover, a:visited:hover {
+  color: #ffffff;
+  text-decoration: underline;
+}
+
+.navmap {
+  width: 450px;
+  float: left;
+  font-family: consolas, monospace;
+}
+.navmap ul li {
+}
+.actions {
+  position: absolute;
+  top: 10px;
+  right: 200px;
+}
+".to_string();
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn css_styling
+///
+/// Generates an inline HTML/CSS styling block with our preferred styles set.
+/// ----------------------------------------------------------------------------------------------------------
+fn css_styling() -> String {
+  return "<style type=\"text/css\" media=\"all\">".to_owned() + &css_rules() + "</style>";
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn more_cowbell
+///
+/// ensures that the cowbell character is supported
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/cowbell")]
+fn more_cowbell() -> (ContentType, String)
+{
+  let response = "<html><head><title>More Cowbell</title></head><body>Cowbell: \x07 (Hex = 0x07)</body></html>";
+  let passed = phext::check_for_cowbell(response);
+  if passed {
+    return (ContentType::HTML, response.to_string());
+  }
+
+  return (ContentType::HTML, "No cowbell!?".to_string());
+}
+
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn liquid
+///
+/// provides the liquid data visualizer
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/liquid/<world>/<coordinate>")]
+#[allow(clippy::op_ref, clippy::needless_borrow)]
+fn liquid(world: &str, coordinate: &str) -> (ContentType, String)
+{
+  let coordinate = coordinate.replace(";", "/");
+  let phext_coordinate = phext::to_coordinate(coordinate.as_str());
+  let library = phext_coordinate.z.library;
+  let shelf = phext_coordinate.z.shelf;
+  let series = phext_coordinate.z.series;
+  let collection = phext_coordinate.y.collection;
+  let volume = phext_coordinate.y.volume;
+  let book = phext_coordinate.y.book;
+  let chapter = phext_coordinate.x.chapter;
+  let seven_prefix = format!("{}.{}.{}/{}.{}.{}/{}", library, shelf, series, collection, volume, book, chapter);
+  let seven_prefix_url = format!("{}.{}.{};{}.{}.{};{}", library, shelf, series, collection, volume, book, chapter);
+  let color_ratio = 255.0/99.0;
+  let pr = ((library as f64)*color_ratio) as usize;
+  let pg = ((shelf as f64)*color_ratio) as usize;
+  let pb = ((series as f64)*color_ratio) as usize;
+  let sr = ((collection as f64)*color_ratio) as usize;
+  let sg = ((volume as f64)*color_ratio) as usize;
+  let sb = ((book as f64)*color_ratio) as usize;
+  let primary_color = format!("rgb({} {} {})", pr, pg, pb);
+  let secondary_color = format!("rgb({} {} {})", sr, sg, sb);
+
+  let css = "
+  body {
+    background: #101419;
+    color: white;
+    font-family: sans-serif;
+    background-color: ".to_string() + primary_color.as_str() + "
+  }
+  a, a:visited {
+    color: white;
+    font-size: 1.6em;
+  }
+  a.small, a.small:visited {
+    font-size: 1em;
+  }
+  .number {
+    font-weight: bold;
+    color: #8f8fd7;
+  }
+  .coordinates {
+    font-style: italic;
+    color: #8fef62;
+  }
+  input {
+    border: 2px solid white;
+    border-radius: 1px;
+    padding: 4px;
+    margin: 1px;
+    margin-bottom: 4px;
+  }
+  #city {
+    position: relative;
+    visibility: hidden;
+    top: 50px;
+    left: 10%;
+    width: 90%;    
+  }
+  #present {
+    position: absolute;
+    z-index: 4;
+    top: 100px;
+    left: -1000px;
+    width: 800px;
+    height: 400px;
+    font-size: 2em;
+    background: white;
+    border: 16px solid orange;
+    color: black;
+    padding: 20px;
+  }
+  .summary {
+    position: absolute;
+    top: -30px;
+  }
+  .outer {
+    background-color: #1B4079;
+    border-radius: 20px;
+    color: white;
+    font-weight: bold;
+    font-size: 1em;
+    z-index: 0;
+  }
+  .inner {
+    overflow: hidden;
+  }
+  .room {
+    width: 60px;
+    height: 48px;
+    color: #202030;
+    background-color: #B4C5E4;
+    border-radius: 3px;
+    z-index: 1;
+  }
+  .outer,
+  .room {
+    position: absolute;
+    border: 2px solid grey;
+    text-align: center;
+    vertical-align: center;
+    margin-bottom: 4px;
+    padding-top: 2px;
+    transition: all 0.4s;
+  }
+  .room:hover {
+    cursor: pointer;
+    background: whitesmoke;
+    color: black;
+    scale: 2;
+    width: 80px;
+    height: 40px;
+    padding-top: 10px;
+    z-index: 3;
+  }
+  .outer {
+    width: 670px;
+    height: 540px;
+    background-color: " + secondary_color.as_str() + "
+  }
+  #presentCloser {
+    text-decoration: underline; cursor: pointer;
+  }
+  #presentCloser:hover {
+    background-color: grey;
+  }
+  ";
+  let js = "
+  <script type=\"text/JavaScript\">
+
+  function dgid(id) {
+    return document.getElementById(id);
+  }
+  
+  var MAJOR_WIDTH = 720;
+  var MAJOR_HEIGHT = 600;
+  
+  function hide() {
+    var present = dgid(\"present\");
+    present.style.left = \"-2500px\";
+  }
+  
+  function show(cellColumn, cellRow, column, row, chapter, section, scroll) {
+    squeeze(cellColumn, cellRow, column, row);
+    var inner = getInner(cellColumn, cellRow, column, row);
+    if (inner) {
+      var computed_coordinate = '".to_string() + seven_prefix_url.as_str() + "' + '.' + section + '.' + scroll;
+      var request = '/api/v1/select/" + &world + "/' + computed_coordinate;
+      var xmlHttp = new XMLHttpRequest();
+      xmlHttp.open( \"GET\", request, false );
+      xmlHttp.send(null);      
+      inner.innerHTML = xmlHttp.responseText;
+      inner.innerHTML += \"<br /><a href='/api/v1/index/" + &world + "/\" + computed_coordinate + \"'>Edit</a>\";
+      inner.style.width = '240px';
+      inner.style.height = '160px';
+      inner.style.overflow = 'hidden';
+    }
+  }
+  
+  function randomInteger(limit) {
+    return Math.floor(Math.random() * (limit + 1));
+  }
+    
+  var loaderDelay = 100;
+  var tx = 0;
+  var ty = 0;
+  var city = false;
+  var zoom_ratio = 1.02;
+  
+  function setupCity() {
+    city = dgid(\"city\");
+    var output = \"\";
+    var section = 1;
+    var scroll = 1;
+    var chapter = 1;
+    var total = 0;
+    var left = 0;
+    var top = 0;
+    var ileft = 0;
+    var itop = 0;
+    for (var j = 1; j <= 11; ++j) {
+      for (var i = 1; i <= 11; ++i) {
+        left = (MAJOR_WIDTH * (i-1));
+        top = (MAJOR_HEIGHT * (j-1));
+        output += \"<div id='outer_\" + i + \"_\" + j + \"' class='outer' style='top: \" + top + \"px; left: \" + left + \"px;'>\" + chapter + \".\" + section + \".\" + scroll + \"\\n\";
+        for (var y = 1; y <= 9; ++y) {
+          for (var x = 1; x <= 9; ++x) {
+            ileft = 64 * x;
+            itop = 48 * y;
+            output += \"<div id='inner_\" + i + \"_\" + j + \"_\" + x + \"_\" + y + \"' class='room' style='position: absolute; top: \" + itop + \"px; left: \" + ileft + \"px;' onclick='show(\" + i + \", \" + j + \", \" + x + \",\" + y + \",\" + chapter + \",\" + section + \",\" + scroll + \");'>\" + scroll + \"</div>\\n\";
+            scroll += 1;
+            total += 1;
+            if (scroll > 99) {
+              scroll = 1;
+              section += 1;
+            }
+            if (section > 99) {
+              scroll = 1;
+              section = 1;
+              chapter += 1;
+            }
+          }
+        }
+        output += \"</div>\\n\";
+      }
+    }
+  
+    var summary = \"<div class='summary'>Rooms on this Block (" + seven_prefix.as_str() + ".*.*): \" + total + \" (\" + Math.round(100*2*total/1024)/100 + \" MB)</div><br />\\n\";
+    city.innerHTML = summary + output;
+
+    loadingAnimation();
+  }
+
+  function loadingAnimation() {
+    city.style.transition = 'all 0.1s';
+    city.style.scale = 0.001;
+    city.style.visibility = 'visible';
+    zoomIn();
+  }
+
+  function zoomIn(ratio) {
+    city.style.scale *= zoom_ratio;
+    if (city.style.scale < 1) {
+      setTimeout(zoomIn, 5);
+    } else {
+      city.style.scale = '';
+
+      finalOrientation();      
+    }
+  }
+
+  function slowScroll() {
+    --loaderDelay;
+    if (loaderDelay >= 0)
+    {
+      var ratio = (100-loaderDelay)/100;
+      window.scrollTo(ratio*tx, ratio*ty);
+      setTimeout(slowScroll, 20);
+    }
+  }
+
+  function finalOrientation() {
+    var selected = getPhextCell(\"" + &coordinate + "\");
+    if (selected) {
+      selected.style.zIndex = \"3\";
+      selected.style.border = \"3px solid yellow\";
+      setTimeout(() => {
+        selected.style.scale = \"5\";        
+      }, 2000);
+
+      var outer = getPhextOuterCell(\"" + &coordinate + "\");
+      if (outer) {
+        tx = parseInt(selected.style.left.replace('px', ''));
+        tx += parseInt(outer.style.left.replace('px', '')) - window.innerWidth/3;
+
+        ty = parseInt(selected.style.top.replace('px', ''));
+        ty += parseInt(outer.style.top.replace('px', '')) - window.innerHeight/4;
+
+        slowScroll();
+      }
+    }
+  }
+  
+  function getOuter(w, x) {
+    return dgid(\"outer_\" + w + \"_\" + x);
+  }
+  
+  function getInner(w, x, y, z) {
+    return dgid(\"inner_\" + w + \"_\" + x + \"_\" + y + \"_\" + z);
+  }
+
+  function phextCoordinateToGridCoordinate(coord, outer) {
+    var parts = coord.split('/');
+    var z = parts[0]; var y = parts[1]; var x = parts[2];
+    var zp = z.split('.'); var yp = y.split('.'); var xp = x.split('.');
+    var lb = zp[0]; var sf = zp[1]; var sr = zp[2];
+    var cn = yp[0]; var vm = yp[1]; var bk = yp[2];
+    var ch = xp[0]; var sn = xp[1]; var sc = xp[2];
+    var position = parseInt(99*(sn-1)) + parseInt(sc) - 1;
+    var blocks = Math.floor(position/81); var ox = Math.floor(blocks/11)+1; var ow = blocks%11+1;
+    var remainder = position%81; var oz = Math.floor(remainder/9)+1; var oy = remainder%9+1;
+    if (outer) {
+      return ow + \"_\" + ox;
+    }
+    return ow + \"_\" + ox + \"_\" + oy + \"_\" + oz;
+  }
+
+  function getPhextCell(coord) {
+    var id = 'inner_' + phextCoordinateToGridCoordinate(coord, false);
+    var handle = dgid(id);
+    if (handle) {
+      return handle;
+    }
+
+    return false;
+  }
+
+  function getPhextOuterCell(coord) {
+    var id = 'outer_' + phextCoordinateToGridCoordinate(coord, true);
+    var handle = dgid(id);
+    if (handle) {
+      return handle;
+    }
+
+    return false;
+  }
+  
+  function squeeze(w, x, y, z) {
+    var cell = getOuter(w, x);
+    var inner = getInner(w, x, y, z);
+    if (cell) {
+    }
+    if (inner && inner.style.scale.length == 0) {
+      inner.style.scale = \"4.0\";
+      inner.style.zIndex = \"3\";
+    } else if (inner) {
+      inner.style.scale = \"\";
+      inner.style.zIndex = \"3\";
+    }
+    for (var i = 1; i <= 9; ++i) {
+      for (var j = 1; j <= 9; ++j) {
+        var adjust = getInner(w, x, i, j);      
+        if (adjust && (adjust != inner) && adjust.style.scale.length == 0) {
+          adjust.style.scale = \"\";
+          adjust.style.width = \"\";
+          adjust.style.height = \"\";
+        }
+      }
+    }
+  }
+  
+  function cleanup(w, x) {
+    for (var y = 1; y <= 9; ++y) {
+      for (var z = 1; z <= 9; ++z) {
+        var cell = getInner(w, x, y, z);
+        if (cell) {
+          cell.style.scale = \"\";
+          cell.style.zIndex = \"\";
+        }      
+      }
+    }
+  
+    var cell = getOuter(w, x);
+    if (cell) {
+      cell.style.scale = \"\";
+    }
+  }
+  </script>
+  ";
+  let response = "<html>
+<head>
+<title>Liquid Metal</title>
+<style type='text/css' media='all'>".to_string() +
+css.as_str() + "
+</style>" +
+&js + "
+</head>
+<body onload=\"setupCity();\">
+  <a href=\"https://phext.io/white-rabbit.html?m=unlocked\">return to game</a>
+<div id=\"city\"></div>
+<div id=\"present\"></div>
+</body></html>";
+
+  return (ContentType::HTML, response);
+}
+
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn subtract
+///
+/// removes scrolls that have content in both archives from the first archive
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/subtract/<world>/<other>")]
+fn subtract(library: &State<Library>, _writable: Writable, world: &str, other: &str) -> Result<(ContentType, String), (Status, String)>
+{
+  let (left, version) = library.checkout(world)?;
+  let right = library.fetch(other)?;
+  let result = phext::subtract(left.as_str(), right.as_str());
+  library.save(world, &result, &version)?;
+
+  return Ok((ContentType::HTML, "OK".to_string()));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn merge
+///
+/// zipper merge for two phexts into one
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/merge/<world>/<mother>/<father>")]
+fn merge(library: &State<Library>, _writable: Writable, world: &str, mother: &str, father: &str) -> Result<(ContentType, String), (Status, String)>
+{
+  let left = library.fetch(mother)?;
+  let right = library.fetch(father)?;
+  let result = phext::merge(left.as_str(), right.as_str());
+  library.save(world, &result, &Version::Any)?;
+
+  return index(library, world, "1.1.1/1.1.1/1.1.1");
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn failure
+///
+/// the status a storage error is reported with: 400 for names and buffers the backend refuses, 404 for
+/// missing worlds, 409 when another writer got there first, and 503 when the backend itself failed
+/// ----------------------------------------------------------------------------------------------------------
+fn failure(error: StorageError) -> (Status, String) {
+  let status = match error {
+    StorageError::Invalid(_) => Status::BadRequest,
+    StorageError::NotFound(_) => Status::NotFound,
+    StorageError::Conflict(_) => Status::Conflict,
+    StorageError::Unavailable(_) => Status::ServiceUnavailable,
+  };
+  return (status, error.to_string());
+}
+
+impl Library {
+  pub fn new(root: PathBuf, storage: Box<dyn Storage>, read_only: bool) -> Library {
+    return Library { root, read_only, storage: Arc::from(storage), indexes: Default::default() };
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn storage
+  ///
+  /// The backend holding this library's worlds
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn storage(&self) -> &dyn Storage {
+    return self.storage.as_ref();
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn fetch
+  ///
+  /// Retrieves the content of the world specified by `world`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn fetch(&self, world: &str) -> Result<String, (Status, String)> {
+    return Ok(self.checkout(world)?.0);
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn checkout
+  ///
+  /// Retrieves the content of `world` along with its version, for an edit to pass back to `save`
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn checkout(&self, world: &str) -> Result<(String, Version), (Status, String)> {
+    let (bytes, version) = self.storage.load(world).map_err(failure)?;
+    let message = "Unable to read ".to_owned() + world + " as UTF-8 (see fsck)";
+    let buffer = String::from_utf8(bytes).map_err(|_| (Status::Conflict, message))?;
+    return Ok((buffer, version));
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn read
+  ///
+  /// Retrieves the raw bytes of the world specified by `world`, without requiring valid UTF-8
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn read(&self, world: &str) -> Result<Vec<u8>, (Status, String)> {
+    return Ok(self.storage.load(world).map_err(failure)?.0);
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn encoded
+  ///
+  /// Retrieves the world specified by `world` encoded with `codec`, straight from storage when it is already
+  /// kept that way
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn encoded(&self, world: &str, codec: Codec) -> Result<Vec<u8>, (Status, String)> {
+    return match codec {
+      Codec::Plain => Ok(self.fetch(world)?.into_bytes()),
+      _ => self.storage.load_encoded(world, codec).map_err(failure),
+    };
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn save
+  ///
+  /// Overwrites the world specified by `world` with `buffer`, keeping its search index current. Storage
+  /// backends replace worlds atomically, so readers never observe a partially-written world. Edits pass the
+  /// version `checkout` returned as `expected`, and get a 409 if another writer saved the world in between.
+  /// Routes that call this take a `Writable` guard, so read-only servers refuse them up front; the 403 here
+  /// is a backstop.
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn save(&self, world: &str, buffer: &str, expected: &Version) -> Result<(), (Status, String)> {
+    if self.read_only {
+      return Err((Status::Forbidden, format!("refusing to write {} on a read-only server", world)));
+    }
+    if !storage::is_world_name(world) {
+      return Err((Status::BadRequest, format!("Invalid world name: {}", world)));
+    }
+    self.storage.store(world, buffer, expected).map_err(failure)?;
+
+    self.indexes.refresh_if_indexed(world, buffer);
+    return Ok(());
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn modified
+  ///
+  /// Retrieves the last modification time of the world specified by `world`, if available
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn modified(&self, world: &str) -> Option<std::time::SystemTime> {
+    return self.storage.modified(world);
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn worlds
+  ///
+  /// Lists the names of every world in the library, sorted alphabetically
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn worlds(&self) -> Result<Vec<String>, (Status, String)> {
+    return self.storage.worlds().map_err(failure);
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn search
+  ///
+  /// searches `world`, first building (or updating) its index if the world changed outside of this server
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn search(&self, world: &str, q: &str) -> Result<Vec<search::SearchHit>, (Status, String)> {
+    let buffer = self.fetch(world)?;
+    return Ok(self.indexes.search(world, buffer.as_str(), q));
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn save (index)
+/// 
+/// This GET masquerades as a call to index, because users are likely to edit a save url to open a new scroll
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/save/<world>/<coordinate>")]
+fn save_index(library: &State<Library>, world: &str, coordinate: &str) -> Result<(ContentType, String), (Status, String)> {
+  return index(library, world, coordinate);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn raw
+///
+/// Provides a way to grab the entire phext buffer
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/raw/<world>")]
+fn raw(library: &State<Library>, accept: AcceptEncoding, world: &str) -> Result<Encoded, (Status, String)> {
+  return Ok(Encoded { codec: accept.0, body: library.encoded(world, accept.0)? });
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn edit_with_rindex
+/// @todo figure out a cleaner way to parse optional args to rocket...
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/edit/<world>/<coordinate>/<rindex>")]
+#[allow(clippy::needless_late_init, clippy::len_zero)]
+fn edit_with_rindex(library: &State<Library>, world: &str, coordinate: &str, rindex: &str) -> Result<(ContentType, String), (Status, String)> {
+  let buffer = library.fetch(world)?;
+  let coord = phext::to_coordinate(coordinate);
+  let scroll;
+  if coordinate.ends_with("-map") {
+    scroll = phext::textmap(buffer.as_str());
+  } else {
+    scroll = phext::fetch(&buffer, coord);
+  }
+  let coord_normalized = coordinate.replace(';', "/");
+
+  let mut rvalue = rindex.trim();
+  if rvalue.len() == 0 { rvalue = "8"; }
+  let mut lb_opt = ""; if rvalue == "0" { lb_opt = " selected"; }
+  let mut sf_opt = ""; if rvalue == "1" { sf_opt = " selected"; }
+  let mut sr_opt = ""; if rvalue == "2" { sr_opt = " selected"; }
+  let mut cn_opt = ""; if rvalue == "3" { cn_opt = " selected"; }
+  let mut vm_opt = ""; if rvalue == "4" { vm_opt = " selected"; }
+  let mut bk_opt = ""; if rvalue == "5" { bk_opt = " selected"; }
+  let mut ch_opt = ""; if rvalue == "6" { ch_opt = " selected"; }
+  let mut sn_opt = ""; if rvalue == "7" { sn_opt = " selected"; }
+  let mut sc_opt = ""; if rvalue == "8" { sc_opt = " selected"; }
+
+  let dimension_opts = format!("
+<option value='0'{}>Library</option>
+<option value='1'{}>Shelf</option>
+<option value='2'{}>Series</option>
+<option value='3'{}>Collection</option>
+<option value='4'{}>Volume</option>
+<option value='5'{}>Book</option>
+<option value='6'{}>Chapter</option>
+<option value='7'{}>Section</option>
+<option value='8'{}>Scroll</option>",
+lb_opt, sf_opt, sr_opt, cn_opt, vm_opt, bk_opt, ch_opt, sn_opt, sc_opt);
+
+  let response = format!("<html>
+<head>
+<title>Phext Box</title>
+{}
+<style>
+#address {{ width: 320px; height: 60px; padding: 10px; border: 2px solid grey; text-align: center; }}
+input, select {{ height: 60px; padding: 10px; font-size: 1.25em; }}
+#jump {{ width: 80px; }}
+</style>
+<script type='text/javascript'>
+function dgid(id) {{
+  return document.getElementById(id);
+}}
+var replace_index = '{}';
+function setDimension(value) {{
+  replace_index = value;
+  if (replace_index < 0) {{ replace_index = 0; }}
+  if (replace_index > 8) {{ replace_index = 8; }}
+}}
+function changeScroll(delta) {{
+  var address = dgid('address');
+  var parts = address.value.replaceAll('/', '.').split('.');
+  if (parts.length < 9) {{ return; }}
+  var last = parts[replace_index];
+  last = parseInt(last) + delta;
+  if (last < 1) {{
+    last = 1;
+  }}
+  if (last > 1000) {{
+    last = 1000;
+  }}
+  var result = '';
+  parts[replace_index] = last;
+
+  result  = parts[0] + '.' + parts[1] + '.' + parts[2] + '/';
+  result += parts[3] + '.' + parts[4] + '.' + parts[5] + '/';
+  result += parts[6] + '.' + parts[7] + '.' + parts[8];
+  
+  goto(result, replace_index);
+}}
+function prevScroll() {{
+  changeScroll(-1);
+}}
+function nextScroll() {{
+  changeScroll(1);
+}}
+function jump() {{
+  var address = dgid('address');
+  goto(address.value);
+}}
+function goto(address, rindex = '') {{
+  var urlAddress = address.replaceAll('/', ';');
+  var target = '/api/v1/edit/{}/' + urlAddress;  
+  if (rindex.length > 0) {{
+    target += '/' + rindex;
+  }}
+  window.location = target;
+}}
+function setFormSaveAction() {{
+  var saveForm = dgid('saveForm');
+  if (saveForm) {{
+    var address = dgid('address');
+    var urlAddress = address.value.replaceAll('/', ';');
+    saveForm.action = saveForm.action.replace('__coordinate__', urlAddress);
+    saveForm.submit();
+  }}
+}}
+</script>
+</head>
+<body>
+
+  <form method='POST' id='saveForm' action='/api/v1/save/{}/__coordinate__'>
+    <label for='address'>Coordinate: <input type='text' id='address' value='{}' /></label>
+    <input type='button' id='jump' value='GO' onclick='jump();' />
+    <input type='button' id='save' value='Save' onclick='setFormSaveAction();' />
+    <input type='button' id='prev' value='Prev' onclick='prevScroll();' />
+    <input type='button' id='next' value='Next' onclick='nextScroll();' />
+    <label for='dimension'>Break: <select id='dimension' onchange='setDimension(this.value);'>
+{}
+      </select></label>
+    <div>
+    <textarea name='content' rows='40' cols='160'>{}</textarea>
+  </form>
+</div>
+</body>
+</html>", css_styling(), rindex, world, world, coord_normalized, dimension_opts, scroll);
+  return Ok((ContentType::HTML, response));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn edit
+///
+/// Provides a node-focused editor for traversing subspace efficiently.
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/edit/<world>/<coordinate>")]
+fn edit(library: &State<Library>, world: &str, coordinate: &str) -> Result<(ContentType, String), (Status, String)> {
+  return edit_with_rindex(library, world, coordinate, "8");
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn homepage
+///
+/// Provides the replit instance (rust.phext.io) homepage
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/index.html")]
+fn homepage(library: &State<Library>) -> Result<(ContentType, String), (Status, String)> {
+  let mut phexts = String::new();
+  for phext_name in library.worlds()? {
+    phexts += &format!("<tr>
+    <td><a href='/api/v1/index/{}/1.1.1;1.1.1;1.1.1'>{}</a></td>
+    <td><a href='/api/v1/raw/{}'>Download</a>
+    </tr>", phext_name, phext_name, phext_name);
+  }
+
+  let response = "
+<html>
+  <head>
+    <title>Welcome, to the Exocortex</title>".to_owned() +
+    css_styling().as_str() + "
+    <script src=\"https://apis.google.com/js/api.js\"></script>
+  </head>
+  <body>
+  <h1>Welcome, to the Exocortex</h1>
+
+  <p>
+  <ul>
+    <li>What if ... you don't need a database?</li>
+    <li>What if ... you didn't need binary file formats?</li>
+    <li>What if ... you lived in the future?</li>
+  </ul>
+  </p>
+
+  <a href='/api/v1/index/world/1.1.1;1.1.1;1.1.1'>Start Here</a><hr />
+  <form method='GET' action='/api/v1/search'>
+    <input class='text' type='text' name='q' placeholder='Search every seed' />
+    <input type='hidden' name='format' value='html' />
+    <input type='submit' value='Search' />
+  </form>
+  <hr /><h1>Available seeds</h1><br />
+  <table>
+  <tr>
+    <th>Edit Seed</th>
+    <th>Download</th>
+  </tr>
+  " + &phexts + "
+  </table>
+  </body>
+  </html>
+  ";
+  return Ok((ContentType::HTML, response));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn html_escape
+///
+/// escapes scroll text for safe inclusion within generated HTML
+/// ----------------------------------------------------------------------------------------------------------
+pub(crate) fn html_escape(text: &str) -> String {
+  return text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&#39;");
+}
+
+/// number of scrolls listed in the `index` sidebar at once
+const TOC_PAGE_SIZE: usize = 100;
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn toc_sidebar
+///
+/// Renders the page of the table of contents that contains `coord`, with links to neighboring pages
+/// ----------------------------------------------------------------------------------------------------------
+fn toc_sidebar(world: &str, buffer: &str, coord: phext::Coordinate) -> String {
+  let scrolls: Vec<subspace::Scroll> = subspace::scrolls(buffer).collect();
+  let position = scrolls.iter().position(|scroll| scroll.coord >= coord).unwrap_or(scrolls.len().saturating_sub(1));
+  let start = (position / TOC_PAGE_SIZE) * TOC_PAGE_SIZE;
+  let end = std::cmp::min(start + TOC_PAGE_SIZE, scrolls.len());
+
+  let mut result = format!("Scrolls: {}<br />\n", scrolls.len());
+  if start > 0 {
+    let prev = scrolls[start - TOC_PAGE_SIZE].coord.to_urlencoded();
+    result += &format!("<a href=\"/api/v1/index/{}/{}\">&laquo; Previous {}</a><br />\n", world, prev, TOC_PAGE_SIZE);
+  }
+  result += "<ul>\n";
+  for scroll in &scrolls[start..end] {
+    let entry = toc::summarize(scroll);
+    let mut summary: String = entry.title.chars().take(32).collect();
+    if summary.len() < entry.title.len() {
+      summary += "...";
+    }
+    result += &format!("<li><a href=\"/api/v1/index/{}/{}\" title=\"{} bytes, {} lines\">{} {}</a></li>\n",
+      world, scroll.coord.to_urlencoded(), entry.bytes, entry.lines, entry.coordinate, html_escape(&summary));
+  }
+  result += "</ul>\n";
+  if end < scrolls.len() {
+    let next = scrolls[end].coord.to_urlencoded();
+    result += &format!("<a href=\"/api/v1/index/{}/{}\">Next {} &raquo;</a><br />\n", world, next, TOC_PAGE_SIZE);
+  }
+
+  return result;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn index
+///
+/// Provides our primary API endpoint for querying phext documents
+///
+/// @param world       the phext document to load (with the '.phext' extension)
+/// @param coordinate  the coordinate to render within `world`
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/index/<world>/<coordinate>")]
+#[allow(clippy::op_ref, clippy::needless_borrow)]
+fn index(library: &State<Library>, world: &str, coordinate: &str) -> Result<(ContentType, String), (Status, String)> {
+  let buffer = library.fetch(world)?;
+  let size = buffer.len();
+  let coord = phext::to_coordinate(coordinate);
+  let scroll = phext::fetch(&buffer, coord);
+  let navmap = toc_sidebar(world, buffer.as_str(), coord);
+
+  let coord = coordinate.replace(';', "/");
+  let response = "
+<html>
+  <head>
+    <title>Phext API Testing</title>".to_owned() +
+    css_styling().as_str() + "
+  <script type=\"text/javascript\">
+  function dgid(id) {
+    return document.getElementById(id);
+  }
+  function load_event() {
+    var se = dgid('scroll_editor');
+    if (se) {
+      se.focus();
+    }
+  }
+  function expand_scroll() {
+    var se = dgid('scroll_editor');
+    if (se) {
+      var es = dgid('expand_subspace');
+      if (es) {
+        es.value = se.value;
+      }
+    }
+  }
+  function contract_phext() {
+    var se = dgid('scroll_editor');
+    if (se) {
+      var cs = dgid('contract_subspace');
+      if (cs) {
+        cs.value = se.value;
+      }
+    }
+  }
+  function insert_phext() {
+    var se = dgid('scroll_editor');
+    var iss = dgid('insert_scroll_subspace');
+    if (iss) {
+      iss.value = se.value;
+    }
+  }
+  function update_phext() {
+    var se = dgid('scroll_editor');
+    var uss = dgid('update_scroll_subspace');
+    if (uss) {
+      uss.value = se.value;
+    }
+  }
+  function insert_phext() {
+    var se = dgid('scroll_editor');
+    var ips = dgid('insert_phext_subspace');
+    if (ips) {
+      ips.value = se.value;
+    }
+  }
+
+  function update_phext() {
+    var se = dgid('scroll_editor');
+    var ups = dgid('update_phext_subspace');
+    if (ups) {
+      ups.value = se.value;
+    }
+  }
+
+  function open_link() {
+    open_url('index');
+  }
+
+  function open_liquid() {
+    open_url('liquid');
+  }
+
+  function open_phext_box() {
+    open_url('edit');
+  }
+
+  function raw_phext() {
+    window.location = \"/api/v1/raw/" + &world + "\";
+  }
+  function world_stats() {
+    window.location = \"/api/v1/stats/" + &world + "\";
+  }
+
+  function open_url(action) {
+    var pc = dgid('phext_coordinate');
+    if (pc) {
+      var coordinate = pc.value.replaceAll('/', ';');
+      window.location = \"/api/v1/\" + action + \"/" + &world + "/\" + coordinate;
+    }
+  }
+
+  function subtract() {
+    var sf = dgid('subtract_form');
+    if (sf.action.endsWith('__other__')) {
+      var phext = prompt(\"Which phext to mask with?\");
+      sf.action = sf.action.replace('__other__', phext);
+    }
+  }
+  function merge() {
+    var mf = dgid('merge_form');
+    if (mf.action.endsWith('__mother__/__father__')) {
+      var mother = dgid('mother').value;
+      var father = dgid('father').value;
+      mf.action = mf.action.replace('__mother__', mother);
+      mf.action = mf.action.replace('__father__', father);
+    }
+  }
+  function replace() {
+    var rf = dgid('replace_form');
+    var rc = dgid('replace_content');
+    var se = dgid('scroll_editor');
+    if (rf.action.endsWith('__start__/__end__')) {
+      var start = dgid('start').value;
+      var end = dgid('end').value;
+      rf.action = rf.action.replace('__start__', start);
+      rf.action = rf.action.replace('__end__', end);
+      rc.value = se.value;
+    }
+  }
+  </script>
+  </head>
+  <body onLoad=\"load_event();\">
+    <div class='navmap'>Phext Viewer<br />
+    <form method='GET' action='/api/v1/search/" + &world + "'>
+      <input class='text' type='text' name='q' placeholder='Search " + &world + "' />
+      <input type='hidden' name='format' value='html' />
+      <input type='submit' value='Search' />
+    </form>
+    " + &world + " (" + &size.to_string() + " bytes):<br />
+    " + &navmap + "</div>
+    <div class='content'>
+      <form method='POST' action='/api/v1/save/" + &world + "/" + coordinate + "'>
+        Phext Coordinate: <input class='text' type='text' name='coordinate' id='phext_coordinate' value='" + &coord + "' />
+        <input type='submit' value='Save' />
+        <input type='button' value='Open' onclick='open_link();' />
+        <input type='button' value='Visualize' onclick='open_liquid();' />
+        <input type='button' value='Edit' onclick='open_phext_box();' />
+        <input type='button' value='Raw' onclick='raw_phext();' />
+        <input type='button' value='Stats' onclick='world_stats();' />
+        <input type='hidden' name='world' value='" + &world + "' />
+        <br />
+        <textarea id='scroll_editor' rows='50' name='content'>" + &scroll + "</textarea>
+      </form>
+
+      <div class='actions'>
+        <h2>Scroll Operations</h2>
+
+        <form method='POST' action='/api/v1/insert/" + &world + "/" + &coordinate + "'>
+          <input type='hidden' name='content' id='insert_scroll_subspace' value='' />
+          <input type='hidden' name='redirect' value='yes' />
+          <input type='submit' value='Insert Scroll' onclick='insert_scroll();' />
+        </form>
+
+        <form method='POST' action='/api/v1/update/" + &world + "/" + &coordinate + "'>
+          <input type='hidden' name='content' id='update_scroll_subspace' value='' />
+          <input type='hidden' name='redirect' value='yes' />
+          <input type='submit' value='Update Scroll' onclick='update_scroll();' />
+        </form>
+
+        <form method='POST' action='/api/v1/delete/" + &world + "/" + &coordinate + "'>
+          <input type='hidden' name='redirect' value='yes' />
+          <input type='submit' value='Delete Scroll' />
+        </form>
+
+        <h2>Phext Operations</h2>
+        <hr />
+
+        <form method='POST' action='/api/v1/expand/" + &world + "'>
+          <input type='hidden' name='content' id='expand_subspace' value='' />
+          <input type='hidden' name='redirect' value='yes' />
+          <input type='submit' value='Expand' onclick='expand_phext();' />
+        </form>
+
+        <form method='POST' action='/api/v1/contract/" + &world + "'>
+          <input type='hidden' name='content' id='contract_subspace' value='' />
+          <input type='hidden' name='redirect' value='yes' />
+          <input type='submit' value='Contract' onclick='contract_phext();' />
+        </form>
+
+        <form method='POST' action='/api/v1/insert/" + &world + "'>
+          <input type='hidden' name='content' id='insert_phext_subspace' value='' />
+          <input type='hidden' name='redirect' value='yes' />
+          <input type='submit' value='Insert' onclick='insert_phext();' />
+        </form>
+
+        <form method='POST' action='/api/v1/update/" + &world + "'>
+          <input type='hidden' name='content' id='update_phext_subspace' value='' />
+          <input type='hidden' name='redirect' value='yes' />
+          <input type='submit' value='Update' onclick='update_phext();' />
+        </form>
+
+        <form method='POST' action='/api/v1/delete/" + &world + "'>
+          <input type='hidden' name='redirect' value='yes' />
+          <input type='submit' value='Delete' />
+        </form>
+
+        <form method='GET' id='subtract_form' action='/api/v1/subtract/" + &world + "/__other__'>
+          <input type='hidden' name='redirect' value='yes' />
+          <input type='submit' value='Subtract' onclick='subtract();' />
+        </form>
+
+        <form method='GET' id='merge_form' action='/api/v1/merge/" + &world + "/__mother__/__father__'>
+          Mother: <input type='text' id='mother' /><br />
+          Father: <input type='text' id='father' /><br />
+          <input type='hidden' name='redirect' value='yes' />
+          <input type='submit' value='Merge' onclick='merge();' />
+        </form>
+
+        <form method='POST' id='replace_form' action='/api/v1/replace/" + &world + "/__start__/__end__'>          
+          Start Coordinate: <input type='text' id='start' value='1.1.1;1.1.1;1.1.1' /><br />
+          End Coordinate: <input type='text' id='end' value='1.1.1;1.1.1;1.1.1' /><br />
+          <input type='hidden' name='content' id='replace_content' />
+          <input type='hidden' name='redirect' value='yes' />
+          <input type='submit' value='Replace' onclick='replace();' />
+        </form>
+      </div>
+
+    </div>
+  </body>
+</html>";
+
+  return Ok((ContentType::HTML, response));
+}
+
+#[get("/favicon.ico")]
+fn favorite_icon() -> (ContentType, Vec<u8>) {
+  let favicon = include_bytes!("favicon.ico");
+  return (ContentType::Icon, favicon.to_vec());
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn select_scroll
+///
+/// retrieves just the raw scroll for a given phext coordinate
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/select/<world>/<coordinate>")]
+fn select_scroll(library: &State<Library>, world: &str, coordinate: &str) -> Result<(ContentType, String), (Status, String)> {
+  let buffer = library.fetch(world)?;
+  let coord = phext::to_coordinate(coordinate);
+  let scroll = phext::fetch(&buffer, coord);
+
+  return Ok((ContentType::Text, scroll));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn select_phext
+///
+/// retrieves the entire phext (copy/paste at scale!)
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/select/<world>")]
+fn select_phext(library: &State<Library>, accept: AcceptEncoding, world: &str) -> Result<Encoded, (Status, String)> {
+  return Ok(Encoded { codec: accept.0, body: library.encoded(world, accept.0)? });
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn insert_scroll
+///
+/// inserts a new scroll (or appends to the existing scroll) at the given coordinate
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/insert/<world>/<coordinate>", data="<scroll>")]
+fn insert_scroll(library: &State<Library>, _writable: Writable, world: &str, coordinate: &str, scroll: Form<Subspace>) -> Result<(ContentType, String), (Status, String)> {
+  let (prior, version) = library.checkout(world)?;
+
+  let message = phext::insert(prior, phext::to_coordinate(coordinate), scroll.content.as_str());
+  library.save(world, &message, &version)?;
+
+  return Ok((ContentType::Text, "OK".to_string()));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn insert_phext
+///
+/// inserts a new scroll (or appends to the existing scroll) at the given coordinate
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/insert/<world>", data="<phext>")]
+fn insert_phext(library: &State<Library>, _writable: Writable, world: &str, phext: Form<Subspace>) -> Result<(ContentType, String), (Status, String)> {
+  let (prior, version) = library.checkout(world)?;
+  let message = prior + &phext.content;
+  library.save(world, &message, &version)?;
+
+  return Ok((ContentType::Text, "OK".to_string()));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn update_scroll
+/// 
+/// replaces the contents of the specified scroll
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/update/<world>/<coordinate>", data="<scroll>")]
+fn update_scroll(library: &State<Library>, _writable: Writable, world: &str, coordinate: &str, scroll: Form<Subspace>) -> Result<(ContentType, String), (Status, String)> {
+  let (prior, version) = library.checkout(world)?;
+
+  let message = phext::replace(prior.as_str(), phext::to_coordinate(coordinate), scroll.content.as_str());
+  library.save(world, &message, &version)?;
+
+  return Ok((ContentType::Text, "OK".to_string()));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn update_phext
+/// 
+/// replaces the contents of the specified scroll
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/update/<world>", data="<phext>")]
+fn update_phext(library: &State<Library>, _writable: Writable, world: &str, phext: Form<Subspace>) -> Result<(ContentType, String), (Status, String)> {
+  library.save(world, &phext.content, &Version::Any)?;
+
+  return Ok((ContentType::Text, "OK".to_string()));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn delete_scroll
+/// 
+/// zeroes the length of the given scroll
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/delete/<world>/<coordinate>")]
+fn delete_scroll(library: &State<Library>, _writable: Writable, world: &str, coordinate: &str) -> Result<(ContentType, String), (Status, String)> {
+  let (prior, version) = library.checkout(world)?;
+
+  let coord = phext::to_coordinate(coordinate);
+  let message = phext::remove(prior.as_str(), coord);
+  library.save(world, &message, &version)?;
+
+  return Ok((ContentType::Text, "OK".to_string()));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn delete_phext
+/// 
+/// zeroes the length of the given scroll
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/delete/<world>")]
+fn delete_phext(library: &State<Library>, _writable: Writable, world: &str) -> Result<(ContentType, String), (Status, String)> {
+  let empty:Subspace = Subspace{ content: "".to_string() };
+  let nothing: Form<Subspace> = Form::from(empty);
+  return update_phext(library, _writable, world, nothing);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn save
+///
+/// Provides a POST API endpoint for accepting a phext scroll oriented at a specific coordinate
+///
+/// @param world       the phext document to save (not including the .phext extension)
+/// @param coordinate  the phext coordinates within `world` to edit
+/// @param scroll      content to replace at the given coordinates
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/save/<world>/<coordinate>", data="<scroll>")]
+fn save(library: &State<Library>, _writable: Writable, world: &str, coordinate: &str, scroll: Form<Subspace>) -> Result<(ContentType, String), (Status, String)> {
+  update_scroll(library, _writable, world, coordinate, scroll)?;
+
+  return index(library, world, coordinate);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn normalize
+///
+/// Provides phext normalization - trimming unused pockets of subspace from an input phext.
+/// WARNING: Overwrites the entire contents of `world`!
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/normalize/<world>", data="<scroll>")]
+fn normalize(library: &State<Library>, _writable: Writable, world: &str, scroll: Form<Subspace>) -> Result<(ContentType, String), (Status, String)> {
+  let message = phext::normalize(scroll.content.as_str());
+  library.save(world, &message, &Version::Any)?;
+
+  return index(library, world, "1.1.1/1.1.1/1.1.1");
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn contract
+///
+/// Provides phext contraction - transforms all dimension breaks down by 1
+/// WARNING: Overwrites the entire contents of `world`!
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/contract/<world>", data="<scroll>")]
+fn contract(library: &State<Library>, _writable: Writable, world: &str, scroll: Form<Subspace>) -> Result<(ContentType, String), (Status, String)> {
+  let message = phext::contract(scroll.content.as_str());
+  library.save(world, &message, &Version::Any)?;
+
+  return index(library, world, "1.1.1/1.1.1/1.1.1");
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn expand
+///
+/// Provides phext expansion - transforms all dimension breaks up by 1
+/// WARNING: Overwrites the entire contents of `world`!
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/expand/<world>", data="<scroll>")]
+fn expand(library: &State<Library>, _writable: Writable, world: &str, scroll: Form<Subspace>) -> Result<(ContentType, String), (Status, String)> {
+  let message = phext::expand(scroll.content.as_str());
+  library.save(world, &message, &Version::Any)?;
+
+  return index(library, world, "1.1.1/1.1.1/1.1.1");
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn range_replace
+///
+/// Inserts the content of `scroll`, overwriting all content from `start` to `end`
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/replace/<world>/<start>/<end>", data="<scroll>")]
+fn range_replace(library: &State<Library>, _writable: Writable, world: &str, start: &str, end: &str, scroll: Form<Subspace>) -> Result<(ContentType, String), (Status, String)> {
+  let range = phext::Range { start: phext::to_coordinate(start), end: phext::to_coordinate(end) };
+  let (prior, version) = library.checkout(world)?;
+
+  let message = phext::range_replace(prior.as_str(), range, scroll.content.as_str());
+  library.save(world, &message, &version)?;
+
+  return index(library, world, "1.1.1/1.1.1/1.1.1");
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn to_range
+///
+/// parses the `start` and `end` of a range route, answering 400 Bad Request for a malformed coordinate
+/// ----------------------------------------------------------------------------------------------------------
+fn to_range(start: &str, end: &str) -> Result<phext::Range, (Status, String)> {
+  let start = query::to_coordinate(start).map_err(|error| (Status::BadRequest, error))?;
+  let end = query::to_coordinate(end).map_err(|error| (Status::BadRequest, error))?;
+  return Ok(phext::Range { start, end });
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn select_range
+///
+/// retrieves every scroll from `start` to `end` as a standalone phext
+///
+/// @param rebase  when true, `start` is relocated to 1.1.1/1.1.1/1.1.1 in the output
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/select-range/<world>/<start>/<end>?<rebase>")]
+fn select_range(library: &State<Library>, world: &str, start: &str, end: &str, rebase: Option<bool>) -> Result<(ContentType, String), (Status, String)> {
+  let range = to_range(start, end)?;
+  let buffer = library.fetch(world)?;
+  let result = subspace::select_range(buffer.as_str(), range, rebase.unwrap_or(false));
+
+  return Ok((ContentType::Text, result));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn export_range
+///
+/// copies every scroll from `start` to `end` out of `world` into a new phext named `target`
+///
+/// @param overwrite  when true, replaces `target` if it already exists (otherwise that is a 409 Conflict)
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/export-range/<world>/<start>/<end>/<target>?<rebase>&<overwrite>")]
+#[allow(clippy::too_many_arguments)]
+fn export_range(library: &State<Library>, _writable: Writable, world: &str, start: &str, end: &str, target: &str, rebase: Option<bool>, overwrite: Option<bool>) -> Result<(ContentType, String), (Status, String)> {
+  let range = to_range(start, end)?;
+  let buffer = library.fetch(world)?;
+  let result = subspace::select_range(buffer.as_str(), range, rebase.unwrap_or(false));
+
+  let overwrite = overwrite.unwrap_or(false);
+  let expected = if overwrite { Version::Any } else { Version::Absent };
+  library.save(target, &result, &expected).map_err(|(status, message)| {
+    if status == Status::Conflict && !overwrite {
+      return (status, format!("{} already exists (pass overwrite=true to replace it)", target));
+    }
+    return (status, message);
+  })?;
+
+  return index(library, target, "1.1.1/1.1.1/1.1.1");
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn delete_range
+///
+/// removes every scroll from `start` to `end`, leaving the rest of `world` untouched
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/delete-range/<world>/<start>/<end>")]
+fn delete_range(library: &State<Library>, _writable: Writable, world: &str, start: &str, end: &str) -> Result<(ContentType, String), (Status, String)> {
+  let range = to_range(start, end)?;
+  let (prior, version) = library.checkout(world)?;
+
+  let message = subspace::delete_range(prior.as_str(), range);
+  library.save(world, &message, &version)?;
+
+  return Ok((ContentType::Text, "OK".to_string()));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn query_scrolls
+///
+/// retrieves every scroll whose coordinate matches `pattern` (see query.rs for the pattern syntax)
+///
+/// @param format  `json` (default) for a list of coordinates and content, or `phext` for a merged phext
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/query/<world>/<pattern>?<format>")]
+fn query_scrolls(library: &State<Library>, world: &str, pattern: &str, format: Option<&str>) -> Result<(ContentType, String), (Status, String)> {
+  let pattern = query::to_pattern(pattern).map_err(|error| (Status::BadRequest, error.to_string()))?;
+  let buffer = library.fetch(world)?;
+  let matches = query::select(buffer.as_str(), &pattern);
+
+  if format == Some("phext") {
+    return Ok((ContentType::Text, subspace::assemble(phext::default_coordinate(), matches.into_iter())));
+  }
+
+  let entries: Vec<ScrollEntry> = matches.iter().map(|scroll| ScrollEntry {
+    coordinate: scroll.coord.to_string(),
+    content: scroll.content.to_string(),
+  }).collect();
+  let response = json::to_string(&entries).expect("unable to serialize query results");
+
+  return Ok((ContentType::JSON, response));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn table_of_contents
+///
+/// lists every populated coordinate in `world` with its size, line count, title, and content hash
+///
+/// @param prefix  only include coordinates beneath this partial address (ex: 1.1.1;1.1.2)
+/// @param offset  the number of matching entries to skip
+/// @param limit   the maximum number of entries to return (defaults to 1000)
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/toc/<world>?<prefix>&<offset>&<limit>")]
+fn table_of_contents(library: &State<Library>, world: &str, prefix: Option<&str>, offset: Option<usize>, limit: Option<usize>) -> Result<(ContentType, String), (Status, String)> {
+  let prefix = query::to_prefix(prefix.unwrap_or("")).map_err(|error| (Status::BadRequest, error.to_string()))?;
+  let buffer = library.fetch(world)?;
+  let page = toc::page(buffer.as_str(), &prefix, offset.unwrap_or(0), limit.unwrap_or(1000));
+  let response = json::to_string(&page).expect("unable to serialize table of contents");
+
+  return Ok((ContentType::JSON, response));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn world_stats
+///
+/// reports size, occupancy, and delimiter statistics for `world`, along with any anomalies worth cleaning up
+///
+/// @param format  `html` (default) for a readable report, or `json`
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/stats/<world>?<format>")]
+fn world_stats(library: &State<Library>, world: &str, format: Option<&str>) -> Result<(ContentType, String), (Status, String)> {
+  let buffer = library.fetch(world)?;
+  let stats = stats::analyze(buffer.as_str());
+
+  if format == Some("json") {
+    let response = json::to_string(&stats).expect("unable to serialize world stats");
+    return Ok((ContentType::JSON, response));
+  }
+
+  let world = html_escape(world);
+  let mut delimiters = String::new();
+  for (name, count) in &stats.delimiters {
+    delimiters += &format!("<tr><td>{}</td><td>{}</td></tr>\n", name, count);
+  }
+
+  let mut distribution = String::new();
+  for level in &stats.distribution {
+    let busiest = level.nodes.iter().map(|node| node.scrolls).max().unwrap_or(0);
+    distribution += &format!("<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n", level.dimension, level.nodes.len(), busiest);
+  }
+
+  let mut largest = String::new();
+  for scroll in &stats.largest {
+    let url = scroll.coordinate.replace('/', ";");
+    largest += &format!("<tr><td><a href='/api/v1/index/{}/{}'>{}</a></td><td>{}</td></tr>\n", world, url, scroll.coordinate, scroll.bytes);
+  }
+
+  let mut anomalies = String::new();
+  for anomaly in &stats.anomalies {
+    let mut examples = String::new();
+    for coordinate in &anomaly.coordinates {
+      examples += &format!("<a href='/api/v1/index/{}/{}'>{}</a> ", world, coordinate.replace('/', ";"), coordinate);
+    }
+    anomalies += &format!("<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n", anomaly.description, anomaly.count, examples);
+  }
+  if stats.anomalies.is_empty() {
+    anomalies = "<tr><td colspan='3'>None found.</td></tr>\n".to_string();
+  }
+
+  let response = format!("<html>
+<head>
+<title>Phext Stats: {}</title>
+{}
+</head>
+<body>
+  <h1><a href='/api/v1/index/{}/1.1.1;1.1.1;1.1.1'>{}</a></h1>
+  <p>{} bytes, {} scrolls, {} empty scrolls (<a href='/api/v1/stats/{}?format=json'>JSON</a>)</p>
+  <h2>Anomalies</h2>
+  <table>
+  <tr><th>Description</th><th>Count</th><th>Found At</th></tr>
+  {}
+  </table>
+  <h2>Occupancy</h2>
+  <table>
+  <tr><th>Dimension</th><th>Populated</th><th>Most Scrolls</th></tr>
+  {}
+  </table>
+  <h2>Largest Scrolls</h2>
+  <table>
+  <tr><th>Coordinate</th><th>Bytes</th></tr>
+  {}
+  </table>
+  <h2>Delimiters</h2>
+  <table>
+  <tr><th>Dimension</th><th>Count</th></tr>
+  {}
+  </table>
+</body>
+</html>", world, css_styling(), world, world, stats.bytes, stats.scrolls, stats.empty_scrolls, world, anomalies, distribution, largest, delimiters);
+
+  return Ok((ContentType::HTML, response));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn validate_world
+///
+/// runs `fsck` against `world`, reporting invalid UTF-8, stray control characters, denormalized delimiters,
+/// out-of-range coordinates and manifest mismatches as JSON
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/fsck/<world>")]
+fn validate_world(library: &State<Library>, world: &str) -> Result<(ContentType, String), (Status, String)> {
+  let report = fsck::check(&library.read(world)?);
+  let response = json::to_string(&report).expect("unable to serialize fsck report");
+
+  return Ok((ContentType::JSON, response));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn export_json
+///
+/// converts `world` to a JSON document that round-trips exactly (see document.rs)
+///
+/// @param layout  `flat` (default) for an array of coordinates and content, or `nested` for an object keyed
+///                by dimension
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/json/<world>?<layout>")]
+fn export_json(library: &State<Library>, world: &str, layout: Option<&str>) -> Result<(ContentType, String), (Status, String)> {
+  let layout = document::Layout::parse(layout.unwrap_or("flat")).map_err(|error| (Status::BadRequest, error))?;
+  let buffer = library.fetch(world)?;
+  let response = document::export(buffer.as_str(), layout).map_err(|error| (Status::BadRequest, error))?;
+
+  return Ok((ContentType::JSON, response));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn import_json
+///
+/// replaces the contents of `world` with a JSON document in either layout
+/// WARNING: Overwrites the entire contents of `world`!
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/json/<world>", data="<document>")]
+fn import_json(library: &State<Library>, _writable: Writable, world: &str, document: json::Json<json::Value>) -> Result<(ContentType, String), (Status, String)> {
+  let buffer = document::import_value(document.into_inner()).map_err(|error| (Status::BadRequest, error))?;
+  library.save(world, &buffer, &Version::Any)?;
+
+  return Ok((ContentType::Text, "OK".to_string()));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn backup_worlds
+///
+/// packs worlds and the files kept beside them (history, snapshots) into a single archive
+///
+/// @param format  `tar` (default), `tar.gz` or `zip`
+/// @param worlds  a comma-separated list of worlds to include (every world by default)
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/backup?<format>&<worlds>")]
+fn backup_worlds(library: &State<Library>, format: Option<&str>, worlds: Option<&str>) -> Result<(ContentType, Vec<u8>), BadRequest<String>> {
+  let format = backup::Format::parse(format.unwrap_or("tar")).map_err(BadRequest)?;
+  let worlds: Vec<String> = worlds.unwrap_or("").split(',').filter(|world| !world.is_empty()).map(|world| world.to_string()).collect();
+  let (worlds, files) = backup::collect(&library.root, library.storage(), &worlds).map_err(BadRequest)?;
+  let archive = backup::write(&worlds, &files, format).map_err(BadRequest)?;
+  let content_type = match format {
+    backup::Format::Tar => ContentType::new("application", "x-tar"),
+    backup::Format::TarGz => ContentType::GZIP,
+    backup::Format::Zip => ContentType::ZIP,
+  };
+
+  return Ok((content_type, archive));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn restore_worlds
+///
+/// restores a backup (tar, tar.gz or zip - detected from its contents) into the data root. Files that
+/// already exist with different content are reported as a 409 conflict and nothing is written, unless
+/// `overwrite` is true. The upload limit is the `backup` limit in Rocket.toml (64 MiB by default).
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/restore?<overwrite>", data="<archive>")]
+async fn restore_worlds(library: &State<Library>, _writable: Writable, limits: &Limits, overwrite: Option<bool>, archive: Data<'_>) -> Result<(ContentType, String), (Status, String)> {
+  let limit = limits.get("backup").unwrap_or(64.mebibytes());
+  let upload = archive.open(limit).into_bytes().await.map_err(|error| (Status::BadRequest, error.to_string()))?;
+  if !upload.is_complete() {
+    return Err((Status::PayloadTooLarge, format!("Backups are limited to {}", limit)));
+  }
+  let (_, files) = backup::read(&upload).map_err(|error| (Status::BadRequest, error))?;
+  let conflicts = backup::conflicts(&library.root, library.storage(), &files);
+  if !conflicts.is_empty() && !overwrite.unwrap_or(false) {
+    return Err((Status::Conflict, format!("These files already exist with different content: {} (pass overwrite=true to replace them)", conflicts.join(", "))));
+  }
+  let restored = backup::restore(&library.root, library.storage(), &files).map_err(|error| (Status::InternalServerError, error))?;
+  let response = json::to_string(&restored).expect("unable to serialize restore report");
+
+  return Ok((ContentType::JSON, response));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn search_response
+///
+/// renders ranked search hits as JSON (the default) or as an HTML results page
+/// ----------------------------------------------------------------------------------------------------------
+fn search_response(scope: &str, q: &str, mut hits: Vec<search::SearchHit>, limit: Option<usize>, format: Option<&str>) -> (ContentType, String) {
+  hits.truncate(limit.unwrap_or(50));
+
+  if format != Some("html") {
+    let response = json::to_string(&hits).expect("unable to serialize search results");
+    return (ContentType::JSON, response);
+  }
+
+  let mut results = String::new();
+  for hit in &hits {
+    let url = hit.coordinate.replace('/', ";");
+    results += &format!("<li><a href='/api/v1/index/{}/{}'>{} {}</a> ({:.2})<br />{}</li>\n",
+      hit.world, url, hit.world, hit.coordinate, hit.score, hit.snippet);
+  }
+  if hits.is_empty() {
+    results = "<li>No matching scrolls.</li>\n".to_string();
+  }
+
+  let response = format!("<html>
+<head>
+<title>Phext Search: {}</title>
+{}
+</head>
+<body>
+  <h1>Search {}</h1>
+  <form method='GET'>
+    <input class='text' type='text' name='q' value='{}' />
+    <input type='hidden' name='format' value='html' />
+    <input type='submit' value='Search' />
+  </form>
+  <ul>
+  {}
+  </ul>
+</body>
+</html>", html_escape(q), css_styling(), scope, html_escape(q), results);
+
+  return (ContentType::HTML, response);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn search_world
+///
+/// ranks the scrolls in `world` against the query `q`, with highlighted snippets
+///
+/// @param limit   the maximum number of hits to return (defaults to 50)
+/// @param format  `json` (default) or `html`
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/search/<world>?<q>&<limit>&<format>")]
+fn search_world(library: &State<Library>, world: &str, q: Option<&str>, limit: Option<usize>, format: Option<&str>) -> Result<(ContentType, String), (Status, String)> {
+  let q = q.unwrap_or("");
+  let hits = library.search(world, q)?;
+
+  return Ok(search_response(world, q, hits, limit, format));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn search_all
+///
+/// ranks the scrolls of every world in the data root against the query `q`
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/search?<q>&<limit>&<format>")]
+fn search_all(library: &State<Library>, q: Option<&str>, limit: Option<usize>, format: Option<&str>) -> Result<(ContentType, String), (Status, String)> {
+  let q = q.unwrap_or("");
+  let mut hits = Vec::new();
+  for world in library.worlds()? {
+    hits.append(&mut library.search(&world, q)?);
+  }
+  search::sort_hits(&mut hits);
+
+  return Ok(search_response("all worlds", q, hits, limit, format));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn to_bound
+///
+/// parses an optional `start` or `end` coordinate, answering 400 Bad Request for a malformed one
+/// ----------------------------------------------------------------------------------------------------------
+fn to_bound(address: Option<&str>) -> Result<Option<phext::Coordinate>, (Status, String)> {
+  return address.map(query::to_coordinate).transpose().map_err(|error| (Status::BadRequest, error));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn grep_world
+///
+/// streams every line in `world` that matches `pattern`, formatted as coordinate:line:column:text
+///
+/// @param literal      treat `pattern` as plain text rather than a regular expression
+/// @param ignore_case  match without regard to case
+/// @param context      number of lines to print before and after each match
+/// @param start        skip scrolls before this coordinate
+/// @param end          skip scrolls after this coordinate
+/// ----------------------------------------------------------------------------------------------------------
+#[get("/api/v1/grep/<world>?<pattern>&<literal>&<ignore_case>&<context>&<start>&<end>")]
+#[allow(clippy::too_many_arguments)]
+fn grep_world(library: &State<Library>, world: &str, pattern: &str, literal: Option<bool>, ignore_case: Option<bool>, context: Option<usize>,
+              start: Option<&str>, end: Option<&str>) -> Result<TextStream![String], (Status, String)> {
+  let options = grep::GrepOptions {
+    pattern: pattern.to_string(),
+    literal: literal.unwrap_or(false),
+    ignore_case: ignore_case.unwrap_or(false),
+    context: context.unwrap_or(0),
+    start: to_bound(start)?,
+    end: to_bound(end)?,
+  };
+  let regex = grep::compile(&options).map_err(|error| (Status::BadRequest, error.to_string()))?;
+  let buffer = library.fetch(world)?;
+
+  return Ok(TextStream! {
+    for line in grep::grep(&buffer, &regex, &options) {
+      yield format!("{}\n", line);
+    }
+  });
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn find_replace
+///
+/// Replaces every match of `pattern` within `world` (or the `start`..`end` range of it) with `replacement`.
+/// Regex replacements may refer to capture groups ($1, ${name}). Nothing is written unless `apply` is true;
+/// otherwise the response previews every affected coordinate with before/after snippets.
+/// ----------------------------------------------------------------------------------------------------------
+#[post("/api/v1/find-replace/<world>", data="<request>")]
+fn find_replace(library: &State<Library>, writable: Option<Writable>, world: &str, request: Form<FindReplace>) -> Result<(ContentType, String), (Status, String)> {
+  if archive::has_delimiters(request.replacement.as_bytes()) {
+    return Err((Status::BadRequest, "Replacements may not contain phext delimiters".to_string()));
+  }
+  if request.apply.unwrap_or(false) && writable.is_none() {
+    return Err((Status::Forbidden, "This server is read-only".to_string()));
+  }
+  let options = grep::GrepOptions {
+    pattern: request.pattern.clone(),
+    literal: request.literal.unwrap_or(false),
+    ignore_case: request.ignore_case.unwrap_or(false),
+    context: 0,
+    start: to_bound(request.start.as_deref())?,
+    end: to_bound(request.end.as_deref())?,
+  };
+  let regex = grep::compile(&options).map_err(|error| (Status::BadRequest, error.to_string()))?;
+  let (prior, version) = library.checkout(world)?;
+
+  let result = rewrite::rewrite(prior.as_str(), &options, &regex, &request.replacement);
+  let applied = request.apply.unwrap_or(false) && !result.changes.is_empty();
+  if applied {
+    library.save(world, &result.buffer, &version)?;
+  }
+
+  let report = RewriteReport {
+    applied,
+    scrolls: result.changes.len(),
+    replacements: result.changes.iter().map(|change| change.replacements).sum(),
+    changes: result.changes,
+  };
+  let response = json::to_string(&report).expect("unable to serialize find/replace report");
+
+  return Ok((ContentType::JSON, response));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn not_found
+///
+/// Provides a specific error message for unrecognized URLs, instructing the user to reach out to us on twitter.
+/// ----------------------------------------------------------------------------------------------------------
+#[catch(404)]
+fn not_found(req: &Request) -> (ContentType, String) {
+  return match req.rocket().state::<Library>() {
+    Some(library) => homepage(library.into()).unwrap_or((ContentType::Text, "Not Found".to_string())),
+    None => (ContentType::Text, "Not Found".to_string()),
+  };
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn default
+///
+/// Handles generic errors - informs the user that an unexpected error has occurred
+/// ----------------------------------------------------------------------------------------------------------
+#[catch(default)]
+fn default(status: Status, req: &Request) -> String {
+    return format!("{} ({})", status, req.uri());
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn build_rocket
+///
+/// Builds a rocket instance serving the worlds found in `config.root`, registers default and 404 pages, and
+/// mounts our GET/POST endpoints. The port comes from Rocket.toml (or ROCKET_PORT) unless `config` sets one.
+/// The storage backend is opened when the rocket ignites, so a database that can't be opened stops launch.
+/// Worlds are compressed per `config.compression` as they are saved; existing worlds are swept at ignition,
+/// and again periodically when the policy waits for worlds to go idle.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn build_rocket(config: ServerConfig) -> Rocket<Build> {
+  let mut figment = rocket::Config::figment();
+  if let Some(port) = config.port {
+    figment = figment.merge(("port", port));
+  }
+  let idle = config.compression.and_then(|compression| compression.idle);
+
+  rocket::custom(figment)
+    .attach(AdHoc::try_on_ignite("Storage", move |rocket| async move {
+      let opened = storage::open_with(&config.storage, &config.root, config.compression);
+      return match opened.and_then(|storage| storage.sweep().map(|_| storage).map_err(String::from)) {
+        Ok(storage) => Ok(rocket.manage(Library::new(config.root, storage, config.read_only))),
+        Err(error) => {
+          eprintln!("{}", error);
+          Err(rocket)
+        },
+      };
+    }))
+    .attach(AdHoc::on_liftoff("Compression", move |rocket| Box::pin(async move {
+      let (Some(idle), Some(library)) = (idle, rocket.state::<Library>()) else {
+        return;
+      };
+      let storage = library.storage.clone();
+      let period = (idle / 2).clamp(Duration::from_secs(1), Duration::from_secs(3600));
+      rocket::tokio::spawn(async move {
+        loop {
+          rocket::tokio::time::sleep(period).await;
+          let storage = storage.clone();
+          if let Ok(Err(error)) = rocket::tokio::task::spawn_blocking(move || storage.sweep()).await {
+            eprintln!("{}", error);
+          }
+        }
+      });
+    })))
+    .register("/", catchers![not_found, default])
+    .mount("/", routes![select_scroll, select_phext,
+                        insert_scroll, insert_phext,
+                        update_scroll, update_phext,
+                        delete_scroll, delete_phext,
+                        edit, edit_with_rindex, raw,
+                        index, save, normalize, expand, contract,
+                        save_index, subtract, merge, range_replace,
+                        select_range, export_range, delete_range, query_scrolls,
+                        table_of_contents, world_stats, validate_world, search_world, search_all,
+                        grep_world, find_replace, export_json, import_json,
+                        backup_worlds, restore_worlds,
+                        favorite_icon, liquid, more_cowbell,
+                        homepage])
+}
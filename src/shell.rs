@@ -0,0 +1,322 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Phext Shell
+///
+/// An interactive shell that speaks the `.phext.history` command language (lp, ds, cs, quit, ...). Every
+/// command typed while a world is loaded is appended to `<world>.phext.history`, and a history file can be
+/// replayed later as a script. Edits are written back to the loaded world as soon as they are made.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use rustyline::error::ReadlineError;
+use std::fs;
+use std::io::Write;
+use crate::archive;
+use crate::subspace;
+use crate::toc;
+
+/// terminates a multi-line scroll typed after `es` or `is`
+const END_OF_SCROLL: &str = ".";
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum Outcome
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Outcome {
+  Continue,
+  Quit,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Shell
+///
+/// The loaded world (if any), its contents, and the coordinate the shell is pointed at
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug)]
+pub struct Shell {
+  pub file: Option<String>,
+  pub buffer: String,
+  pub coord: phext::Coordinate,
+}
+
+/// one line of help per command, in the order they are listed by `help`
+const HELP: [&str; 15] = [
+  "lp <file>         load phext (created on the first edit if it doesn't exist)",
+  "ds                display the current scroll",
+  "cs <coord>        change scroll",
+  "ns / ps           move to the next / previous populated scroll",
+  "ls [prefix]       list populated coordinates",
+  "es [text]         edit: replace the current scroll (with no text, reads lines until a lone '.')",
+  "is [text]         insert: append to the current scroll (with no text, reads lines until a lone '.')",
+  "rm                delete the current scroll",
+  "nz                normalize the loaded phext",
+  "ex                expand every delimiter by one dimension",
+  "ct                contract every delimiter by one dimension",
+  "pwd               print the loaded file and coordinate",
+  "help              print this list",
+  "quit              leave the shell",
+  "(long names: load, show, goto, next, prev, list, edit, insert, delete, normalize, expand, contract, exit)",
+];
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn history_path
+///
+/// names the history file kept alongside `file` (ex: world.phext -> world.phext.history)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn history_path(file: &str) -> String {
+  return file.to_owned() + ".history";
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn script_lines
+///
+/// splits a history file into commands - older history files separate sessions with phext delimiters
+/// ----------------------------------------------------------------------------------------------------------
+pub fn script_lines(script: &str) -> Vec<String> {
+  return script.split(|c: char| c.is_ascii() && phext::is_phext_break(c as u8))
+    .map(|line| line.trim_end_matches('\r').to_string())
+    .collect();
+}
+
+impl Default for Shell {
+  fn default() -> Shell {
+    return Shell::new();
+  }
+}
+
+impl Shell {
+  pub fn new() -> Shell {
+    return Shell { file: None, buffer: String::new(), coord: phext::default_coordinate() };
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn prompt
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn prompt(&self) -> String {
+    return match &self.file {
+      Some(file) => format!("{} {}> ", file, self.coord),
+      None => "phext> ".to_string(),
+    };
+  }
+
+  fn loaded(&self) -> Result<&str, String> {
+    return self.file.as_deref().ok_or("No phext loaded (use lp <file>)".to_string());
+  }
+
+  fn commit(&mut self, buffer: String) -> Result<(), String> {
+    let file = self.loaded()?.to_string();
+    crate::cli::save(&file, &buffer)?;
+    self.buffer = buffer;
+    return Ok(());
+  }
+
+  fn read_scroll(text: &str, next_line: &mut dyn FnMut() -> Option<String>) -> Result<String, String> {
+    let mut scroll = text.to_string();
+    if scroll.is_empty() {
+      let mut lines: Vec<String> = Vec::new();
+      loop {
+        match next_line() {
+          Some(line) if line == END_OF_SCROLL => break,
+          Some(line) => lines.push(line),
+          None => return Err(format!("Scroll ended without a closing '{}'", END_OF_SCROLL)),
+        }
+      }
+      scroll = lines.join("\n");
+    }
+    if archive::has_delimiters(scroll.as_bytes()) {
+      return Err("Scrolls may not contain phext delimiters".to_string());
+    }
+    return Ok(scroll);
+  }
+
+  fn populated(&self) -> Vec<phext::Coordinate> {
+    return subspace::scrolls(&self.buffer).map(|scroll| scroll.coord).collect();
+  }
+
+  /// --------------------------------------------------------------------------------------------------------
+  /// @fn execute
+  ///
+  /// runs a single command, writing its output to `out`. Commands that accept a multi-line scroll pull the
+  /// extra lines from `next_line`.
+  /// --------------------------------------------------------------------------------------------------------
+  pub fn execute(&mut self, line: &str, next_line: &mut dyn FnMut() -> Option<String>, out: &mut dyn Write) -> Result<Outcome, String> {
+    let line = line.trim();
+    let (command, argument) = match line.split_once(char::is_whitespace) {
+      Some((command, argument)) => (command, argument.trim()),
+      None => (line, ""),
+    };
+    let mut output = String::new();
+
+    match command {
+      "" => {},
+      "quit" | "exit" => return Ok(Outcome::Quit),
+      "help" => output = HELP.join("\n") + "\n",
+      "lp" | "load" => {
+        if argument.is_empty() {
+          return Err("Usage: lp <file>".to_string());
+        }
+        let mut file = argument.to_string();
+        if !file.ends_with(".phext") && !crate::cli::exists(&file) {
+          file += ".phext";
+        }
+        self.buffer = match crate::cli::exists(&file) {
+          true => crate::cli::load(&file)?,
+          false => String::new(),
+        };
+        self.file = Some(file);
+        self.coord = phext::default_coordinate();
+      },
+      "ds" | "show" => {
+        self.loaded()?;
+        output = subspace::fetch(&self.buffer, self.coord).to_string();
+        if !output.ends_with('\n') {
+          output.push('\n');
+        }
+      },
+      "cs" | "goto" => self.coord = crate::query::to_coordinate(argument)?,
+      "ns" | "next" | "ps" | "prev" => {
+        self.loaded()?;
+        let populated = self.populated();
+        let target = match command {
+          "ns" | "next" => populated.into_iter().find(|coord| *coord > self.coord),
+          _ => populated.into_iter().rev().find(|coord| *coord < self.coord),
+        };
+        self.coord = target.ok_or("No more scrolls in that direction".to_string())?;
+        output = format!("{}\n", self.coord);
+      },
+      "ls" | "list" => {
+        self.loaded()?;
+        let prefix = crate::query::to_prefix(argument).map_err(|_| format!("Invalid prefix: {}", argument))?;
+        for scroll in subspace::scrolls(&self.buffer).filter(|scroll| prefix.matches(scroll.coord)) {
+          output += &format!("{}\t{}\t{}\n", scroll.coord, scroll.content.len(), toc::title(scroll.content));
+        }
+      },
+      "pwd" => output = format!("{} {}\n", self.loaded()?, self.coord),
+      "es" | "edit" | "is" | "insert" => {
+        self.loaded()?;
+        let scroll = Shell::read_scroll(argument, next_line)?;
+        let updated = match command {
+          "es" | "edit" => subspace::replace(&self.buffer, self.coord, &scroll),
+          _ => subspace::replace(&self.buffer, self.coord, &(subspace::fetch(&self.buffer, self.coord).to_string() + &scroll)),
+        };
+        self.commit(updated)?;
+      },
+      "rm" | "delete" => {
+        self.loaded()?;
+        let updated = subspace::replace(&self.buffer, self.coord, "");
+        self.commit(updated)?;
+      },
+      "nz" | "normalize" | "ex" | "expand" | "ct" | "contract" => {
+        self.loaded()?;
+        let updated = match command {
+          "nz" | "normalize" => phext::normalize(&self.buffer),
+          "ex" | "expand" => phext::expand(&self.buffer),
+          _ => phext::contract(&self.buffer),
+        };
+        self.commit(updated)?;
+      },
+      _ => return Err(format!("Unknown command: {} (try help)", command)),
+    }
+
+    out.write_all(output.as_bytes()).map_err(|error| error.to_string())?;
+    return Ok(Outcome::Continue);
+  }
+}
+
+fn append_history(shell: &Shell, lines: &[String]) {
+  if let Some(file) = &shell.file {
+    let history = fs::OpenOptions::new().create(true).append(true).open(history_path(file));
+    if let Ok(mut history) = history {
+      let _result = history.write_all((lines.join("\n") + "\n").as_bytes());
+    }
+  }
+}
+
+fn load_history(editor: &mut rustyline::DefaultEditor, file: &str) {
+  if let Ok(history) = fs::read_to_string(history_path(file)) {
+    for line in script_lines(&history).iter().filter(|line| !line.trim().is_empty()) {
+      let _result = editor.add_history_entry(line.as_str());
+    }
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn run_interactive
+///
+/// reads commands with line editing until `quit` (or end of input), recording each successful command in
+/// the history of the world that is loaded once it completes (so that the history replays cleanly)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn run_interactive(initial: Option<&str>) -> Result<(), String> {
+  let mut editor = rustyline::DefaultEditor::new().map_err(|error| error.to_string())?;
+  let mut shell = Shell::new();
+  let mut stdout = std::io::stdout();
+
+  if let Some(file) = initial {
+    shell.execute(&format!("lp {}", file), &mut || None, &mut stdout)?;
+    load_history(&mut editor, shell.loaded()?);
+  }
+
+  loop {
+    let line = match editor.readline(&shell.prompt()) {
+      Ok(line) => line,
+      Err(ReadlineError::Interrupted) => continue,
+      Err(ReadlineError::Eof) => break,
+      Err(error) => return Err(error.to_string()),
+    };
+    if line.trim().is_empty() {
+      continue;
+    }
+    let _result = editor.add_history_entry(line.as_str());
+
+    let previous = shell.file.clone();
+    let mut consumed = vec![line.clone()];
+    let mut next_line = || {
+      let line = editor.readline("... ").ok()?;
+      consumed.push(line.clone());
+      return Some(line);
+    };
+    let outcome = shell.execute(&line, &mut next_line, &mut stdout);
+    if shell.file != previous {
+      if let Some(file) = shell.file.clone() {
+        load_history(&mut editor, &file);
+      }
+    }
+    if outcome.is_ok() {
+      append_history(&shell, &consumed);
+    }
+
+    match outcome {
+      Ok(Outcome::Quit) => break,
+      Ok(Outcome::Continue) => {},
+      Err(message) => eprintln!("{}", message),
+    }
+  }
+
+  return Ok(());
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn run_script
+///
+/// replays the commands in `script` (ex: a .phext.history file) non-interactively, stopping at the first
+/// command that fails. History files hold one session after another, so `quit` ends the current session
+/// and the next line starts a fresh one.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn run_script(script: &str, out: &mut dyn Write) -> Result<(), String> {
+  let lines = script_lines(script);
+  let mut shell = Shell::new();
+  let mut position = 0;
+  while position < lines.len() {
+    let line = lines[position].clone();
+    position += 1;
+    let mut next_line = || {
+      let line = lines.get(position).cloned();
+      position += 1;
+      return line;
+    };
+    let outcome = shell.execute(&line, &mut next_line, out).map_err(|error| format!("{}: {}", line.trim(), error))?;
+    if outcome == Outcome::Quit {
+      shell = Shell::new();
+    }
+  }
+
+  return Ok(());
+}
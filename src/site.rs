@@ -0,0 +1,233 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Static Site Export
+///
+/// Renders a world as a self-contained static HTML site that any file server can host: one page per
+/// populated scroll (with prev/next links along every dimension), an index page holding the table of
+/// contents, and a search index that the index page queries in the browser. Every link is relative.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use rocket::serde::json;
+use rocket::serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use crate::server::{css_rules, html_escape};
+use crate::{query, search, stats, subspace, toc};
+
+/// styles added to the server's stylesheet for generated pages
+const SITE_CSS: &str = "
+pre.scroll {
+  white-space: pre-wrap;
+  font-size: 1.2em;
+  margin-left: 470px;
+}
+table.breaks td {
+  padding: 2px 8px;
+}
+span.disabled {
+  color: #77778a;
+}
+";
+
+/// ranks results the same way as the server's search (tf-idf over the postings in search.json)
+const SEARCH_SCRIPT: &str = "
+<script type='text/javascript'>
+function runSearch(form) {
+  var terms = form.q.value.toLowerCase().split(/[^\\p{L}\\p{N}]+/u).filter(function(term) { return term.length > 0; });
+  fetch('search.json').then(function(response) { return response.json(); }).then(function(index) {
+    var scores = {};
+    terms.forEach(function(term) {
+      var postings = index.terms[term] || [];
+      var idf = Math.log(1 + index.documents.length / Math.max(postings.length, 1));
+      postings.forEach(function(posting) {
+        scores[posting[0]] = (scores[posting[0]] || 0) + posting[1] * idf;
+      });
+    });
+    var ranked = Object.keys(scores).sort(function(a, b) { return scores[b] - scores[a] || a - b; });
+    var results = document.getElementById('results');
+    results.innerHTML = '';
+    ranked.forEach(function(id) {
+      var entry = index.documents[id];
+      var link = document.createElement('a');
+      link.href = entry.page;
+      link.textContent = entry.coordinate + ' ' + entry.title;
+      var item = document.createElement('li');
+      item.appendChild(link);
+      results.appendChild(item);
+    });
+    if (ranked.length == 0) {
+      results.innerHTML = '<li>No matches</li>';
+    }
+  });
+  return false;
+}
+</script>";
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct SearchDocument
+///
+/// One page listed in search.json
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+struct SearchDocument {
+  coordinate: String,
+  title: String,
+  page: String,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct SearchIndex
+///
+/// The client-side search index: the documents, and for each term a list of [document, occurrences] pairs
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+struct SearchIndex {
+  documents: Vec<SearchDocument>,
+  terms: BTreeMap<String, Vec<(usize, usize)>>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn page_name
+///
+/// the file name of the page for `coord`, relative to the pages directory (ex: 1.1.1-1.1.1-1.1.2.html)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn page_name(coord: phext::Coordinate) -> String {
+  return coord.to_string().replace('/', "-") + ".html";
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn neighbors
+///
+/// finds, for every scroll and dimension, the first scroll of the previous and next populated unit of that
+/// dimension (ex: the start of the previous and next chapter), as indexes into `scrolls`
+/// ----------------------------------------------------------------------------------------------------------
+fn neighbors(scrolls: &[subspace::Scroll]) -> Vec<[(Option<usize>, Option<usize>); 9]> {
+  let columns: Vec<Vec<(Option<usize>, Option<usize>)>> = (0..9).map(|level| {
+    let mut starts: Vec<usize> = Vec::new();
+    let mut groups: Vec<usize> = Vec::with_capacity(scrolls.len());
+    for (index, scroll) in scrolls.iter().enumerate() {
+      let prefix = &query::dimensions(scroll.coord)[..=level];
+      if starts.last().map(|start| &query::dimensions(scrolls[*start].coord)[..=level] != prefix).unwrap_or(true) {
+        starts.push(index);
+      }
+      groups.push(starts.len() - 1);
+    }
+    return groups.into_iter().map(|group| (group.checked_sub(1).map(|group| starts[group]), starts.get(group + 1).copied())).collect();
+  }).collect();
+  return (0..scrolls.len()).map(|index| std::array::from_fn(|level| columns[level][index])).collect();
+}
+
+fn link(scrolls: &[subspace::Scroll], target: Option<usize>, label: &str) -> String {
+  return match target {
+    Some(index) => format!("<a href='{}' title='{}'>{}</a>", page_name(scrolls[index].coord), scrolls[index].coord, label),
+    None => format!("<span class='disabled'>{}</span>", label),
+  };
+}
+
+fn head(title: &str, stylesheet: &str) -> String {
+  return format!("<!DOCTYPE html>
+<html>
+<head>
+<meta charset='utf-8' />
+<title>{}</title>
+<link rel='stylesheet' href='{}' />
+</head>", html_escape(title), stylesheet);
+}
+
+fn render_page(world: &str, scrolls: &[subspace::Scroll], index: usize, links: &[(Option<usize>, Option<usize>); 9]) -> String {
+  let scroll = &scrolls[index];
+  let title = toc::title(scroll.content);
+  let mut breaks = String::new();
+  for (level, (previous, next)) in links.iter().enumerate() {
+    breaks += &format!("<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+      link(scrolls, *previous, "&laquo; Prev"), stats::DIMENSION_NAMES[level], link(scrolls, *next, "Next &raquo;"));
+  }
+
+  return format!("{}
+<body>
+<div class='navmap'>
+<a href='../index.html'>{}</a>: {}<br />
+<table class='breaks'>
+{}</table>
+</div>
+<pre class='scroll'>{}</pre>
+</body>
+</html>
+", head(&format!("{} - {} {}", world, scroll.coord, title), "../style.css"), html_escape(world), scroll.coord, breaks, html_escape(scroll.content));
+}
+
+fn render_index(world: &str, scrolls: &[subspace::Scroll]) -> String {
+  let mut contents = String::new();
+  for scroll in scrolls {
+    let entry = toc::summarize(scroll);
+    contents += &format!("<li><a href='pages/{}' title='{} bytes, {} lines'>{} {}</a></li>\n",
+      page_name(scroll.coord), entry.bytes, entry.lines, entry.coordinate, html_escape(&entry.title));
+  }
+
+  return format!("{}
+<body>
+{}
+<h1>{}</h1>
+<form onsubmit='return runSearch(this);'>
+  <input class='text' type='text' name='q' placeholder='Search {}' />
+  <input type='submit' value='Search' />
+</form>
+<ul id='results'></ul>
+<h2>Contents ({} scrolls)</h2>
+<ul>
+{}</ul>
+</body>
+</html>
+", head(world, "style.css"), SEARCH_SCRIPT, html_escape(world), html_escape(world), scrolls.len(), contents);
+}
+
+fn search_index(scrolls: &[subspace::Scroll]) -> SearchIndex {
+  let mut documents = Vec::new();
+  let mut terms: BTreeMap<String, Vec<(usize, usize)>> = BTreeMap::new();
+  for (id, scroll) in scrolls.iter().enumerate() {
+    documents.push(SearchDocument {
+      coordinate: scroll.coord.to_string(),
+      title: toc::title(scroll.content),
+      page: format!("pages/{}", page_name(scroll.coord)),
+    });
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for term in search::tokenize(scroll.content) {
+      *counts.entry(term).or_insert(0) += 1;
+    }
+    for (term, count) in counts {
+      terms.entry(term).or_default().push((id, count));
+    }
+  }
+  return SearchIndex { documents, terms };
+}
+
+fn write(path: &Path, content: &str) -> Result<(), String> {
+  return fs::write(path, content).map_err(|error| format!("unable to write {}: {}", path.display(), error));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn generate
+///
+/// writes the static site for `buffer` into `directory` (which must be empty or missing): index.html,
+/// style.css, search.json, and a page per populated scroll under pages/. Returns the number of pages.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn generate(world: &str, buffer: &str, directory: &Path) -> Result<usize, String> {
+  if fs::read_dir(directory).is_ok_and(|mut entries| entries.next().is_some()) {
+    return Err(format!("{} already exists and is not empty", directory.display()));
+  }
+  let pages = directory.join("pages");
+  fs::create_dir_all(&pages).map_err(|error| format!("unable to create {}: {}", pages.display(), error))?;
+
+  let scrolls: Vec<subspace::Scroll> = subspace::scrolls(buffer).collect();
+  for (index, links) in neighbors(&scrolls).iter().enumerate() {
+    write(&pages.join(page_name(scrolls[index].coord)), &render_page(world, &scrolls, index, links))?;
+  }
+  write(&directory.join("index.html"), &render_index(world, &scrolls))?;
+  write(&directory.join("style.css"), &(css_rules() + SITE_CSS))?;
+  let index = json::to_string(&search_index(&scrolls)).map_err(|error| format!("unable to serialize search index: {}", error))?;
+  write(&directory.join("search.json"), &index)?;
+
+  return Ok(scrolls.len());
+}
@@ -0,0 +1,221 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// World Statistics
+///
+/// Summarizes how a phext occupies subspace, and flags content that suggests it should be normalized or
+/// cleaned up before it grows any larger.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use rocket::serde::Serialize;
+use std::collections::BTreeMap;
+use crate::query;
+use crate::subspace;
+
+/// dimension names, ordered from library down to scroll
+pub const DIMENSION_NAMES: [&str; 9] = ["library", "shelf", "series", "collection", "volume", "book", "chapter", "section", "scroll"];
+
+/// number of entries reported in `largest`
+const LARGEST_SCROLLS: usize = 10;
+
+/// number of example coordinates reported for each anomaly
+const ANOMALY_EXAMPLES: usize = 10;
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Occupancy
+///
+/// The number of populated scrolls beneath one node of the hierarchy (ex: book 1.1.1/1.1.2)
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Occupancy {
+  pub prefix: String,
+  pub scrolls: usize,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Distribution
+///
+/// Occupancy for every populated node of a single dimension
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Distribution {
+  pub dimension: String,
+  pub nodes: Vec<Occupancy>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct ScrollSize
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ScrollSize {
+  pub coordinate: String,
+  pub bytes: usize,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Anomaly
+///
+/// A class of problem found in the buffer, with a count and a few of the coordinates it occurs at
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Anomaly {
+  pub kind: String,
+  pub description: String,
+  pub count: usize,
+  pub coordinates: Vec<String>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct WorldStats
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct WorldStats {
+  pub bytes: usize,
+  pub scrolls: usize,
+  pub empty_scrolls: usize,
+  pub delimiters: BTreeMap<String, usize>,
+  pub distribution: Vec<Distribution>,
+  pub largest: Vec<ScrollSize>,
+  pub anomalies: Vec<Anomaly>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn delimiter_name
+///
+/// names the dimension that `byte` breaks, if it is a phext delimiter (line breaks included)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn delimiter_name(byte: u8) -> Option<&'static str> {
+  return match byte as char {
+    phext::LINE_BREAK       => Some("line"),
+    phext::SCROLL_BREAK     => Some("scroll"),
+    phext::SECTION_BREAK    => Some("section"),
+    phext::CHAPTER_BREAK    => Some("chapter"),
+    phext::BOOK_BREAK       => Some("book"),
+    phext::VOLUME_BREAK     => Some("volume"),
+    phext::COLLECTION_BREAK => Some("collection"),
+    phext::SERIES_BREAK     => Some("series"),
+    phext::SHELF_BREAK      => Some("shelf"),
+    phext::LIBRARY_BREAK    => Some("library"),
+    _ => None,
+  };
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn is_stray_control
+///
+/// determines if `byte` is a control character with no meaning in phext (tabs, carriage returns, and the
+/// cowbell are all permitted)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn is_stray_control(byte: u8) -> bool {
+  if phext::is_phext_break(byte) || byte == b'\t' || byte == b'\r' || byte == phext::MORE_COWBELL as u8 {
+    return false;
+  }
+  return byte < 0x20 || byte == 0x7f;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn format_prefix
+///
+/// formats the leading dimensions of a coordinate in canonical form (ex: 1.1.1/1.2)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn format_prefix(dimensions: &[usize]) -> String {
+  let mut result = String::new();
+  for (i, value) in dimensions.iter().enumerate() {
+    if i > 0 {
+      if i % 3 == 0 { result.push('/'); } else { result.push('.'); }
+    }
+    result += &value.to_string();
+  }
+  return result;
+}
+
+fn record(anomaly: &mut Anomaly, coord: phext::Coordinate, occurrences: usize) {
+  if occurrences == 0 {
+    return;
+  }
+  anomaly.count += occurrences;
+  let coordinate = coord.to_string();
+  if anomaly.coordinates.len() < ANOMALY_EXAMPLES && !anomaly.coordinates.contains(&coordinate) {
+    anomaly.coordinates.push(coordinate);
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn analyze
+///
+/// gathers occupancy, size, and delimiter statistics for `buffer` in a single pass over its scrolls
+/// ----------------------------------------------------------------------------------------------------------
+pub fn analyze(buffer: &str) -> WorldStats {
+  let bytes = buffer.as_bytes();
+  let mut delimiters: BTreeMap<String, usize> = BTreeMap::new();
+  for name in ["line", "scroll", "section", "chapter", "book", "volume", "collection", "series", "shelf", "library"] {
+    delimiters.insert(name.to_string(), 0);
+  }
+  for byte in bytes {
+    if let Some(name) = delimiter_name(*byte) {
+      *delimiters.entry(name.to_string()).or_insert(0) += 1;
+    }
+  }
+
+  let mut nodes: Vec<BTreeMap<Vec<usize>, usize>> = vec![BTreeMap::new(); 8];
+  let mut sizes: Vec<ScrollSize> = Vec::new();
+  let mut stray = Anomaly { kind: "control".to_string(), description: "control characters outside of the phext delimiter set".to_string(), count: 0, coordinates: vec![] };
+  let mut crlf = Anomaly { kind: "crlf".to_string(), description: "CRLF line endings".to_string(), count: 0, coordinates: vec![] };
+  let mut runs = Anomaly { kind: "denormalized".to_string(), description: "delimiters that phext::normalize would remove".to_string(), count: 0, coordinates: vec![] };
+
+  let mut scrolls = 0;
+  let mut previous = phext::default_coordinate();
+  let mut previous_end = 0;
+  for scroll in subspace::scrolls(buffer) {
+    scrolls += 1;
+    let dimensions = query::dimensions(scroll.coord);
+    for (level, counts) in nodes.iter_mut().enumerate() {
+      *counts.entry(dimensions[..level + 1].to_vec()).or_insert(0) += 1;
+    }
+    sizes.push(ScrollSize { coordinate: scroll.coord.to_string(), bytes: scroll.content.len() });
+
+    record(&mut stray, scroll.coord, scroll.content.bytes().filter(|byte| is_stray_control(*byte)).count());
+    record(&mut crlf, scroll.coord, scroll.content.matches("\r\n").count());
+    let minimal = subspace::delimiters_between(previous, scroll.coord).len();
+    let actual = scroll.offset - previous_end;
+    record(&mut runs, scroll.coord, actual.saturating_sub(minimal));
+
+    previous = scroll.coord;
+    previous_end = scroll.offset + scroll.content.len();
+  }
+  let trailing = bytes.len() - previous_end;
+  if trailing > 0 {
+    let mut coord = previous;
+    for byte in &bytes[previous_end..] {
+      subspace::advance(&mut coord, *byte);
+    }
+    record(&mut runs, coord, trailing);
+  }
+
+  let slots = 1 + bytes.iter().filter(|byte| phext::is_phext_break(**byte) && **byte != phext::LINE_BREAK as u8).count();
+  let distribution = nodes.into_iter().enumerate().map(|(level, counts)| Distribution {
+    dimension: DIMENSION_NAMES[level].to_string(),
+    nodes: counts.into_iter().map(|(prefix, scrolls)| Occupancy {
+      prefix: format_prefix(&prefix),
+      scrolls,
+    }).collect(),
+  }).collect();
+
+  sizes.sort_by_key(|scroll| std::cmp::Reverse(scroll.bytes));
+  sizes.truncate(LARGEST_SCROLLS);
+
+  let anomalies = vec![stray, crlf, runs].into_iter().filter(|anomaly| anomaly.count > 0).collect();
+
+  return WorldStats {
+    bytes: bytes.len(),
+    scrolls,
+    empty_scrolls: slots.saturating_sub(scrolls),
+    delimiters,
+    distribution,
+    largest: sizes,
+    anomalies,
+  };
+}
@@ -0,0 +1,523 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Phext Storage Backends
+///
+/// Everything the server reads or writes goes through the `Storage` trait, so a data root can keep its worlds
+/// as flat `.phext` files (the default), as rows in a SQLite database, one row per scroll keyed by the nine
+/// coordinate dimensions, or as objects in an S3-compatible bucket (see objects.rs). Large worlds stored in
+/// SQLite can be queried and inspected without parsing the whole buffer. Files kept beside a world (history,
+/// snapshots) always stay on disk.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::compression::{self, Codec, Compression};
+use crate::objects::{ObjectConfig, ObjectStorage};
+use crate::{archive, document, query, subspace};
+
+/// the database file a SQLite data root keeps its worlds in
+pub const SQLITE_NAME: &str = "phext.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS worlds (
+  name TEXT PRIMARY KEY NOT NULL,
+  modified INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS scrolls (
+  world TEXT NOT NULL REFERENCES worlds(name) ON DELETE CASCADE,
+  library INTEGER NOT NULL,
+  shelf INTEGER NOT NULL,
+  series INTEGER NOT NULL,
+  collection INTEGER NOT NULL,
+  volume INTEGER NOT NULL,
+  book INTEGER NOT NULL,
+  chapter INTEGER NOT NULL,
+  section INTEGER NOT NULL,
+  scroll INTEGER NOT NULL,
+  content TEXT NOT NULL,
+  PRIMARY KEY (world, library, shelf, series, collection, volume, book, chapter, section, scroll)
+) WITHOUT ROWID;
+";
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum Backend
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub enum Backend {
+  #[default]
+  File,
+  Sqlite,
+  Object(ObjectConfig),
+}
+
+impl Backend {
+  pub fn parse(name: &str) -> Result<Backend, String> {
+    return match name {
+      "file" => Ok(Backend::File),
+      "sqlite" => Ok(Backend::Sqlite),
+      _ if name.starts_with("s3://") => Ok(Backend::Object(ObjectConfig::parse(name)?)),
+      _ => Err(format!("Unknown storage backend: {} (expected file, sqlite or s3://bucket/prefix)", name)),
+    };
+  }
+
+  pub fn name(&self) -> &'static str {
+    return match self {
+      Backend::File => "file",
+      Backend::Sqlite => "sqlite",
+      Backend::Object(_) => "s3",
+    };
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum StorageError
+///
+/// Why a backend refused a load or store, so the server can answer with the matching status
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum StorageError {
+  /// the world name or buffer can't be stored (ex: `../secrets`, or coordinates beyond 99)
+  Invalid(String),
+  /// there is no world by that name
+  NotFound(String),
+  /// another writer changed the world first
+  Conflict(String),
+  /// the backend failed, or holds a world it can't read back
+  Unavailable(String),
+}
+
+impl StorageError {
+  pub fn message(&self) -> &str {
+    return match self {
+      StorageError::Invalid(message) | StorageError::NotFound(message) => message,
+      StorageError::Conflict(message) | StorageError::Unavailable(message) => message,
+    };
+  }
+}
+
+impl std::fmt::Display for StorageError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    return write!(f, "{}", self.message());
+  }
+}
+
+impl From<StorageError> for String {
+  fn from(error: StorageError) -> String {
+    return error.to_string();
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum Version
+///
+/// The version of a world that a load returned. Passing it back to `store` makes the write conditional, so
+/// an edit based on a stale copy fails with `StorageError::Conflict` instead of discarding a newer write.
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub enum Version {
+  /// replace whatever is stored (ex: copies, restores, and routes that overwrite a whole world)
+  #[default]
+  Any,
+  /// only create the world - fails if it already exists
+  Absent,
+  /// only replace this version: an ETag, or the time the world last changed
+  Tag(String),
+}
+
+/// the error a store reports when `world` moved on from the version it expected
+fn conflict(world: &str) -> StorageError {
+  return StorageError::Conflict(format!("{} was changed by another writer - reload and try again", world));
+}
+
+/// nanoseconds since the epoch, as file and SQLite versions are tagged
+fn nanos(time: SystemTime) -> u64 {
+  return time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_nanos() as u64).unwrap_or(0);
+}
+
+/// the modification time for a write that replaces the version at `current` - later than it, even when the
+/// clock (or the file system's timestamps) are coarser than the writes
+fn advance(current: Option<u64>) -> u64 {
+  return std::cmp::max(nanos(SystemTime::now()), current.map_or(0, |modified| modified + 1));
+}
+
+/// the version written at `modified`, or Absent for a world that doesn't exist
+fn version_at(modified: Option<u64>) -> Version {
+  return modified.map(|modified| Version::Tag(modified.to_string())).unwrap_or(Version::Absent);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @trait Storage
+///
+/// Loads and stores whole worlds by name
+/// ----------------------------------------------------------------------------------------------------------
+pub trait Storage: Send + Sync + std::fmt::Debug {
+  /// the raw bytes of `world` (file worlds aren't required to be valid UTF-8), and the version they belong to
+  fn load(&self, world: &str) -> Result<(Vec<u8>, Version), StorageError>;
+
+  /// replaces the contents of `world`, creating it if needed, provided it is still at `expected` - readers
+  /// never observe a partial write
+  fn store(&self, world: &str, buffer: &str, expected: &Version) -> Result<(), StorageError>;
+
+  /// when `world` last changed, if known
+  fn modified(&self, world: &str) -> Option<SystemTime>;
+
+  /// the names of every world, sorted alphabetically
+  fn worlds(&self) -> Result<Vec<String>, StorageError>;
+
+  /// the bytes of `world` encoded with `codec`, for sending with a Content-Encoding
+  fn load_encoded(&self, world: &str, codec: Codec) -> Result<Vec<u8>, StorageError> {
+    return compression::encode(&self.load(world)?.0, codec).map_err(StorageError::Unavailable);
+  }
+
+  /// applies the backend's compression policy to the worlds already stored, returning the worlds re-encoded
+  fn sweep(&self) -> Result<Vec<(String, Codec)>, StorageError> {
+    return Ok(Vec::new());
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn is_world_name
+///
+/// restricts worlds to plain names, so that no request can reach outside of the data root (ex: `../secrets`
+/// or a name with an encoded slash)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn is_world_name(name: &str) -> bool {
+  return !name.is_empty() && !name.contains(['/', '\\', '\0']) && !name.starts_with('.');
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn is_world_file
+///
+/// whether `name` is a file FileStorage keeps `world` in: the world in any encoding, or the staging file of
+/// a save in progress
+/// ----------------------------------------------------------------------------------------------------------
+pub fn is_world_file(world: &str, name: &str) -> bool {
+  let staging = format!(".{}.phext.", world);
+  return Codec::ALL.iter().any(|codec| name == format!("{}.phext{}", world, codec.extension())) || (name.starts_with(&staging) && name.ends_with(".tmp"));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn open
+///
+/// opens the storage for the data root `root`, creating the SQLite database on first use
+/// ----------------------------------------------------------------------------------------------------------
+pub fn open(backend: &Backend, root: &Path) -> Result<Box<dyn Storage>, String> {
+  return open_with(backend, root, None);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn open_with
+///
+/// opens the storage for `root` with a compression policy for file worlds (other backends ignore it)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn open_with(backend: &Backend, root: &Path, compression: Option<Compression>) -> Result<Box<dyn Storage>, String> {
+  return match backend {
+    Backend::File => Ok(Box::new(match compression {
+      Some(compression) => FileStorage::with_compression(root.to_path_buf(), compression),
+      None => FileStorage::new(root.to_path_buf()),
+    })),
+    Backend::Sqlite => Ok(Box::new(SqliteStorage::open(&root.join(SQLITE_NAME))?)),
+    Backend::Object(config) => Ok(Box::new(ObjectStorage::open(config.clone()))),
+  };
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn copy
+///
+/// copies `worlds` (or every world when none are named) from one storage to another, replacing worlds of the
+/// same name. Returns the names copied.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn copy(from: &dyn Storage, to: &dyn Storage, worlds: &[String]) -> Result<Vec<String>, String> {
+  let selected = match worlds.len() {
+    0 => from.worlds()?,
+    _ => worlds.to_vec(),
+  };
+  for world in &selected {
+    let (bytes, _) = from.load(world)?;
+    let buffer = String::from_utf8(bytes).map_err(|_| format!("{} is not valid UTF-8 (see fsck)", world))?;
+    to.store(world, &buffer, &Version::Any)?;
+  }
+  return Ok(selected);
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct FileStorage
+///
+/// One file per world in the data root: `<world>.phext`, or `<world>.phext.zst` / `<world>.phext.gz` when it
+/// is stored compressed. With a compression policy, every write (and `sweep`) picks the encoding the policy
+/// asks for; without one, worlds keep the encoding they already have.
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug)]
+pub struct FileStorage {
+  root: PathBuf,
+  compression: Option<Compression>,
+  writing: Mutex<()>,
+}
+
+/// a staging file name no other save (in this process or another) is using: hidden, so backups and world
+/// listings skip it
+fn staging_name(world: &str) -> String {
+  static STAGED: AtomicUsize = AtomicUsize::new(0);
+  return format!(".{}.phext.{}-{}.tmp", world, std::process::id(), STAGED.fetch_add(1, Ordering::Relaxed));
+}
+
+impl FileStorage {
+  pub fn new(root: PathBuf) -> FileStorage {
+    return FileStorage { root, compression: None, writing: Mutex::new(()) };
+  }
+
+  pub fn with_compression(root: PathBuf, compression: Compression) -> FileStorage {
+    return FileStorage { root, compression: Some(compression), writing: Mutex::new(()) };
+  }
+
+  fn path(&self, world: &str, codec: Codec) -> Result<PathBuf, StorageError> {
+    if !is_world_name(world) {
+      return Err(StorageError::Invalid(format!("Invalid world name: {}", world)));
+    }
+    return Ok(self.root.join(format!("{}.phext{}", world, codec.extension())));
+  }
+
+  /// the file `world` is stored in, and its encoding
+  fn stored(&self, world: &str) -> Result<Option<(Codec, PathBuf)>, StorageError> {
+    for codec in Codec::ALL {
+      let path = self.path(world, codec)?;
+      if path.is_file() {
+        return Ok(Some((codec, path)));
+      }
+    }
+    return Ok(None);
+  }
+
+  /// the modification time of the file `world` is stored in (its version - sweeps keep it as they re-encode)
+  fn last_written(&self, world: &str) -> Result<Option<u64>, StorageError> {
+    return match self.stored(world)? {
+      Some((_, path)) => Ok(Some(file_modified(&File::open(&path).map_err(|error| unreadable(&path, error))?, &path)?)),
+      None => Ok(None),
+    };
+  }
+
+  /// one writer at a time, so a sweep never puts back a world that was saved while it was re-encoding
+  fn writing(&self) -> std::sync::MutexGuard<'_, ()> {
+    return self.writing.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+  }
+
+  /// writes `bytes` (already encoded with `codec`) via a staging file, then removes the world's files in
+  /// any other encoding
+  fn replace(&self, world: &str, bytes: &[u8], codec: Codec, modified: Option<SystemTime>) -> Result<(), StorageError> {
+    let filename = self.path(world, codec)?;
+    let staging = self.root.join(staging_name(world));
+    let written = File::create(&staging).and_then(|mut file| {
+      file.write_all(bytes)?;
+      if let Some(modified) = modified {
+        file.set_modified(modified)?;
+      }
+      return fs::rename(&staging, &filename);
+    });
+    if let Err(error) = written {
+      let _ = fs::remove_file(&staging);
+      return Err(StorageError::Unavailable(format!("Unable to replace {}: {}", filename.display(), error)));
+    }
+    for other in Codec::ALL.iter().filter(|other| **other != codec) {
+      let stale = self.path(world, *other)?;
+      if stale.is_file() {
+        fs::remove_file(&stale).map_err(|error| StorageError::Unavailable(format!("Unable to remove {}: {}", stale.display(), error)))?;
+      }
+    }
+    return Ok(());
+  }
+}
+
+fn unreadable(path: &Path, error: std::io::Error) -> StorageError {
+  return StorageError::Unavailable(format!("Unable to read {}: {}", path.display(), error));
+}
+
+/// the modification time of an open world file (saves rename a new file into place, so it belongs to the
+/// content read from the same handle)
+fn file_modified(file: &File, path: &Path) -> Result<u64, StorageError> {
+  let modified = file.metadata().and_then(|metadata| metadata.modified()).map_err(|error| unreadable(path, error))?;
+  return Ok(nanos(modified));
+}
+
+impl Storage for FileStorage {
+  fn load(&self, world: &str) -> Result<(Vec<u8>, Version), StorageError> {
+    let (codec, path) = self.stored(world)?.ok_or(StorageError::NotFound(format!("Unable to find {}", world)))?;
+    let mut file = File::open(&path).map_err(|error| unreadable(&path, error))?;
+    let version = version_at(Some(file_modified(&file, &path)?));
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).map_err(|error| unreadable(&path, error))?;
+    let buffer = compression::decode(&bytes, codec).map_err(|error| StorageError::Unavailable(format!("{}: {}", path.display(), error)))?;
+    return Ok((buffer, version));
+  }
+
+  /// sends the file as it is stored when it already has the requested encoding
+  fn load_encoded(&self, world: &str, codec: Codec) -> Result<Vec<u8>, StorageError> {
+    return match self.stored(world)? {
+      Some((stored, path)) if stored == codec => fs::read(&path).map_err(|error| unreadable(&path, error)),
+      _ => compression::encode(&self.load(world)?.0, codec).map_err(StorageError::Unavailable),
+    };
+  }
+
+  /// writes to a temporary file first and renames it into place
+  fn store(&self, world: &str, buffer: &str, expected: &Version) -> Result<(), StorageError> {
+    let _writing = self.writing();
+    let current = self.last_written(world)?;
+    if *expected != Version::Any && version_at(current) != *expected {
+      return Err(conflict(world));
+    }
+    let codec = match self.compression {
+      Some(policy) => policy.choose(buffer.len(), Duration::ZERO),
+      None => self.stored(world)?.map(|(codec, _)| codec).unwrap_or_default(),
+    };
+    let encoded = compression::encode(buffer.as_bytes(), codec).map_err(StorageError::Unavailable)?;
+    return self.replace(world, &encoded, codec, Some(UNIX_EPOCH + Duration::from_nanos(advance(current))));
+  }
+
+  fn modified(&self, world: &str) -> Option<SystemTime> {
+    let (_, path) = self.stored(world).ok()??;
+    return fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+  }
+
+  fn worlds(&self) -> Result<Vec<String>, StorageError> {
+    let entries = fs::read_dir(&self.root).map_err(|error| StorageError::Unavailable(format!("unable to read {}: {}", self.root.display(), error)))?;
+    let mut worlds: Vec<String> = entries
+      .filter_map(|entry| {
+        let name = entry.ok()?.file_name().to_string_lossy().to_string();
+        return Codec::ALL.iter().find_map(|codec| name.strip_suffix(&format!(".phext{}", codec.extension())).map(|world| world.to_string()));
+      })
+      .filter(|world| is_world_name(world))
+      .collect();
+    worlds.sort();
+    worlds.dedup();
+    return Ok(worlds);
+  }
+
+  /// re-encodes every world whose encoding differs from the policy's choice (ex: worlds that have become
+  /// idle), keeping their modification times
+  fn sweep(&self) -> Result<Vec<(String, Codec)>, StorageError> {
+    let Some(policy) = self.compression else {
+      return Ok(Vec::new());
+    };
+    let mut changed = Vec::new();
+    for world in self.worlds()? {
+      let _writing = self.writing();
+      let Some((codec, path)) = self.stored(&world)? else {
+        continue;
+      };
+      let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+      let idle_for = modified.and_then(|modified| modified.elapsed().ok()).unwrap_or_default();
+      let stored = fs::read(&path).map_err(|error| unreadable(&path, error))?;
+      let buffer = compression::decode(&stored, codec).map_err(|error| StorageError::Unavailable(format!("{}: {}", path.display(), error)))?;
+      let target = policy.choose(buffer.len(), idle_for);
+      if target != codec {
+        let encoded = compression::encode(&buffer, target).map_err(StorageError::Unavailable)?;
+        self.replace(&world, &encoded, target, modified)?;
+        changed.push((world, target));
+      }
+    }
+    return Ok(changed);
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct SqliteStorage
+///
+/// Every world in one database: a `worlds` table with the time each world last changed, and a `scrolls`
+/// table with a row per scroll. Rows are exactly the scrolls `document::scrolls` lists, so the empty
+/// scrolls a buffer needs to round-trip are kept, and worlds with coordinates beyond 99 are refused.
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug)]
+pub struct SqliteStorage {
+  connection: Mutex<Connection>,
+}
+
+fn sql_error(error: rusqlite::Error) -> StorageError {
+  return StorageError::Unavailable(format!("SQLite error: {}", error));
+}
+
+/// when `world` last changed (its version), or None if there is no such world
+fn sqlite_version(connection: &Connection, world: &str) -> Result<Option<u64>, StorageError> {
+  let modified: Option<i64> = connection.query_row("SELECT modified FROM worlds WHERE name = ?1", params![world], |row| row.get(0))
+    .optional().map_err(sql_error)?;
+  return Ok(modified.map(|modified| modified as u64));
+}
+
+impl SqliteStorage {
+  pub fn open(path: &Path) -> Result<SqliteStorage, String> {
+    let connection = Connection::open(path).map_err(|error| format!("unable to open {}: {}", path.display(), error))?;
+    connection.execute_batch("PRAGMA foreign_keys = ON;").map_err(sql_error)?;
+    connection.execute_batch(SCHEMA).map_err(sql_error)?;
+    return Ok(SqliteStorage { connection: Mutex::new(connection) });
+  }
+
+  fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+    return self.connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+  }
+}
+
+impl Storage for SqliteStorage {
+  fn load(&self, world: &str) -> Result<(Vec<u8>, Version), StorageError> {
+    let connection = self.connection();
+    let Some(modified) = sqlite_version(&connection, world)? else {
+      return Err(StorageError::NotFound(format!("Unable to find {}", world)));
+    };
+
+    let mut statement = connection.prepare("SELECT library, shelf, series, collection, volume, book, chapter, section, scroll, content
+      FROM scrolls WHERE world = ?1
+      ORDER BY library, shelf, series, collection, volume, book, chapter, section, scroll").map_err(sql_error)?;
+    let rows = statement.query_map(params![world], |row| {
+      let mut dimensions = [0usize; 9];
+      for (index, dimension) in dimensions.iter_mut().enumerate() {
+        *dimension = row.get(index)?;
+      }
+      return Ok((dimensions, row.get::<_, String>(9)?));
+    }).map_err(sql_error)?;
+    let scrolls: Vec<([usize; 9], String)> = rows.collect::<Result<_, _>>().map_err(sql_error)?;
+
+    let buffer = subspace::assemble(phext::default_coordinate(), scrolls.iter().map(|(dimensions, content)| {
+      return subspace::Scroll { coord: archive::to_coordinate(dimensions), content: content.as_str(), offset: 0 };
+    }));
+    return Ok((buffer.into_bytes(), version_at(Some(modified))));
+  }
+
+  /// replaces the world's rows in a single transaction, moving its modification time forward
+  fn store(&self, world: &str, buffer: &str, expected: &Version) -> Result<(), StorageError> {
+    let scrolls = document::scrolls(buffer).map_err(StorageError::Invalid)?;
+
+    let mut connection = self.connection();
+    let transaction = connection.transaction().map_err(sql_error)?;
+    let current = sqlite_version(&transaction, world)?;
+    if *expected != Version::Any && version_at(current) != *expected {
+      return Err(conflict(world));
+    }
+    let modified = advance(current) as i64;
+    transaction.execute("INSERT INTO worlds (name, modified) VALUES (?1, ?2) ON CONFLICT(name) DO UPDATE SET modified = ?2",
+      params![world, modified]).map_err(sql_error)?;
+    transaction.execute("DELETE FROM scrolls WHERE world = ?1", params![world]).map_err(sql_error)?;
+    {
+      let mut insert = transaction.prepare("INSERT INTO scrolls
+        (world, library, shelf, series, collection, volume, book, chapter, section, scroll, content)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)").map_err(sql_error)?;
+      for scroll in &scrolls {
+        let d = query::dimensions(scroll.coord);
+        insert.execute(params![world, d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7], d[8], scroll.content]).map_err(sql_error)?;
+      }
+    }
+    return transaction.commit().map_err(sql_error);
+  }
+
+  fn modified(&self, world: &str) -> Option<SystemTime> {
+    let nanos: i64 = self.connection().query_row("SELECT modified FROM worlds WHERE name = ?1", params![world], |row| row.get(0)).ok()?;
+    return Some(UNIX_EPOCH + Duration::from_nanos(nanos as u64));
+  }
+
+  fn worlds(&self) -> Result<Vec<String>, StorageError> {
+    let connection = self.connection();
+    let mut statement = connection.prepare("SELECT name FROM worlds ORDER BY name").map_err(sql_error)?;
+    let names = statement.query_map([], |row| row.get(0)).map_err(sql_error)?;
+    return names.collect::<Result<Vec<String>, _>>().map_err(sql_error);
+  }
+}
@@ -0,0 +1,182 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Subspace Helpers
+///
+/// Utilities for working with portions of a phext buffer (ranges of scrolls) that libphext doesn't expose.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Scroll
+///
+/// A borrowed view of one populated scroll within a phext buffer
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Scroll<'a> {
+  pub coord: phext::Coordinate,
+  pub content: &'a str,
+  pub offset: usize,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct ScrollIterator
+///
+/// Walks a phext buffer exactly once, yielding each non-empty scroll in subspace order. Unlike
+/// `phext::phokenize`, no copies of the remaining buffer are made along the way.
+/// ----------------------------------------------------------------------------------------------------------
+pub struct ScrollIterator<'a> {
+  buffer: &'a str,
+  index: usize,
+  coord: phext::Coordinate,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn scrolls
+///
+/// iterates over every populated scroll in `buffer`
+/// ----------------------------------------------------------------------------------------------------------
+pub fn scrolls(buffer: &str) -> ScrollIterator<'_> {
+  return ScrollIterator { buffer, index: 0, coord: phext::default_coordinate() };
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn advance
+///
+/// moves `coord` forward by the given delimiter, returning false if `byte` is not a dimension break
+/// ----------------------------------------------------------------------------------------------------------
+pub fn advance(coord: &mut phext::Coordinate, byte: u8) -> bool {
+  match byte as char {
+    phext::SCROLL_BREAK     => coord.scroll_break(),
+    phext::SECTION_BREAK    => coord.section_break(),
+    phext::CHAPTER_BREAK    => coord.chapter_break(),
+    phext::BOOK_BREAK       => coord.book_break(),
+    phext::VOLUME_BREAK     => coord.volume_break(),
+    phext::COLLECTION_BREAK => coord.collection_break(),
+    phext::SERIES_BREAK     => coord.series_break(),
+    phext::SHELF_BREAK      => coord.shelf_break(),
+    phext::LIBRARY_BREAK    => coord.library_break(),
+    _ => { return false; }
+  }
+  return true;
+}
+
+impl<'a> Iterator for ScrollIterator<'a> {
+  type Item = Scroll<'a>;
+
+  fn next(&mut self) -> Option<Scroll<'a>> {
+    let bytes = self.buffer.as_bytes();
+    while self.index < bytes.len() {
+      let start = self.index;
+      let coord = self.coord;
+      while self.index < bytes.len() && !advance(&mut self.coord, bytes[self.index]) {
+        self.index += 1;
+      }
+      let content = &self.buffer[start..self.index];
+      if self.index < bytes.len() {
+        self.index += 1;
+      }
+      if !content.is_empty() {
+        return Some(Scroll { coord, content, offset: start });
+      }
+    }
+    return None;
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn fetch
+///
+/// returns the scroll at `coord`, or an empty string if it isn't populated (`phext::fetch` returns part of a
+/// neighbouring scroll for unpopulated coordinates)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn fetch(buffer: &str, coord: phext::Coordinate) -> &str {
+  return scrolls(buffer).take_while(|scroll| scroll.coord <= coord).find(|scroll| scroll.coord == coord).map(|scroll| scroll.content).unwrap_or_default();
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn delimiters_between
+///
+/// Produces the minimal sequence of dimension breaks needed to walk from `from` to `to`
+/// ----------------------------------------------------------------------------------------------------------
+pub fn delimiters_between(from: phext::Coordinate, to: phext::Coordinate) -> String {
+  let mut coord = from;
+  let mut output = String::new();
+  while coord < to {
+    if coord.z.library < to.z.library          { output.push(phext::LIBRARY_BREAK);    coord.library_break();    continue; }
+    if coord.z.shelf < to.z.shelf              { output.push(phext::SHELF_BREAK);      coord.shelf_break();      continue; }
+    if coord.z.series < to.z.series            { output.push(phext::SERIES_BREAK);     coord.series_break();     continue; }
+    if coord.y.collection < to.y.collection    { output.push(phext::COLLECTION_BREAK); coord.collection_break(); continue; }
+    if coord.y.volume < to.y.volume            { output.push(phext::VOLUME_BREAK);     coord.volume_break();     continue; }
+    if coord.y.book < to.y.book                { output.push(phext::BOOK_BREAK);       coord.book_break();       continue; }
+    if coord.x.chapter < to.x.chapter          { output.push(phext::CHAPTER_BREAK);    coord.chapter_break();    continue; }
+    if coord.x.section < to.x.section          { output.push(phext::SECTION_BREAK);    coord.section_break();    continue; }
+    if coord.x.scroll < to.x.scroll            { output.push(phext::SCROLL_BREAK);     coord.scroll_break();     continue; }
+    break;
+  }
+  return output;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn in_range
+///
+/// determines if `coord` falls between the (inclusive) start and end of `range`
+/// ----------------------------------------------------------------------------------------------------------
+pub fn in_range(coord: phext::Coordinate, range: phext::Range) -> bool {
+  return coord >= range.start && coord <= range.end;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn assemble
+///
+/// packs a sequence of scrolls (in subspace order) back into a phext buffer, relative to `origin`
+/// ----------------------------------------------------------------------------------------------------------
+pub fn assemble<'a, I: Iterator<Item = Scroll<'a>>>(origin: phext::Coordinate, scrolls: I) -> String {
+  let mut result = String::new();
+  let mut coord = origin;
+  for scroll in scrolls {
+    result.push_str(&delimiters_between(coord, scroll.coord));
+    result.push_str(scroll.content);
+    coord = scroll.coord;
+  }
+
+  return result;
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn select_range
+///
+/// extracts every scroll between `range.start` and `range.end` into a standalone phext
+///
+/// @param rebase  when true, `range.start` becomes 1.1.1/1.1.1/1.1.1 in the output
+/// ----------------------------------------------------------------------------------------------------------
+pub fn select_range(buffer: &str, range: phext::Range, rebase: bool) -> String {
+  let mut origin = phext::default_coordinate();
+  if rebase {
+    origin = range.start;
+  }
+
+  return assemble(origin, scrolls(buffer).filter(|scroll| in_range(scroll.coord, range)));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn delete_range
+///
+/// removes every scroll between `range.start` and `range.end`, leaving the rest of subspace in place
+/// ----------------------------------------------------------------------------------------------------------
+pub fn delete_range(buffer: &str, range: phext::Range) -> String {
+  return assemble(phext::default_coordinate(), scrolls(buffer).filter(|scroll| !in_range(scroll.coord, range)));
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn replace
+///
+/// sets the scroll at `coord` to `content` (removing it when `content` is empty) and leaves every other scroll
+/// where it was (`phext::replace` and `phext::remove` misplace scrolls at unpopulated coordinates)
+/// ----------------------------------------------------------------------------------------------------------
+pub fn replace(buffer: &str, coord: phext::Coordinate, content: &str) -> String {
+  let mut edited: Vec<Scroll> = scrolls(buffer).filter(|scroll| scroll.coord != coord).collect();
+  if !content.is_empty() {
+    let position = edited.partition_point(|scroll| scroll.coord < coord);
+    edited.insert(position, Scroll { coord, content, offset: 0 });
+  }
+  return assemble(phext::default_coordinate(), edited.into_iter());
+}
@@ -0,0 +1,262 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Phext Sync
+///
+/// Keeps a directory of plain files and a phext in step with each other. The mapping between paths and
+/// coordinates is the pack manifest stored in the world itself, and the checksum recorded for each file is
+/// the last state both sides agreed on: whichever side moved away from it is copied to the other, and a file
+/// whose scroll changed too is reported as a conflict instead of being overwritten.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use crate::archive::{self, ManifestEntry, PackedFile};
+use crate::{query, subspace};
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum SyncEvent
+///
+/// One change made (or refused) while reconciling a directory with its phext
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone)]
+pub enum SyncEvent {
+  ToScroll { path: String, coordinate: phext::Coordinate },
+  ToFile { path: String, coordinate: phext::Coordinate },
+  Added { path: String, coordinate: phext::Coordinate },
+  FileRemoved { path: String, coordinate: phext::Coordinate },
+  ScrollCleared { path: String, coordinate: phext::Coordinate },
+  Conflict { path: String, coordinate: phext::Coordinate, reason: String },
+}
+
+impl SyncEvent {
+  pub fn is_conflict(&self) -> bool {
+    return matches!(self, SyncEvent::Conflict { .. });
+  }
+}
+
+impl fmt::Display for SyncEvent {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    return match self {
+      SyncEvent::ToScroll { path, coordinate } => write!(f, "{}: updated from {}", coordinate, path),
+      SyncEvent::ToFile { path, coordinate } => write!(f, "{}: updated from {}", path, coordinate),
+      SyncEvent::Added { path, coordinate } => write!(f, "{}: added {}", coordinate, path),
+      SyncEvent::FileRemoved { path, coordinate } => write!(f, "{}: cleared ({} was removed)", coordinate, path),
+      SyncEvent::ScrollCleared { path, coordinate } => write!(f, "{}: removed ({} was cleared)", path, coordinate),
+      SyncEvent::Conflict { path, coordinate, reason } => write!(f, "conflict: {} and {} {}", path, coordinate, reason),
+    };
+  }
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @enum FileWrite
+///
+/// A change a sync pass makes to the directory: a file rewritten from its scroll, or removed with it
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone)]
+pub enum FileWrite {
+  Restore { entry: ManifestEntry, content: Vec<u8> },
+  Remove { path: String },
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Reconciled
+///
+/// The updated phext after a sync pass, the file changes that go with it, and everything that pass changed
+/// or refused to change
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug)]
+pub struct Reconciled {
+  pub buffer: String,
+  pub writes: Vec<FileWrite>,
+  pub events: Vec<SyncEvent>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct Scrolls
+///
+/// The populated scrolls of a phext keyed by coordinate. Sync edits this map and reassembles the buffer
+/// once, because `phext::fetch` and `phext::replace` misplace scrolls past the last populated coordinate.
+/// ----------------------------------------------------------------------------------------------------------
+struct Scrolls {
+  scrolls: BTreeMap<[usize; 9], String>,
+  changed: bool,
+}
+
+impl Scrolls {
+  fn new(buffer: &str) -> Scrolls {
+    let scrolls = subspace::scrolls(buffer)
+      .filter(|scroll| !scroll.content.is_empty())
+      .map(|scroll| (query::dimensions(scroll.coord), scroll.content.to_string()))
+      .collect();
+    return Scrolls { scrolls, changed: false };
+  }
+
+  fn get(&self, coordinate: phext::Coordinate) -> &str {
+    return self.scrolls.get(&query::dimensions(coordinate)).map(|content| content.as_str()).unwrap_or_default();
+  }
+
+  fn set(&mut self, coordinate: phext::Coordinate, content: &str) {
+    match content.len() {
+      0 => self.scrolls.remove(&query::dimensions(coordinate)),
+      _ => self.scrolls.insert(query::dimensions(coordinate), content.to_string()),
+    };
+    self.changed = true;
+  }
+
+  fn assemble(&self) -> String {
+    return subspace::assemble(phext::default_coordinate(), self.scrolls.iter().map(|(dimensions, content)| {
+      return subspace::Scroll { coord: archive::to_coordinate(dimensions), content: content.as_str(), offset: 0 };
+    }));
+  }
+}
+
+fn is_taken(scrolls: &Scrolls, entries: &[ManifestEntry], coordinate: phext::Coordinate) -> bool {
+  return entries.iter().any(|entry| entry.coordinate == coordinate) || !scrolls.get(coordinate).is_empty();
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn allocate
+///
+/// picks a coordinate for a file that isn't in the manifest yet: the slot `pack` would give it if that slot
+/// is free, otherwise the next free scroll after the last file already synced from the same directory
+/// ----------------------------------------------------------------------------------------------------------
+fn allocate(scrolls: &Scrolls, entries: &[ManifestEntry], file: &PackedFile) -> Result<phext::Coordinate, String> {
+  if !is_taken(scrolls, entries, file.coordinate) {
+    return Ok(file.coordinate);
+  }
+
+  let directory = Path::new(&file.path).parent();
+  let mut dimensions = entries.iter()
+    .filter(|entry| Path::new(&entry.path).parent() == directory)
+    .map(|entry| query::dimensions(entry.coordinate))
+    .max()
+    .unwrap_or(query::dimensions(file.coordinate));
+  while dimensions[8] < phext::COORDINATE_MAXIMUM - 1 {
+    dimensions[8] += 1;
+    let candidate = archive::to_coordinate(&dimensions);
+    if !is_taken(scrolls, entries, candidate) {
+      return Ok(candidate);
+    }
+  }
+  return Err(format!("no free scroll left for {}", file.path));
+}
+
+fn conflict(entry: &ManifestEntry, reason: &str) -> SyncEvent {
+  return SyncEvent::Conflict { path: entry.path.clone(), coordinate: entry.coordinate, reason: reason.to_string() };
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn reconcile
+///
+/// makes a single sync pass over `root` and `buffer`, returning the phext with file edits (and new files)
+/// applied and the file writes that carry scroll edits back to the directory. Nothing is written here: the
+/// caller saves the phext first and then `apply`s the writes. Conflicting entries are left untouched on both
+/// sides, and keep their old checksum, so they are reported again until someone resolves them.
+/// ----------------------------------------------------------------------------------------------------------
+pub fn reconcile(buffer: &str, root: &Path, ignore: &dyn Fn(&Path, bool) -> bool) -> Result<Reconciled, String> {
+  let manifest_coordinate = phext::to_coordinate(archive::MANIFEST_COORDINATE);
+  let mut scrolls = Scrolls::new(buffer);
+  let manifest = scrolls.get(manifest_coordinate).to_string();
+  let archive::Manifest { entries, directories } = archive::parse_manifest(&manifest)?;
+  let files = archive::collect(root, ignore)?;
+  let mut on_disk: BTreeMap<&str, &PackedFile> = files.iter().map(|file| (file.path.as_str(), file)).collect();
+
+  let mut kept: Vec<ManifestEntry> = Vec::new();
+  let mut writes: Vec<FileWrite> = Vec::new();
+  let mut events: Vec<SyncEvent> = Vec::new();
+  for mut entry in entries {
+    let scroll = scrolls.get(entry.coordinate).to_string();
+    let scroll_changed = phext::checksum(&scroll) != entry.hash;
+    let file = match on_disk.remove(entry.path.as_str()) {
+      Some(file) => file,
+      None => {
+        if scroll_changed && !scroll.is_empty() {
+          events.push(conflict(&entry, "disagree: the file was removed while its scroll was edited"));
+          kept.push(entry);
+        } else {
+          scrolls.set(entry.coordinate, "");
+          events.push(SyncEvent::FileRemoved { path: entry.path, coordinate: entry.coordinate });
+        }
+        continue;
+      }
+    };
+    let file_changed = phext::checksum(&file.content) != entry.hash;
+
+    match (file_changed, scroll_changed) {
+      (false, false) => {},
+      (true, false) => {
+        scrolls.set(entry.coordinate, &file.content);
+        entry.hash = phext::checksum(&file.content);
+        entry.encoding = file.encoding;
+        events.push(SyncEvent::ToScroll { path: entry.path.clone(), coordinate: entry.coordinate });
+      },
+      (false, true) if scroll.is_empty() => {
+        writes.push(FileWrite::Remove { path: entry.path.clone() });
+        events.push(SyncEvent::ScrollCleared { path: entry.path, coordinate: entry.coordinate });
+        continue;
+      },
+      (false, true) => match archive::decode(entry.encoding, &scroll) {
+        Ok(content) => {
+          writes.push(FileWrite::Restore { entry: entry.clone(), content });
+          entry.hash = phext::checksum(&scroll);
+          events.push(SyncEvent::ToFile { path: entry.path.clone(), coordinate: entry.coordinate });
+        },
+        Err(error) => events.push(conflict(&entry, &format!("disagree: the scroll is not valid {} ({})", entry.encoding.name(), error))),
+      },
+      (true, true) => {
+        if file.content == scroll {
+          entry.hash = phext::checksum(&scroll);
+        } else {
+          events.push(conflict(&entry, "both changed"));
+        }
+      },
+    }
+    kept.push(entry);
+  }
+
+  for file in on_disk.into_values() {
+    let coordinate = allocate(&scrolls, &kept, file)?;
+    scrolls.set(coordinate, &file.content);
+    kept.push(ManifestEntry {
+      coordinate,
+      mode: file.mode,
+      encoding: file.encoding,
+      hash: phext::checksum(&file.content),
+      path: file.path.clone(),
+    });
+    events.push(SyncEvent::Added { path: file.path.clone(), coordinate });
+  }
+
+  kept.sort_by_key(|entry| query::dimensions(entry.coordinate));
+  let recorded = archive::format_manifest(&archive::Manifest { entries: kept, directories });
+  if recorded != manifest {
+    scrolls.set(manifest_coordinate, &recorded);
+  }
+
+  let updated = match scrolls.changed {
+    true => scrolls.assemble(),
+    false => buffer.to_string(),
+  };
+  return Ok(Reconciled { buffer: updated, writes, events });
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn apply
+///
+/// makes the file changes decided on by `reconcile` beneath `root`
+/// ----------------------------------------------------------------------------------------------------------
+pub fn apply(root: &Path, writes: &[FileWrite]) -> Result<(), String> {
+  for write in writes {
+    match write {
+      FileWrite::Restore { entry, content } => archive::restore(root, entry, content)?,
+      FileWrite::Remove { path } => {
+        if !archive::is_safe_path(path) {
+          return Err(format!("refusing to remove a file outside of {}: {}", root.display(), path));
+        }
+        let removed = root.join(path);
+        std::fs::remove_file(&removed).map_err(|error| format!("unable to remove {}: {}", removed.display(), error))?;
+      },
+    }
+  }
+  return Ok(());
+}
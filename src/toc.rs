@@ -0,0 +1,82 @@
+/// ----------------------------------------------------------------------------------------------------------
+/// Table of Contents
+///
+/// Machine-readable summaries of every populated scroll in a phext, used by the `toc` endpoint and the
+/// `index` sidebar.
+/// ----------------------------------------------------------------------------------------------------------
+use libphext::phext as phext;
+use rocket::serde::Serialize;
+use crate::query;
+use crate::subspace;
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct TocEntry
+///
+/// Summary of one populated scroll
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TocEntry {
+  pub coordinate: String,
+  pub bytes: usize,
+  pub lines: usize,
+  pub title: String,
+  pub hash: String,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @struct TocPage
+///
+/// One page of table of contents entries, along with the total number of entries that matched
+/// ----------------------------------------------------------------------------------------------------------
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TocPage {
+  pub total: usize,
+  pub offset: usize,
+  pub limit: usize,
+  pub entries: Vec<TocEntry>,
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn title
+///
+/// uses the first non-blank line of a scroll as its title
+/// ----------------------------------------------------------------------------------------------------------
+pub fn title(content: &str) -> String {
+  let line = content.lines().map(|line| line.trim()).find(|line| !line.is_empty()).unwrap_or("");
+  return line.to_string();
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn summarize
+///
+/// builds the table of contents entry for a single scroll
+/// ----------------------------------------------------------------------------------------------------------
+pub fn summarize(scroll: &subspace::Scroll) -> TocEntry {
+  return TocEntry {
+    coordinate: scroll.coord.to_string(),
+    bytes: scroll.content.len(),
+    lines: scroll.content.lines().count(),
+    title: title(scroll.content),
+    hash: phext::checksum(scroll.content),
+  };
+}
+
+/// ----------------------------------------------------------------------------------------------------------
+/// @fn page
+///
+/// summarizes the scrolls beneath `prefix`, skipping `offset` entries and returning at most `limit`
+/// ----------------------------------------------------------------------------------------------------------
+pub fn page(buffer: &str, prefix: &query::CoordinatePattern, offset: usize, limit: usize) -> TocPage {
+  let mut total = 0;
+  let mut entries = Vec::new();
+  for scroll in subspace::scrolls(buffer).filter(|scroll| prefix.matches(scroll.coord)) {
+    if total >= offset && entries.len() < limit {
+      entries.push(summarize(&scroll));
+    }
+    total += 1;
+  }
+
+  return TocPage { total, offset, limit, entries };
+}
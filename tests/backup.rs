@@ -0,0 +1,71 @@
+#![allow(clippy::needless_return)]
+
+/// ----------------------------------------------------------------------------------------------------------
+/// Backup Tests
+///
+/// Reads and restores archives built in memory, including ones no `phext backup` would write.
+/// ----------------------------------------------------------------------------------------------------------
+use hello_phext::backup::{self, BackupFile, Format, Manifest, ManifestFile};
+use hello_phext::storage::{FileStorage, Storage};
+
+fn file(name: &str, content: &[u8]) -> BackupFile {
+  return BackupFile { name: name.to_string(), content: content.to_vec() };
+}
+
+/// a tar holding `entries` as given, after a manifest that describes `listed`
+fn tar_with(listed: &[BackupFile], entries: &[BackupFile]) -> Vec<u8> {
+  let manifest = Manifest {
+    version: 1,
+    created: 0,
+    worlds: vec!["world".to_string()],
+    files: listed.iter().map(|file| ManifestFile { name: file.name.clone(), bytes: file.content.len(), crc32: crc32fast::hash(&file.content) }).collect(),
+  };
+  let manifest = rocket::serde::json::to_string(&manifest).unwrap();
+
+  let mut builder = tar::Builder::new(Vec::new());
+  for (name, content) in std::iter::once((backup::MANIFEST_NAME, manifest.as_bytes())).chain(entries.iter().map(|entry| (entry.name.as_str(), entry.content.as_slice()))) {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    builder.append_data(&mut header, name, content).unwrap();
+  }
+  return builder.into_inner().unwrap();
+}
+
+#[test]
+fn reads_refuse_backups_that_unpack_past_the_limit() {
+  let files = vec![file("a.phext", &[b'a'; 1500]), file("b.phext", &[b'b'; 1500]), file("c.phext", &[b'c'; 1500])];
+  for format in [Format::Tar, Format::TarGz, Format::Zip] {
+    let archive = backup::write(&["a".to_string(), "b".to_string(), "c".to_string()], &files, format).unwrap();
+    let error = backup::read_limited(&archive, 2000).unwrap_err();
+    assert_eq!(error, "This backup unpacks to more than 2000 bytes", "{format:?}");
+    let (_, read) = backup::read_limited(&archive, 10_000).unwrap();
+    assert_eq!(read, files, "{format:?}");
+  }
+}
+
+#[test]
+fn reads_refuse_repeated_entries() {
+  let world = file("world.phext", b"original");
+  let archive = tar_with(std::slice::from_ref(&world), &[world.clone(), file("world.phext", b"replaced")]);
+  assert_eq!(backup::read(&archive).unwrap_err(), "world.phext appears more than once in the backup");
+
+  let archive = tar_with(&[world.clone(), world.clone()], std::slice::from_ref(&world));
+  assert_eq!(backup::read(&archive).unwrap_err(), "world.phext is listed more than once in phext-backup.json");
+}
+
+#[test]
+fn restores_check_every_file_before_writing_any() {
+  let dir = tempfile::tempdir().unwrap();
+  let storage = FileStorage::new(dir.path().to_path_buf());
+  let files = vec![
+    file("alpha.phext", b"alpha"),
+    file("alpha.phext.history", b"lp alpha.phext\n"),
+    file("beta.phext", b"bad\xff"),
+  ];
+
+  let error = backup::restore(dir.path(), &storage, &files).unwrap_err();
+  assert_eq!(error, "beta.phext is not valid UTF-8");
+  assert!(storage.worlds().unwrap().is_empty());
+  assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
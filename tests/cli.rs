@@ -0,0 +1,986 @@
+#![allow(clippy::needless_return)]
+
+/// ----------------------------------------------------------------------------------------------------------
+/// CLI Integration Tests
+///
+/// Runs the built `phext` binary against scratch directories and checks its output.
+/// ----------------------------------------------------------------------------------------------------------
+use hello_phext::compression::{self, Codec};
+use hello_phext::subspace;
+use libphext::phext;
+use std::fs;
+use std::path::Path;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn phext(dir: &Path, args: &[&str]) -> Output {
+  return Command::new(env!("CARGO_BIN_EXE_phext"))
+    .args(args)
+    .current_dir(dir)
+    .output()
+    .expect("unable to run phext");
+}
+
+fn stdout(output: &Output) -> String {
+  return String::from_utf8_lossy(&output.stdout).to_string();
+}
+
+fn stderr(output: &Output) -> String {
+  return String::from_utf8_lossy(&output.stderr).to_string();
+}
+
+#[test]
+fn lists_commands_without_arguments() {
+  let dir = tempfile::tempdir().unwrap();
+  let output = phext(dir.path(), &[]);
+  assert!(output.status.success());
+  let text = stdout(&output);
+  assert!(text.contains("available commands:"));
+  for command in ["pack ", "<archive>", "unpack <phext>", "grep ", "help "] {
+    assert!(text.contains(command), "missing {command} in {text}");
+  }
+}
+
+#[test]
+fn rejects_unknown_commands() {
+  let dir = tempfile::tempdir().unwrap();
+  let output = phext(dir.path(), &["frobnicate"]);
+  assert_eq!(output.status.code(), Some(2));
+  assert!(stderr(&output).contains("Unknown command: frobnicate"));
+  assert!(stdout(&output).contains("available commands:"));
+}
+
+#[test]
+fn help_prints_the_reference_phext() {
+  let dir = tempfile::tempdir().unwrap();
+  let output = phext(dir.path(), &["help"]);
+  assert!(output.status.success());
+  let text = stdout(&output);
+  assert!(text.contains("The Reference Phext has 4 scrolls"));
+  for scroll in ["* Quick Example", "* Second Scroll", "* Second Section", "* Section Chapter"] {
+    assert!(text.contains(scroll), "missing {scroll} in {text}");
+  }
+}
+
+#[test]
+fn help_describes_a_single_command() {
+  let dir = tempfile::tempdir().unwrap();
+  let output = phext(dir.path(), &["help", "grep"]);
+  assert!(output.status.success());
+  assert!(stdout(&output).contains("Usage: phext grep"));
+}
+
+#[test]
+fn pack_stores_local_files_in_order_with_a_manifest() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("beta.txt"), "second file").unwrap();
+  fs::write(dir.path().join("alpha.txt"), "first file").unwrap();
+  fs::write(dir.path().join(".gitignore"), "ignored").unwrap();
+  fs::write(dir.path().join("out.phext"), "stale output").unwrap();
+
+  let output = phext(dir.path(), &["pack", "out.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert!(stdout(&output).starts_with("hello-phext v"));
+  assert!(stdout(&output).contains("Packing local files into out.phext..."));
+
+  let packed = fs::read_to_string(dir.path().join("out.phext")).unwrap();
+  let (content, manifest) = packed.split_once('\x01').expect("manifest library");
+  assert_eq!(content, "first file\x17second file");
+  let lines: Vec<&str> = manifest.lines().collect();
+  assert_eq!(lines[0], "# phext manifest v3");
+  assert!(lines[1].starts_with("1.1.1/1.1.1/1.1.1\t") && lines[1].contains("\ttext\t") && lines[1].ends_with("\talpha.txt"));
+  assert!(lines[2].starts_with("1.1.1/1.1.1/1.1.2\t") && lines[2].ends_with("\tbeta.txt"));
+  assert_eq!(lines.len(), 3);
+}
+
+#[test]
+fn pack_requires_an_archive() {
+  let dir = tempfile::tempdir().unwrap();
+  let output = phext(dir.path(), &["pack"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("Missing file parameter for pack."));
+}
+
+#[test]
+fn unpack_restores_packed_files() {
+  let source = tempfile::tempdir().unwrap();
+  fs::write(source.path().join("alpha.txt"), "first file\nwith two lines").unwrap();
+  fs::write(source.path().join("run.sh"), "echo hello").unwrap();
+  fs::write(source.path().join("empty.txt"), "").unwrap();
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(source.path().join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+  }
+  assert!(phext(source.path(), &["pack", "out.phext"]).status.success());
+
+  let target = tempfile::tempdir().unwrap();
+  let archive = source.path().join("out.phext");
+  let output = phext(target.path(), &["unpack", archive.to_str().unwrap(), "restored"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert!(stdout(&output).starts_with("hello-phext v"));
+
+  let restored = target.path().join("restored");
+  assert_eq!(fs::read_to_string(restored.join("alpha.txt")).unwrap(), "first file\nwith two lines");
+  assert_eq!(fs::read_to_string(restored.join("run.sh")).unwrap(), "echo hello");
+  assert_eq!(fs::read_to_string(restored.join("empty.txt")).unwrap(), "");
+  assert!(!restored.join("out.phext").exists());
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    assert_eq!(fs::metadata(restored.join("run.sh")).unwrap().permissions().mode() & 0o777, 0o755);
+  }
+}
+
+#[test]
+fn pack_encodes_binary_files_and_delimiters() {
+  let binary: Vec<u8> = (0..=255).collect();
+  let delimited = "before\x17after\x01library".as_bytes();
+  let source = tempfile::tempdir().unwrap();
+  fs::write(source.path().join("binary.bin"), &binary).unwrap();
+  fs::write(source.path().join("delimited.txt"), delimited).unwrap();
+  fs::write(source.path().join("plain.txt"), "plain text").unwrap();
+
+  let output = phext(source.path(), &["pack", "out.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let text = stdout(&output);
+  assert!(text.contains("1.1.1/1.1.1/1.1.1: binary.bin (base64)"), "{text}");
+  assert!(text.contains("1.1.1/1.1.1/1.1.2: delimited.txt (base64)"), "{text}");
+  assert!(text.contains("1.1.1/1.1.1/1.1.3: plain.txt\n"), "{text}");
+
+  let packed = fs::read_to_string(source.path().join("out.phext")).unwrap();
+  let (content, _manifest) = packed.split_once('\x01').expect("manifest library");
+  assert_eq!(content.matches('\x17').count(), 2);
+
+  let target = tempfile::tempdir().unwrap();
+  let archive = source.path().join("out.phext");
+  let output = phext(target.path(), &["unpack", archive.to_str().unwrap()]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(fs::read(target.path().join("binary.bin")).unwrap(), binary);
+  assert_eq!(fs::read(target.path().join("delimited.txt")).unwrap(), delimited);
+  assert_eq!(fs::read_to_string(target.path().join("plain.txt")).unwrap(), "plain text");
+}
+
+#[test]
+fn unpack_refuses_paths_outside_the_target() {
+  let dir = tempfile::tempdir().unwrap();
+  let manifest = "# phext manifest v1\n1.1.1/1.1.1/1.1.1\t0644\tdeadbeef\t../escaped.txt";
+  fs::write(dir.path().join("evil.phext"), format!("payload\x01{manifest}")).unwrap();
+  fs::create_dir(dir.path().join("target")).unwrap();
+
+  let output = phext(dir.path(), &["unpack", "evil.phext", "target"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("refusing to write outside of target: ../escaped.txt"));
+  assert!(!dir.path().join("escaped.txt").exists());
+}
+
+#[test]
+fn unpack_verifies_checksums() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("alpha.txt"), "first file").unwrap();
+  assert!(phext(dir.path(), &["pack", "out.phext"]).status.success());
+  let packed = fs::read_to_string(dir.path().join("out.phext")).unwrap();
+  fs::write(dir.path().join("out.phext"), packed.replace("first file", "tampered")).unwrap();
+
+  let output = phext(dir.path(), &["unpack", "out.phext", "target"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("checksum mismatch for alpha.txt at 1.1.1/1.1.1/1.1.1"));
+  assert!(!dir.path().join("target").join("alpha.txt").exists());
+}
+
+#[test]
+fn unpack_requires_a_manifest() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("plain.phext"), "just a scroll").unwrap();
+  let output = phext(dir.path(), &["unpack", "plain.phext"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("no manifest found at 2.1.1/1.1.1/1.1.1"));
+}
+
+fn write_tree(root: &Path, files: &[(&str, &str)]) {
+  for (path, content) in files {
+    let location = root.join(path);
+    fs::create_dir_all(location.parent().unwrap()).unwrap();
+    fs::write(location, content).unwrap();
+  }
+}
+
+#[test]
+fn pack_maps_directories_onto_higher_dimensions() {
+  let dir = tempfile::tempdir().unwrap();
+  write_tree(dir.path(), &[
+    ("top.txt", "top"),
+    ("docs/guide.txt", "guide"),
+    ("docs/deep/leaf.txt", "leaf"),
+    ("docs/deep/other.txt", "other"),
+    ("src/main.rs", "fn main() {}"),
+  ]);
+
+  let output = phext(dir.path(), &["pack", "out.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let text = stdout(&output);
+  for line in [
+    "1.1.1/1.1.1/1.1.1: top.txt",
+    "1.1.1/1.1.1/2.1.1: docs/guide.txt",
+    "1.1.1/1.1.1/2.2.1: docs/deep/leaf.txt",
+    "1.1.1/1.1.1/2.2.2: docs/deep/other.txt",
+    "1.1.1/1.1.1/3.1.1: src/main.rs",
+  ] {
+    assert!(text.contains(line), "missing {line} in {text}");
+  }
+
+  let first = fs::read(dir.path().join("out.phext")).unwrap();
+  assert!(phext(dir.path(), &["pack", "out.phext"]).status.success());
+  assert_eq!(fs::read(dir.path().join("out.phext")).unwrap(), first);
+}
+
+#[test]
+fn pack_then_unpack_round_trips_a_nested_tree() {
+  let files = [
+    ("README.md", "# nested\n"),
+    ("a/one.txt", "one"),
+    ("a/b/two.txt", "two"),
+    ("a/b/c/three.txt", "three\nlines\n"),
+    ("a/b/c/d/four.txt", "four"),
+    ("z/last.txt", "last"),
+    ("z/empty.txt", ""),
+  ];
+  let source = tempfile::tempdir().unwrap();
+  write_tree(source.path(), &files);
+  assert!(phext(source.path(), &["pack", "tree.phext"]).status.success());
+
+  let target = tempfile::tempdir().unwrap();
+  let archive = source.path().join("tree.phext");
+  let output = phext(target.path(), &["unpack", archive.to_str().unwrap()]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  for (path, content) in files {
+    assert_eq!(fs::read_to_string(target.path().join(path)).unwrap(), content, "{path}");
+  }
+}
+
+#[test]
+fn pack_rejects_trees_deeper_than_a_phext() {
+  let dir = tempfile::tempdir().unwrap();
+  write_tree(dir.path(), &[("1/2/3/4/5/6/7/8/deep.txt", "too deep")]);
+  let output = phext(dir.path(), &["pack", "out.phext"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("nested 8 levels deep"));
+}
+
+#[test]
+fn unpack_reads_empty_scrolls_at_unpopulated_coordinates() {
+  let source = tempfile::tempdir().unwrap();
+  write_tree(source.path(), &[("a.txt", "one"), ("b.txt", "two"), ("c.txt", ""), ("d/e.txt", "three")]);
+  assert!(phext(source.path(), &["pack", "out.phext"]).status.success());
+
+  // c.txt has no scroll of its own; move it past the end of its library as well
+  let packed = fs::read_to_string(source.path().join("out.phext")).unwrap();
+  let moved = packed.replace("1.1.1/1.1.1/1.1.3\t", "1.1.1/1.1.1/9.9.9\t");
+  fs::write(source.path().join("moved.phext"), &moved).unwrap();
+
+  for archive in ["out.phext", "moved.phext"] {
+    let target = tempfile::tempdir().unwrap();
+    let output = phext(target.path(), &["unpack", source.path().join(archive).to_str().unwrap()]);
+    assert!(output.status.success(), "{archive}: {}", stderr(&output));
+    for (path, content) in [("a.txt", "one"), ("b.txt", "two"), ("c.txt", ""), ("d/e.txt", "three")] {
+      assert_eq!(fs::read_to_string(target.path().join(path)).unwrap(), content, "{archive}: {path}");
+    }
+  }
+}
+
+#[cfg(unix)]
+#[test]
+fn pack_rejects_names_with_tabs_or_line_breaks() {
+  for name in ["tab\there.txt", "line\nbreak.txt"] {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join(name), "content").unwrap();
+    let output = phext(dir.path(), &["pack", "out.phext"]);
+    assert!(!output.status.success(), "{name}");
+    assert!(stderr(&output).contains("names may not contain tabs or line breaks"), "{}", stderr(&output));
+    assert!(!dir.path().join("out.phext").exists());
+  }
+}
+
+#[test]
+fn pack_then_unpack_restores_empty_directories() {
+  let source = tempfile::tempdir().unwrap();
+  write_tree(source.path(), &[("full/file.txt", "content")]);
+  fs::create_dir_all(source.path().join("empty")).unwrap();
+  fs::create_dir_all(source.path().join("outer/inner")).unwrap();
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(source.path().join("empty"), fs::Permissions::from_mode(0o700)).unwrap();
+  }
+
+  let output = phext(source.path(), &["pack", "out.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let text = stdout(&output);
+  for line in ["directory: empty/", "directory: outer/", "directory: outer/inner/"] {
+    assert!(text.contains(line), "missing {line} in {text}");
+  }
+  let packed = fs::read_to_string(source.path().join("out.phext")).unwrap();
+  assert!(packed.contains("\ndirectory\t0700\tempty"), "{packed}");
+
+  let target = tempfile::tempdir().unwrap();
+  let output = phext(target.path(), &["unpack", source.path().join("out.phext").to_str().unwrap()]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert!(stdout(&output).contains("directory: outer/inner/"));
+  assert_eq!(fs::read_to_string(target.path().join("full/file.txt")).unwrap(), "content");
+  assert!(target.path().join("empty").is_dir());
+  assert!(target.path().join("outer/inner").is_dir());
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    assert_eq!(fs::metadata(target.path().join("empty")).unwrap().permissions().mode() & 0o777, 0o700);
+  }
+}
+
+#[test]
+fn unpack_refuses_directories_outside_the_target() {
+  let dir = tempfile::tempdir().unwrap();
+  let manifest = "# phext manifest v2\ndirectory\t0755\t../escaped";
+  fs::write(dir.path().join("evil.phext"), format!("payload\x01{manifest}")).unwrap();
+  fs::create_dir(dir.path().join("target")).unwrap();
+
+  let output = phext(dir.path(), &["unpack", "evil.phext", "target"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("refusing to write outside of target: ../escaped"));
+  assert!(!dir.path().join("escaped").exists());
+}
+
+#[test]
+fn pack_honors_phextignore_and_globs() {
+  let dir = tempfile::tempdir().unwrap();
+  write_tree(dir.path(), &[
+    (".phextignore", "target/\n*.log\n!keep.log\n"),
+    (".gitignore", "secret.txt\n"),
+    ("keep.log", "kept"),
+    ("noise.log", "noise"),
+    ("secret.txt", "secret"),
+    ("notes.md", "notes"),
+    ("src/lib.rs", "lib"),
+    ("src/lib.md", "lib docs"),
+    ("target/debug/junk.rs", "junk"),
+    ("vendor/dep.rs", "dep"),
+  ]);
+
+  let output = phext(dir.path(), &["pack", "--dry-run", "out.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let text = stdout(&output);
+  assert!(text.contains("Planning to pack local files into out.phext..."));
+  for path in [".phextignore", "keep.log", "secret.txt", "notes.md", "src/lib.rs", "vendor/dep.rs"] {
+    assert!(text.contains(&format!(": {path}\n")), "missing {path} in {text}");
+  }
+  for path in ["noise.log", "junk.rs", ".gitignore"] {
+    assert!(!text.contains(path), "unexpected {path} in {text}");
+  }
+  assert!(!dir.path().join("out.phext").exists());
+
+  let output = phext(dir.path(), &["pack", "--dry-run", "--gitignore", "--include", "*.rs", "--exclude", "vendor", "out.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let text = stdout(&output);
+  assert!(text.contains("1.1.1/1.1.1/1.2.1: src/lib.rs\n"), "{text}");
+  for path in ["secret.txt", "notes.md", "lib.md", "dep.rs", "junk.rs"] {
+    assert!(!text.contains(path), "unexpected {path} in {text}");
+  }
+}
+
+fn phext_with_input(dir: &Path, args: &[&str], input: &str) -> Output {
+  let mut child = Command::new(env!("CARGO_BIN_EXE_phext"))
+    .args(args)
+    .current_dir(dir)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .expect("unable to run phext");
+  child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+  return child.wait_with_output().expect("unable to wait for phext");
+}
+
+#[test]
+fn scroll_commands_edit_a_local_phext() {
+  let dir = tempfile::tempdir().unwrap();
+  assert!(phext_with_input(dir.path(), &["put", "world.phext", "1.1.1/1.1.1/1.1.1"], "first scroll").status.success());
+  assert!(phext_with_input(dir.path(), &["put", "world.phext", "1.1.1;1.2.1;1.1.2"], "Title\nbody").status.success());
+
+  let output = phext_with_input(dir.path(), &["put", "world.phext", "1.1.1/1.1.1/1.1.2"], "split\x17scroll");
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("Scrolls may not contain phext delimiters"));
+  let output = phext(dir.path(), &["cat", "world.phext", "1.1.1/1.1"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("Invalid coordinate: 1.1.1/1.1"));
+
+  let output = phext(dir.path(), &["cat", "world.phext", "1.1.1/1.2.1/1.1.2"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(stdout(&output), "Title\nbody");
+
+  let output = phext(dir.path(), &["ls", "world.phext"]);
+  assert_eq!(stdout(&output), "1.1.1/1.1.1/1.1.1\t12\tfirst scroll\n1.1.1/1.2.1/1.1.2\t10\tTitle\n");
+  let output = phext(dir.path(), &["ls", "world.phext", "1.1.1/1.2"]);
+  assert_eq!(stdout(&output), "1.1.1/1.2.1/1.1.2\t10\tTitle\n");
+
+  let output = phext(dir.path(), &["tree", "world.phext"]);
+  let text = stdout(&output);
+  assert!(text.starts_with("library 1\n  shelf 1\n    series 1\n      collection 1\n        volume 1\n"), "{text}");
+  assert!(text.contains("                scroll 1 (12 bytes): first scroll\n        volume 2\n          book 1\n"), "{text}");
+  assert!(text.ends_with("                scroll 2 (10 bytes): Title\n"), "{text}");
+
+  assert!(phext(dir.path(), &["rm", "world.phext", "1.1.1/1.1.1/1.1.1"]).status.success());
+  let output = phext(dir.path(), &["ls", "world.phext"]);
+  assert_eq!(stdout(&output), "1.1.1/1.2.1/1.1.2\t10\tTitle\n");
+  let output = phext(dir.path(), &["rm", "world.phext", "1.1.1/1.1.1/1.1.1"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("No scroll found at 1.1.1/1.1.1/1.1.1"));
+}
+
+#[test]
+fn scroll_commands_leave_neighbouring_scrolls_in_place() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("world.phext"), "one\x17two\x18three").unwrap();
+
+  // 1.1.3 is unpopulated and 9.1.1 is past the end, so neither has any content
+  for coordinate in ["1.1.1/1.1.1/1.1.3", "1.1.1/1.1.1/9.1.1"] {
+    let output = phext(dir.path(), &["cat", "world.phext", coordinate]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "", "{coordinate}");
+    let output = phext(dir.path(), &["rm", "world.phext", coordinate]);
+    assert!(stderr(&output).contains(&format!("No scroll found at {coordinate}")));
+  }
+  assert_eq!(fs::read_to_string(dir.path().join("world.phext")).unwrap(), "one\x17two\x18three");
+
+  assert!(phext_with_input(dir.path(), &["put", "world.phext", "1.1.1/1.1.1/1.1.3"], "gap").status.success());
+  assert_eq!(fs::read_to_string(dir.path().join("world.phext")).unwrap(), "one\x17two\x17gap\x18three");
+  assert!(phext_with_input(dir.path(), &["put", "world.phext", "1.1.1/1.1.1/9.1.1"], "end").status.success());
+  assert_eq!(fs::read_to_string(dir.path().join("world.phext")).unwrap(), "one\x17two\x17gap\x18three\x19\x19\x19\x19\x19\x19\x19\x19end");
+
+  assert!(phext(dir.path(), &["rm", "world.phext", "1.1.1/1.1.1/1.1.2"]).status.success());
+  assert!(phext(dir.path(), &["rm", "world.phext", "1.1.1/1.1.1/9.1.1"]).status.success());
+  assert_eq!(fs::read_to_string(dir.path().join("world.phext")).unwrap(), "one\x17\x17gap\x18three");
+  for (coordinate, content) in [("1.1.1/1.1.1/1.1.1", "one"), ("1.1.1/1.1.1/1.1.3", "gap"), ("1.1.1/1.1.1/1.2.1", "three")] {
+    assert_eq!(stdout(&phext(dir.path(), &["cat", "world.phext", coordinate])), content);
+  }
+}
+
+#[test]
+fn shell_edits_a_world_and_records_its_history() {
+  let dir = tempfile::tempdir().unwrap();
+  let commands = "lp notes\nes hello\ncs 1.1.1/1.1.1/1.2.1\nes\nfirst line\nsecond line\n.\nbogus\nps\nds\nns\nls\nquit\n";
+  let output = phext_with_input(dir.path(), &["shell"], commands);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(stdout(&output), "1.1.1/1.1.1/1.1.1\nhello\n1.1.1/1.1.1/1.2.1\n1.1.1/1.1.1/1.1.1\t5\thello\n1.1.1/1.1.1/1.2.1\t22\tfirst line\n");
+  assert!(stderr(&output).contains("Unknown command: bogus"));
+  assert_eq!(fs::read_to_string(dir.path().join("notes.phext")).unwrap(), "hello\x18first line\nsecond line");
+
+  let history = fs::read_to_string(dir.path().join("notes.phext.history")).unwrap();
+  assert_eq!(history, commands.replace("bogus\n", ""));
+}
+
+#[test]
+fn shell_replays_a_history_script() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("world.phext"), "one\x17two\x17three").unwrap();
+  fs::write(dir.path().join("world.phext.history"), "lp world.phext\nds\x17\ncs 1.1.1/1.1.1/1.1.3\x17\nds\nes replaced\nquit\x17\nlp world.phext\nds\n").unwrap();
+
+  let output = phext(dir.path(), &["shell", "--script", "world.phext.history"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(stdout(&output), "one\nthree\none\n");
+  assert_eq!(fs::read_to_string(dir.path().join("world.phext")).unwrap(), "one\x17two\x17replaced");
+
+  fs::write(dir.path().join("broken.history"), "lp world.phext\ncs 1.1\nds\n").unwrap();
+  let output = phext(dir.path(), &["shell", "--script", "broken.history"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("cs 1.1: Invalid coordinate: 1.1"));
+
+  // quit ends a session, so the next one starts without a loaded phext
+  fs::write(dir.path().join("sessions.history"), "lp world.phext\nquit\nds\n").unwrap();
+  let output = phext(dir.path(), &["shell", "--script", "sessions.history"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("ds: No phext loaded"));
+}
+
+#[test]
+fn shell_replays_the_sample_history_session_by_session() {
+  let dir = tempfile::tempdir().unwrap();
+  let repository = Path::new(env!("CARGO_MANIFEST_DIR"));
+  for file in ["world.phext", "world-sample.phext", "world-sample.phext.history"] {
+    fs::copy(repository.join(file), dir.path().join(file)).unwrap();
+  }
+
+  let output = phext(dir.path(), &["shell", "--script", "world-sample.phext.history"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let first = |file: &str| {
+    let buffer = fs::read_to_string(dir.path().join(file)).unwrap();
+    let scroll = subspace::fetch(&buffer, phext::default_coordinate()).to_string();
+    return match scroll.ends_with('\n') {
+      true => scroll,
+      false => scroll + "\n",
+    };
+  };
+  assert_eq!(stdout(&output), first("world.phext").repeat(2) + &first("world-sample.phext"));
+  for file in ["world.phext", "world-sample.phext"] {
+    assert_eq!(fs::read(dir.path().join(file)).unwrap(), fs::read(repository.join(file)).unwrap(), "{file}");
+  }
+}
+
+#[test]
+fn shell_edits_scrolls_at_unpopulated_coordinates_in_place() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("world.phext"), "one\x17two\x18three").unwrap();
+  let commands = [
+    "lp world.phext",
+    "cs 1.1.1/1.1.1/1.1.3", "ds", "es gap", "is !",
+    "cs 1.1.1/1.1.1/9.1.1", "ds", "rm", "edit end",
+    "cs 1.1.1/1.1.1/1.1.2", "delete",
+    "cs 1.1.1/1.1.1/1.2.1", "ds",
+  ];
+  fs::write(dir.path().join("edits.history"), commands.join("\n")).unwrap();
+
+  let output = phext(dir.path(), &["shell", "--script", "edits.history"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(stdout(&output), "\n\nthree\n");
+  assert_eq!(fs::read_to_string(dir.path().join("world.phext")).unwrap(), "one\x17\x17gap!\x18three\x19\x19\x19\x19\x19\x19\x19\x19end");
+}
+
+#[test]
+fn grep_reports_coordinates() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("world.phext"), "hello\x17nothing here\x18Hello again").unwrap();
+
+  let output = phext(dir.path(), &["grep", "-i", "hello", "world.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let text = stdout(&output);
+  assert!(text.contains("1.1.1/1.1.1/1.1.1:1:1:hello"));
+  assert!(text.contains("1.1.1/1.1.1/1.2.1:1:1:Hello again"));
+  assert!(!text.contains("nothing here"));
+
+  let output = phext(dir.path(), &["grep", "--start", "1.1.1/1.1.1/1.2", "hello", "world.phext"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("Invalid coordinate: 1.1.1/1.1.1/1.2"), "{}", stderr(&output));
+}
+
+#[test]
+fn serve_requires_an_existing_root() {
+  let dir = tempfile::tempdir().unwrap();
+  let output = phext(dir.path(), &["serve", "--root", "missing"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("missing is not a directory"));
+
+  let output = phext(dir.path(), &["serve", "--port", "http"]);
+  assert!(stderr(&output).contains("Invalid port: http"));
+}
+
+#[test]
+fn sync_copies_edits_in_both_directions() {
+  let dir = tempfile::tempdir().unwrap();
+  write_tree(dir.path(), &[("docs/a.txt", "alpha"), ("docs/sub/b.txt", "beta")]);
+  assert!(phext(dir.path(), &["sync", "--once", "world.phext", "docs"]).status.success());
+  assert_eq!(stdout(&phext(dir.path(), &["cat", "world.phext", "1.1.1/1.1.1/1.2.1"])), "beta");
+
+  fs::write(dir.path().join("docs/a.txt"), "alpha two").unwrap();
+  assert!(phext_with_input(dir.path(), &["put", "world.phext", "1.1.1/1.1.1/1.2.1"], "beta two").status.success());
+  let output = phext(dir.path(), &["sync", "--once", "world.phext", "docs"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert!(stdout(&output).contains("1.1.1/1.1.1/1.1.1: updated from a.txt"));
+  assert!(stdout(&output).contains("sub/b.txt: updated from 1.1.1/1.1.1/1.2.1"));
+  assert_eq!(stdout(&phext(dir.path(), &["cat", "world.phext", "1.1.1/1.1.1/1.1.1"])), "alpha two");
+  assert_eq!(fs::read_to_string(dir.path().join("docs/sub/b.txt")).unwrap(), "beta two");
+}
+
+#[test]
+fn sync_tracks_added_and_removed_files() {
+  let dir = tempfile::tempdir().unwrap();
+  write_tree(dir.path(), &[("a.txt", "alpha"), ("b.txt", "beta")]);
+  assert!(phext(dir.path(), &["sync", "--once", "world.phext"]).status.success());
+
+  fs::remove_file(dir.path().join("a.txt")).unwrap();
+  write_tree(dir.path(), &[("c.txt", "gamma")]);
+  let output = phext(dir.path(), &["sync", "--once", "world.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert!(stdout(&output).contains("1.1.1/1.1.1/1.1.1: cleared (a.txt was removed)"));
+  assert!(stdout(&output).contains("added c.txt"));
+  assert!(!stdout(&output).contains("world.phext"));
+
+  let listing = stdout(&phext(dir.path(), &["ls", "world.phext", "1.1.1/1.1.1/1.1"]));
+  assert!(!listing.contains("alpha"));
+  assert!(listing.contains("beta") && listing.contains("gamma"));
+
+  assert!(phext(dir.path(), &["rm", "world.phext", "1.1.1/1.1.1/1.1.2"]).status.success());
+  let output = phext(dir.path(), &["sync", "--once", "world.phext"]);
+  assert!(stdout(&output).contains("b.txt: removed (1.1.1/1.1.1/1.1.2 was cleared)"));
+  assert!(!dir.path().join("b.txt").exists());
+}
+
+#[test]
+fn sync_reports_conflicts_without_overwriting() {
+  let dir = tempfile::tempdir().unwrap();
+  write_tree(dir.path(), &[("docs/a.txt", "alpha")]);
+  assert!(phext(dir.path(), &["sync", "--once", "world.phext", "docs"]).status.success());
+
+  fs::write(dir.path().join("docs/a.txt"), "from the editor").unwrap();
+  assert!(phext_with_input(dir.path(), &["put", "world.phext", "1.1.1/1.1.1/1.1.1"], "from an agent").status.success());
+  let output = phext(dir.path(), &["sync", "--once", "world.phext", "docs"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("conflict: a.txt and 1.1.1/1.1.1/1.1.1 both changed"));
+  assert!(stderr(&output).contains("1 unresolved conflict(s)"));
+  assert_eq!(fs::read_to_string(dir.path().join("docs/a.txt")).unwrap(), "from the editor");
+  assert_eq!(stdout(&phext(dir.path(), &["cat", "world.phext", "1.1.1/1.1.1/1.1.1"])), "from an agent");
+
+  fs::write(dir.path().join("docs/a.txt"), "from an agent").unwrap();
+  assert!(phext(dir.path(), &["sync", "--once", "world.phext", "docs"]).status.success());
+}
+
+#[test]
+fn sync_restores_a_missing_directory() {
+  let dir = tempfile::tempdir().unwrap();
+  write_tree(dir.path(), &[("docs/a.txt", "alpha")]);
+  assert!(phext(dir.path(), &["sync", "--once", "world.phext", "docs"]).status.success());
+  fs::remove_dir_all(dir.path().join("docs")).unwrap();
+
+  let output = phext(dir.path(), &["sync", "--once", "world.phext", "docs"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(fs::read_to_string(dir.path().join("docs/a.txt")).unwrap(), "alpha");
+  assert_eq!(stdout(&phext(dir.path(), &["cat", "world.phext", "1.1.1/1.1.1/1.1.1"])), "alpha");
+}
+
+#[test]
+fn fsck_reports_problems_and_repairs_a_copy() {
+  let dir = tempfile::tempdir().unwrap();
+  assert!(phext_with_input(dir.path(), &["put", "clean.phext", "1.1.1/1.1.1/1.1.2"], "fine").status.success());
+  let output = phext(dir.path(), &["fsck", "clean.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert!(stdout(&output).contains("clean.phext: 0 error(s), 0 warning(s)"));
+
+  let broken = b"one\x17t\xffwo\x05\x17\x17\x18three\r\n";
+  fs::write(dir.path().join("broken.phext"), broken).unwrap();
+  let output = phext(dir.path(), &["fsck", "broken.phext"]);
+  assert!(!output.status.success());
+  let text = stdout(&output);
+  assert!(text.contains("error: invalid UTF-8 sequences (1): 1.1.1/1.1.1/1.1.2"), "{text}");
+  assert!(text.contains("error: control characters outside of the phext delimiter set (1): 1.1.1/1.1.1/1.1.2"), "{text}");
+  assert!(text.contains("warning: CRLF line endings (1): 1.1.1/1.1.1/1.2.1"), "{text}");
+  assert!(text.contains("warning: delimiters that phext::normalize would remove (2): 1.1.1/1.1.1/1.2.1"), "{text}");
+  assert!(stderr(&output).contains("broken.phext: 2 error(s), 2 warning(s)"));
+
+  let output = phext(dir.path(), &["fsck", "--repair", "fixed.phext", "broken.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert!(stdout(&output).contains("fixed.phext: 0 error(s), 0 warning(s)"));
+  assert_eq!(fs::read_to_string(dir.path().join("fixed.phext")).unwrap(), "one\x17t\u{fffd}wo\x18three\n");
+  assert_eq!(fs::read(dir.path().join("broken.phext")).unwrap(), broken);
+}
+
+#[test]
+fn fsck_flags_unreachable_coordinates() {
+  let dir = tempfile::tempdir().unwrap();
+  let world = "first".to_string() + &"\x17".repeat(150) + "far" + &"\x18".repeat(1200) + "farther";
+  fs::write(dir.path().join("world.phext"), world).unwrap();
+  let output = phext(dir.path(), &["fsck", "world.phext"]);
+  assert!(!output.status.success());
+  let text = stdout(&output);
+  assert!(text.contains("error: scrolls beyond coordinate 99, which libphext folds onto 99 (and the editor lets you address up to 1000) (2): 1.1.1/1.1.1/1.1.151 1.1.1/1.1.1/1.1201.1"), "{text}");
+  assert!(stderr(&output).contains("world.phext: 1 error(s), 1 warning(s)"));
+}
+
+#[test]
+fn fsck_verifies_manifest_checksums() {
+  let dir = tempfile::tempdir().unwrap();
+  write_tree(dir.path(), &[("a.txt", "alpha"), ("b.txt", "line\r\nending")]);
+  assert!(phext(dir.path(), &["pack", "world.phext"]).status.success());
+  let output = phext(dir.path(), &["fsck", "--repair", "fixed.phext", "world.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert!(phext(dir.path(), &["unpack", "fixed.phext", "out"]).status.success());
+  assert_eq!(fs::read_to_string(dir.path().join("out/b.txt")).unwrap(), "line\nending");
+
+  assert!(phext_with_input(dir.path(), &["put", "world.phext", "1.1.1/1.1.1/1.1.1"], "tampered").status.success());
+  let output = phext(dir.path(), &["fsck", "world.phext"]);
+  assert!(!output.status.success());
+  assert!(stdout(&output).contains("error: scrolls that no longer match their manifest checksum (1): 1.1.1/1.1.1/1.1.1"));
+}
+
+#[test]
+fn remote_rejects_unknown_operations_and_coordinates() {
+  let dir = tempfile::tempdir().unwrap();
+  let output = phext(dir.path(), &["remote", "http://127.0.0.1:1", "rename", "world"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("Unknown remote operation: rename world"));
+
+  let output = phext(dir.path(), &["remote", "http://127.0.0.1:1", "select", "world", "1.1.1"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("Invalid coordinate: 1.1.1"));
+}
+
+#[test]
+fn json_conversion_round_trips() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("world.phext"), "first\x17\x18second").unwrap();
+
+  let output = phext(dir.path(), &["to-json", "world.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert!(stdout(&output).starts_with(r#"[{"coordinate":"1.1.1/1.1.1/1.1.1","content":"first"}"#));
+
+  let output = phext(dir.path(), &["to-json", "world.phext", "world.json", "--nested"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let output = phext(dir.path(), &["from-json", "world.json", "copy.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(stdout(&output), "copy.phext: 2 scrolls\n");
+  assert_eq!(fs::read_to_string(dir.path().join("copy.phext")).unwrap(), "first\x17\x18second");
+}
+
+#[test]
+fn markdown_export_nests_scrolls_by_dimension() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("world.phext"), "# Intro\x17second\x18# Section 2\x01far away").unwrap();
+
+  let output = phext(dir.path(), &["to-markdown", "world.phext", "docs"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(stdout(&output), "Wrote 4 scrolls to docs\n");
+  let section = dir.path().join("docs/01/01/01/01/01/01/01/02/01.md");
+  assert_eq!(fs::read_to_string(section).unwrap(), "---\ncoordinate: 1.1.1/1.1.1/1.2.1\n---\n# Section 2");
+  assert!(dir.path().join("docs/02/01/01/01/01/01/01/01/01.md").exists());
+
+  let output = phext(dir.path(), &["to-markdown", "world.phext", "docs"]);
+  assert!(stderr(&output).contains("docs already exists and is not empty"));
+
+  let output = phext(dir.path(), &["to-markdown", "--start", "1.1.1/1.1.1/1.1.2", "--end", "1.1.1;1.1.1;1.2.1", "world.phext", "range"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(stdout(&output), "Wrote 2 scrolls to range\n");
+  assert!(!dir.path().join("range/01/01/01/01/01/01/01/01/01.md").exists());
+}
+
+#[test]
+fn markdown_import_reads_coordinates_from_front_matter() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("world.phext"), "first\x17second\x18third").unwrap();
+  assert!(phext(dir.path(), &["to-markdown", "world.phext", "docs"]).status.success());
+
+  let output = phext(dir.path(), &["from-markdown", "docs", "copy.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(fs::read_to_string(dir.path().join("copy.phext")).unwrap(), "first\x17second\x18third");
+
+  // files are placed by their front matter, wherever they live, and empty files clear their scroll
+  let docs = dir.path().join("docs/01/01/01/01/01/01/01");
+  fs::rename(docs.join("02/01.md"), dir.path().join("docs/renamed.md")).unwrap();
+  fs::write(docs.join("01/02.md"), "---\ncoordinate: 1.1.1/1.1.1/1.1.2\n---\n").unwrap();
+  fs::write(dir.path().join("docs/new.md"), "---\ntitle: New\ncoordinate: \"1.1.1;1.1.1;1.1.3\"\n---\nadded\n").unwrap();
+  fs::write(dir.path().join("docs/notes.txt"), "ignored").unwrap();
+  let output = phext(dir.path(), &["from-markdown", "docs", "copy.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(fs::read_to_string(dir.path().join("copy.phext")).unwrap(), "first\x17\x17added\n\x18third");
+
+  // --merge keeps scrolls that the folder doesn't mention
+  fs::write(dir.path().join("partial.phext"), "old\x17keep\x19other").unwrap();
+  let partial = tempfile::tempdir_in(dir.path()).unwrap();
+  fs::write(partial.path().join("one.md"), "---\ncoordinate: 1.1.1/1.1.1/1.1.1\n---\nnew").unwrap();
+  let output = phext(dir.path(), &["from-markdown", "--merge", partial.path().to_str().unwrap(), "partial.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(fs::read_to_string(dir.path().join("partial.phext")).unwrap(), "new\x17keep\x19other");
+  fs::write(partial.path().join("one.md"), "---\ncoordinate: 1.1.1/1.1.1/1.1.1\n---\nnewer").unwrap();
+  let output = phext(dir.path(), &["from-markdown", partial.path().to_str().unwrap(), "partial.phext", "--merge"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(fs::read_to_string(dir.path().join("partial.phext")).unwrap(), "newer\x17keep\x19other");
+
+  fs::write(partial.path().join("two.md"), "---\ncoordinate: 1.1.1/1.1.1/1.1.1\n---\nclash").unwrap();
+  let output = phext(dir.path(), &["from-markdown", partial.path().to_str().unwrap(), "clash.phext"]);
+  assert!(stderr(&output).contains("are both at 1.1.1/1.1.1/1.1.1"), "{}", stderr(&output));
+  fs::write(partial.path().join("two.md"), "no front matter").unwrap();
+  let output = phext(dir.path(), &["from-markdown", partial.path().to_str().unwrap(), "clash.phext"]);
+  assert!(stderr(&output).contains("two.md: missing front matter"), "{}", stderr(&output));
+}
+
+#[test]
+fn site_renders_linked_pages_and_a_search_index() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("world.phext"), "Intro\x17Second <b>\x18Section two\x17More\x19Chapter two").unwrap();
+
+  let output = phext(dir.path(), &["site", "world.phext", "public"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(stdout(&output), "Wrote 5 pages to public\n");
+
+  let public = dir.path().join("public");
+  let index = fs::read_to_string(public.join("index.html")).unwrap();
+  assert!(index.contains("<a href='pages/1.1.1-1.1.1-1.2.1.html'"), "{}", index);
+  assert!(index.contains("1.1.1/1.1.1/1.1.2 Second &lt;b&gt;"));
+  assert!(!index.contains("href='/"), "links must be relative: {}", index);
+  assert!(public.join("style.css").exists());
+
+  let page = fs::read_to_string(public.join("pages/1.1.1-1.1.1-1.1.2.html")).unwrap();
+  assert!(page.contains("<pre class='scroll'>Second &lt;b&gt;</pre>"));
+  assert!(page.contains("<link rel='stylesheet' href='../style.css' />"));
+  // scroll steps to the neighbouring scrolls, section to the start of the next section, chapter to the next chapter
+  let row = |name: &str| page.lines().find(|line| line.contains(&format!("<td>{name}</td>"))).unwrap().to_string();
+  assert!(row("scroll").contains("href='1.1.1-1.1.1-1.1.1.html'") && row("scroll").contains("href='1.1.1-1.1.1-1.2.1.html'"));
+  assert!(row("section").contains("<span class='disabled'>&laquo; Prev</span>") && row("section").contains("href='1.1.1-1.1.1-1.2.1.html'"));
+  assert!(row("chapter").contains("href='1.1.1-1.1.1-2.1.1.html'"));
+  assert!(row("library").contains("<span class='disabled'>Next &raquo;</span>"));
+
+  let search = fs::read_to_string(public.join("search.json")).unwrap();
+  assert!(search.contains(r#"{"coordinate":"1.1.1/1.1.1/1.2.1","title":"Section two","page":"pages/1.1.1-1.1.1-1.2.1.html"}"#), "{}", search);
+  assert!(search.contains(r#""two":[[2,1],[4,1]]"#), "{}", search);
+
+  let output = phext(dir.path(), &["site", "world.phext", "public"]);
+  assert!(stderr(&output).contains("public already exists and is not empty"));
+}
+
+#[test]
+fn backups_round_trip_in_every_format() {
+  let dir = tempfile::tempdir().unwrap();
+  let root = dir.path().join("root");
+  fs::create_dir(&root).unwrap();
+  fs::write(root.join("world.phext"), "first\x17second").unwrap();
+  fs::write(root.join("world.phext.history"), "lp world.phext\nds\n").unwrap();
+  fs::write(root.join("world.phext.snapshot-1"), "first").unwrap();
+  fs::write(root.join("other.phext"), "other").unwrap();
+  fs::write(root.join("notes.txt"), "not a world").unwrap();
+
+  for archive in ["backup.tar", "backup.tar.gz", "backup.zip"] {
+    let output = phext(dir.path(), &["backup", "--root", "root", archive]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("Wrote 2 worlds (4 files) to {archive}\n"));
+
+    let target = dir.path().join(archive.replace('.', "-"));
+    let output = phext(dir.path(), &["restore", "--root", target.to_str().unwrap(), archive]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).ends_with("4 files restored, 0 already up to date\n"), "{}", stdout(&output));
+    for name in ["world.phext", "world.phext.history", "world.phext.snapshot-1", "other.phext"] {
+      assert_eq!(fs::read(target.join(name)).unwrap(), fs::read(root.join(name)).unwrap(), "{name} from {archive}");
+    }
+    assert!(!target.join("notes.txt").exists());
+  }
+
+  let output = phext(dir.path(), &["backup", "--root", "root", "--world", "other", "other.zip"]);
+  assert_eq!(stdout(&output), "Wrote 1 worlds (1 files) to other.zip\n");
+  let output = phext(dir.path(), &["backup", "--root", "root", "--world", "missing", "missing.zip"]);
+  assert!(stderr(&output).contains("No world named missing"));
+  let output = phext(dir.path(), &["backup", "--root", "root", "backup.rar"]);
+  assert!(stderr(&output).contains("Unable to tell the backup format of backup.rar"));
+}
+
+#[test]
+fn restores_refuse_conflicts_and_damaged_backups() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("world.phext"), "original").unwrap();
+  assert!(phext(dir.path(), &["backup", "backup.tar"]).status.success());
+
+  // restoring over identical files is a no-op
+  let output = phext(dir.path(), &["restore", "backup.tar"]);
+  assert_eq!(stdout(&output), "0 files restored, 1 already up to date\n");
+
+  fs::write(dir.path().join("world.phext"), "edited since").unwrap();
+  let output = phext(dir.path(), &["restore", "backup.tar"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("already exist with different content: world.phext"), "{}", stderr(&output));
+  assert_eq!(fs::read_to_string(dir.path().join("world.phext")).unwrap(), "edited since");
+
+  let output = phext(dir.path(), &["restore", "--force", "backup.tar"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(fs::read_to_string(dir.path().join("world.phext")).unwrap(), "original");
+
+  let mut damaged = fs::read(dir.path().join("backup.tar")).unwrap();
+  let offset = damaged.windows(8).position(|window| window == b"original").unwrap();
+  damaged[offset] = b'O';
+  fs::write(dir.path().join("damaged.tar"), damaged).unwrap();
+  let output = phext(dir.path(), &["restore", "--force", "damaged.tar"]);
+  assert!(stderr(&output).contains("world.phext does not match its checksum"), "{}", stderr(&output));
+}
+
+#[test]
+fn convert_moves_worlds_between_storage_backends() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("world.phext"), "first\x17\x18second").unwrap();
+  fs::write(dir.path().join("other.phext"), "other").unwrap();
+
+  let output = phext(dir.path(), &["convert", "file", "sqlite"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(stdout(&output), "copied other\ncopied world\n2 worlds copied from file to sqlite storage\n");
+  assert!(dir.path().join("phext.sqlite").exists());
+
+  let copy = dir.path().join("copy");
+  let output = phext(dir.path(), &["backup", "--storage", "sqlite", "--world", "world", "world.tar"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let output = phext(dir.path(), &["restore", "--root", copy.to_str().unwrap(), "world.tar"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(fs::read_to_string(copy.join("world.phext")).unwrap(), "first\x17\x18second");
+
+  let output = phext(&copy, &["convert", "--world", "world", "file", "sqlite"]);
+  assert_eq!(stdout(&output), "copied world\n1 worlds copied from file to sqlite storage\n");
+  fs::remove_file(copy.join("world.phext")).unwrap();
+  let output = phext(&copy, &["convert", "sqlite", "file"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(fs::read_to_string(copy.join("world.phext")).unwrap(), "first\x17\x18second");
+
+  let output = phext(dir.path(), &["convert", "file", "file"]);
+  assert!(stderr(&output).contains("Nothing to convert: both backends are file"));
+  let output = phext(dir.path(), &["convert", "file", "postgres"]);
+  assert!(stderr(&output).contains("Unknown storage backend: postgres"));
+}
+
+#[test]
+fn commands_read_and_write_compressed_worlds() {
+  let dir = tempfile::tempdir().unwrap();
+  write_tree(dir.path(), &[("docs/a.txt", "alpha")]);
+  assert!(phext(dir.path(), &["sync", "--once", "world.phext", "docs"]).status.success());
+  assert!(phext(dir.path(), &["compress", "--min-bytes", "0", "zstd"]).status.success());
+  let stored = dir.path().join("world.phext.zst");
+  assert!(stored.exists() && !dir.path().join("world.phext").exists());
+
+  assert_eq!(stdout(&phext(dir.path(), &["cat", "world.phext", "1.1.1/1.1.1/1.1.1"])), "alpha");
+  assert_eq!(stdout(&phext(dir.path(), &["grep", "alp", "world.phext"])), "1.1.1/1.1.1/1.1.1:1:1:alpha\n");
+  assert!(phext(dir.path(), &["fsck", "world.phext"]).status.success());
+  assert!(phext_with_input(dir.path(), &["put", "world.phext", "1.1.1/1.1.1/1.1.1"], "alpha two").status.success());
+  fs::write(dir.path().join("docs/b.txt"), "beta").unwrap();
+  let output = phext(dir.path(), &["sync", "--once", "world.phext", "docs"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(fs::read_to_string(dir.path().join("docs/a.txt")).unwrap(), "alpha two");
+  assert!(!dir.path().join("world.phext").exists());
+  let buffer = String::from_utf8(compression::decode(&fs::read(&stored).unwrap(), Codec::Zstd).unwrap()).unwrap();
+  assert!(buffer.starts_with("alpha two\x17beta"), "{buffer:?}");
+
+  let unpacked = dir.path().join("unpacked");
+  assert!(phext(dir.path(), &["unpack", "world.phext", unpacked.to_str().unwrap()]).status.success());
+  assert_eq!(fs::read_to_string(unpacked.join("b.txt")).unwrap(), "beta");
+
+  // a world synced from inside its own directory never packs itself, in any encoding
+  let inside = dir.path().join("inside");
+  write_tree(&inside, &[("a.txt", "alpha")]);
+  assert!(phext(&inside, &["sync", "--once", "world.phext"]).status.success());
+  assert!(phext(&inside, &["compress", "--min-bytes", "0", "gzip"]).status.success());
+  let output = phext(&inside, &["sync", "--once", "world.phext"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(stdout(&output), "");
+  assert_eq!(stdout(&phext(&inside, &["ls", "world.phext"])).lines().count(), 2);
+}
+
+#[test]
+fn compress_reencodes_worlds_by_size_and_idle_time() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("large.phext"), "x".repeat(2000)).unwrap();
+  fs::write(dir.path().join("small.phext"), "tiny").unwrap();
+  fs::write(dir.path().join("large.phext.history"), "history").unwrap();
+
+  let output = phext(dir.path(), &["compress", "--min-bytes", "1000", "zstd"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(stdout(&output), "large: zstd\n1 worlds re-encoded\n");
+  assert!(dir.path().join("large.phext.zst").exists() && !dir.path().join("large.phext").exists());
+  assert!(dir.path().join("small.phext").exists());
+
+  // backups hold the world itself, not the compressed file it is kept in
+  let output = phext(dir.path(), &["backup", "large.tar"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let copy = dir.path().join("copy");
+  let output = phext(dir.path(), &["restore", "--root", copy.to_str().unwrap(), "large.tar"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(fs::read_to_string(copy.join("large.phext")).unwrap(), "x".repeat(2000));
+  assert!(!copy.join("large.phext.zst").exists());
+
+  let output = phext(dir.path(), &["compress", "--idle", "3600", "gzip"]);
+  assert_eq!(stdout(&output), "large: none\n1 worlds re-encoded\n");
+  let output = phext(dir.path(), &["compress", "--idle", "0", "gzip"]);
+  assert_eq!(stdout(&output), "large: gzip\nsmall: gzip\n2 worlds re-encoded\n");
+
+  let output = phext(dir.path(), &["compress", "none"]);
+  assert_eq!(stdout(&output), "large: none\nsmall: none\n2 worlds re-encoded\n");
+  assert_eq!(fs::read_to_string(dir.path().join("small.phext")).unwrap(), "tiny");
+  let output = phext(dir.path(), &["compress", "brotli"]);
+  assert!(stderr(&output).contains("Unknown compression: brotli"));
+}
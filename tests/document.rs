@@ -0,0 +1,77 @@
+#![allow(clippy::needless_return)]
+
+/// ----------------------------------------------------------------------------------------------------------
+/// JSON Document Tests
+///
+/// Round-trips phext buffers through both JSON layouts and checks the documents other services would see.
+/// ----------------------------------------------------------------------------------------------------------
+use hello_phext::document::{export, import, Layout};
+
+const BUFFERS: [&str; 9] = [
+  "",
+  "only",
+  "first\x17second\x18third\x01far away",
+  "gap\x17\x17\x17after",
+  "skipped\x17\x18ahead",
+  "\x17\x18\x19\x1a\x1c\x1d\x1e\x1f\x01",
+  "trailing\x17",
+  "\x18leading section",
+  "text with\nlines and \u{1f680}\x1e\x17series",
+];
+
+#[test]
+fn both_layouts_round_trip_exactly() {
+  for buffer in BUFFERS {
+    for layout in [Layout::Flat, Layout::Nested] {
+      let document = export(buffer, layout).unwrap();
+      assert_eq!(import(&document).unwrap(), buffer, "{:?} via {}", buffer, document);
+    }
+  }
+}
+
+#[test]
+fn flat_documents_only_keep_empty_scrolls_that_matter() {
+  assert_eq!(export("", Layout::Flat).unwrap(), "[]");
+  assert_eq!(export("a\x17\x17b", Layout::Flat).unwrap(),
+    r#"[{"coordinate":"1.1.1/1.1.1/1.1.1","content":"a"},{"coordinate":"1.1.1/1.1.1/1.1.3","content":"b"}]"#);
+  assert_eq!(export("a\x17\x18b\x17", Layout::Flat).unwrap(),
+    concat!(r#"[{"coordinate":"1.1.1/1.1.1/1.1.1","content":"a"},{"coordinate":"1.1.1/1.1.1/1.1.2","content":""},"#,
+            r#"{"coordinate":"1.1.1/1.1.1/1.2.1","content":"b"},{"coordinate":"1.1.1/1.1.1/1.2.2","content":""}]"#));
+}
+
+#[test]
+fn nested_documents_are_keyed_by_dimension_in_numeric_order() {
+  let mut buffer = "1".to_string();
+  for scroll in 2..=10 {
+    buffer += &format!("\x17{}", scroll);
+  }
+  let document = export(&buffer, Layout::Nested).unwrap();
+  assert!(document.starts_with(r#"{"1":{"1":{"1":{"1":{"1":{"1":{"1":{"1":{"1":"1","2":"2","#), "{}", document);
+  assert!(document.ends_with(r#""9":"9","10":"10"}}}}}}}}}"#), "{}", document);
+}
+
+#[test]
+fn imports_accept_any_order_and_either_coordinate_form() {
+  let flat = r#"[{"coordinate":"1.1.1;1.1.1;1.2.1","content":"b"},{"coordinate":"1.1.1/1.1.1/1.1.1","content":"a"}]"#;
+  assert_eq!(import(flat).unwrap(), "a\x18b");
+  let nested = r#"{"1":{"1":{"1":{"1":{"1":{"1":{"1":{"2":{"1":"b"},"1":{"1":"a"}}}}}}}}}"#;
+  assert_eq!(import(nested).unwrap(), "a\x18b");
+}
+
+#[test]
+fn imports_reject_malformed_documents() {
+  assert!(import("not json").unwrap_err().contains("Invalid JSON document"));
+  assert!(import(r#"[{"coordinate":"1.1.1","content":"a"}]"#).unwrap_err().contains("Invalid coordinate"));
+  let duplicate = r#"[{"coordinate":"1.1.1/1.1.1/1.1.1","content":"a"},{"coordinate":"1.1.1;1.1.1;1.1.1","content":"b"}]"#;
+  assert!(import(duplicate).unwrap_err().contains("Duplicate coordinate"));
+  let delimiters = r#"[{"coordinate":"1.1.1/1.1.1/1.1.1","content":"a\u0017b"}]"#;
+  assert!(import(delimiters).unwrap_err().contains("contains phext delimiters"));
+  assert!(import(r#"{"1":{"x":"a"}}"#).unwrap_err().contains("Invalid dimension key: x"));
+  assert!(import(r#"{"1":"too shallow"}"#).unwrap_err().contains("Expected an object keyed by dimension at 1"));
+}
+
+#[test]
+fn exports_refuse_coordinates_beyond_99() {
+  let buffer = "x\x17".repeat(100) + "past the edge";
+  assert!(export(&buffer, Layout::Flat).unwrap_err().contains("beyond 99"));
+}
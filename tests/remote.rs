@@ -0,0 +1,106 @@
+#![allow(clippy::needless_return)]
+
+/// ----------------------------------------------------------------------------------------------------------
+/// Remote Client Integration Tests
+///
+/// Launches the API on a free local port with `build_rocket` and drives it over HTTP with `Remote`.
+/// ----------------------------------------------------------------------------------------------------------
+use hello_phext::remote::{url_coordinate, Remote};
+use hello_phext::server::{build_rocket, ServerConfig};
+use std::fs;
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+fn launch(root: &Path, read_only: bool) -> Remote {
+  let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+  std::env::set_var("ROCKET_LOG_LEVEL", "off");
+  let config = ServerConfig { root: root.to_path_buf(), port: Some(port), read_only, ..ServerConfig::default() };
+  std::thread::spawn(move || {
+    let _ = rocket::execute(build_rocket(config).launch());
+  });
+
+  for _ in 0..200 {
+    if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+      return Remote::new(&format!("http://127.0.0.1:{}/", port));
+    }
+    std::thread::sleep(Duration::from_millis(25));
+  }
+  panic!("the server on port {} never started", port);
+}
+
+fn world(root: &Path, name: &str) -> String {
+  return fs::read_to_string(root.join(name.to_owned() + ".phext")).unwrap();
+}
+
+#[test]
+fn coordinates_accept_either_form() {
+  assert_eq!(url_coordinate("1.1.1/1.1.1/1.1.2").unwrap(), "1.1.1;1.1.1;1.1.2");
+  assert_eq!(url_coordinate("1.1.1;1.1.1;1.1.2").unwrap(), "1.1.1;1.1.1;1.1.2");
+  assert!(url_coordinate("1.1.1/1.1.1").unwrap_err().contains("Invalid coordinate"));
+}
+
+#[test]
+fn scroll_operations_round_trip() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("world.phext"), "first").unwrap();
+  let remote = launch(dir.path(), false);
+
+  assert_eq!(remote.select("world", "1.1.1;1.1.1;1.1.1").unwrap(), "first");
+  remote.update("world", "1.1.1/1.1.1/1.1.2", "second").unwrap();
+  assert_eq!(world(dir.path(), "world"), "first\x17second");
+  remote.insert("world", "1.1.1/1.1.1/1.1.2", " scroll").unwrap();
+  assert_eq!(remote.select("world", "1.1.1/1.1.1/1.1.2").unwrap(), "second scroll");
+  remote.delete("world", "1.1.1;1.1.1;1.1.1").unwrap();
+  assert_eq!(remote.select_phext("world").unwrap(), "\x17second scroll");
+
+  remote.update_phext("world", "a\x17b\x17c\x17d").unwrap();
+  remote.replace("world", "1.1.1/1.1.1/1.1.2", "1.1.1/1.1.1/1.1.3", "middle").unwrap();
+  assert_eq!(world(dir.path(), "world"), "a\x17middle\x17d");
+  remote.insert_phext("world", "\x18e").unwrap();
+  assert_eq!(world(dir.path(), "world"), "a\x17middle\x17d\x18e");
+  remote.delete_phext("world").unwrap();
+  assert_eq!(world(dir.path(), "world"), "");
+}
+
+#[test]
+fn world_operations_round_trip() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("mother.phext"), "m1\x17m2").unwrap();
+  fs::write(dir.path().join("father.phext"), "f1\x18f2").unwrap();
+  fs::write(dir.path().join("child.phext"), "").unwrap();
+  let remote = launch(dir.path(), false);
+
+  remote.merge("child", "mother", "father").unwrap();
+  let merged = remote.select_phext("child").unwrap();
+  assert!(merged.contains("m2") && merged.contains("f2"));
+
+  remote.subtract("child", "mother").unwrap();
+  let subtracted = remote.select_phext("child").unwrap();
+  assert!(!subtracted.contains("m2") && subtracted.contains("f2"));
+
+  remote.expand("child", "one\x17two").unwrap();
+  assert_eq!(world(dir.path(), "child"), "one\x18two");
+  remote.contract("child", "one\x18two").unwrap();
+  assert_eq!(world(dir.path(), "child"), "one\x17two");
+  remote.normalize("child", "one\x17\x17\x18two").unwrap();
+  assert_eq!(world(dir.path(), "child"), "one\x18two");
+}
+
+#[test]
+fn errors_carry_the_status_and_body() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("world.phext"), "first").unwrap();
+  let remote = launch(dir.path(), true);
+
+  let refused = remote.update("world", "1.1.1/1.1.1/1.1.1", "changed").unwrap_err();
+  assert!(refused.contains("403"), "{}", refused);
+  assert!(refused.contains("/api/v1/update/world/1.1.1;1.1.1;1.1.1"), "{}", refused);
+  assert_eq!(world(dir.path(), "world"), "first");
+
+  let missing = remote.select_phext("missing").unwrap_err();
+  assert!(missing.contains("returned 404: Unable to find missing"), "{}", missing);
+
+  assert!(remote.select("world", "not-a-coordinate").unwrap_err().contains("Invalid coordinate"));
+  assert!(Remote::new("http://127.0.0.1:1").select_phext("world").is_err());
+}
@@ -0,0 +1,50 @@
+#![allow(clippy::needless_return)]
+
+/// ----------------------------------------------------------------------------------------------------------
+/// Search Index Tests
+///
+/// Builds and incrementally updates a world's inverted index, checking ranks and snippets along the way.
+/// ----------------------------------------------------------------------------------------------------------
+use hello_phext::search::{self, WorldIndex};
+use std::collections::BTreeSet;
+
+fn ranked(index: &WorldIndex, buffer: &str, query: &str) -> Vec<String> {
+  return index.search("world", buffer, query).into_iter().map(|hit| hit.coordinate).collect();
+}
+
+#[test]
+fn tokens_are_lowercase_alphanumeric_runs() {
+  assert_eq!(search::tokenize("Hello, World! caf\u{e9}-42"), ["hello", "world", "caf\u{e9}", "42"]);
+  assert!(search::tokenize(" -- ").is_empty());
+}
+
+#[test]
+fn updates_follow_edits_and_moved_scrolls() {
+  let mut index = WorldIndex::default();
+  let first = "alpha beta\x17beta beta\x17gamma";
+  index.update(first);
+  assert!(index.is_current(first));
+  assert_eq!(ranked(&index, first, "beta"), ["1.1.1/1.1.1/1.1.2", "1.1.1/1.1.1/1.1.1"]);
+
+  let second = "inserted\x17alpha beta\x17gamma delta";
+  assert!(!index.is_current(second));
+  index.update(second);
+  assert_eq!(ranked(&index, second, "beta"), ["1.1.1/1.1.1/1.1.2"]);
+  assert_eq!(ranked(&index, second, "alpha"), ["1.1.1/1.1.1/1.1.2"]);
+  assert_eq!(ranked(&index, second, "delta"), ["1.1.1/1.1.1/1.1.3"]);
+  assert_eq!(index.search("world", second, "gamma")[0].snippet, "<mark>gamma</mark> delta");
+
+  index.update("");
+  assert!(ranked(&index, "", "alpha").is_empty());
+}
+
+#[test]
+fn snippets_are_escaped_and_trimmed_around_the_first_match() {
+  let wanted: BTreeSet<String> = ["needle".to_string()].into_iter().collect();
+  assert_eq!(search::snippet("first line\n  <b>needle</b> & more", &wanted), "&lt;b&gt;<mark>needle</mark>&lt;/b&gt; &amp; more");
+  let long = "x ".repeat(100) + "needle" + &" y".repeat(100);
+  let snippet = search::snippet(&long, &wanted);
+  assert!(snippet.starts_with("...") && snippet.ends_with("..."), "{snippet}");
+  assert!(snippet.contains("<mark>needle</mark>"));
+  assert_eq!(search::snippet("no match", &wanted), "");
+}
@@ -0,0 +1,761 @@
+#![allow(clippy::needless_return)]
+
+/// ----------------------------------------------------------------------------------------------------------
+/// Server Integration Tests
+///
+/// Mounts the API on a scratch data root with `build_rocket` and drives it with a local client. Tests that
+/// don't depend on how worlds are stored run once per storage backend.
+/// ----------------------------------------------------------------------------------------------------------
+use hello_phext::compression::{self, Codec, Compression};
+use hello_phext::server::{build_rocket, Library, ServerConfig};
+use hello_phext::storage::{self, Backend, Storage, StorageError, Version};
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::blocking::Client;
+use rocket::serde::json::{self, Value};
+use std::fs;
+use std::path::Path;
+
+const BACKENDS: [Backend; 2] = [Backend::File, Backend::Sqlite];
+
+fn client(root: &Path, backend: &Backend, read_only: bool) -> Client {
+  let config = ServerConfig { root: root.to_path_buf(), storage: backend.clone(), read_only, ..ServerConfig::default() };
+  return Client::tracked(build_rocket(config)).expect("unable to build rocket");
+}
+
+fn write_world(root: &Path, backend: &Backend, world: &str, buffer: &str) {
+  storage::open(backend, root).unwrap().store(world, buffer, &Version::Any).unwrap();
+}
+
+fn read_world(root: &Path, backend: &Backend, world: &str) -> String {
+  return String::from_utf8(storage::open(backend, root).unwrap().load(world).unwrap().0).unwrap();
+}
+
+#[test]
+fn serves_worlds_from_the_configured_root() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "first\x17second");
+    let client = client(dir.path(), &backend, false);
+
+    let response = client.get("/api/v1/select/world/1.1.1;1.1.1;1.1.2").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().unwrap(), "second");
+
+    let homepage = client.get("/index.html").dispatch().into_string().unwrap();
+    assert!(homepage.contains("world"));
+  }
+}
+
+#[test]
+fn writes_land_in_the_configured_root() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "first");
+    let client = client(dir.path(), &backend, false);
+
+    let response = client.post("/api/v1/update/world/1.1.1;1.1.1;1.1.2")
+      .header(ContentType::Form)
+      .body("content=added")
+      .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(read_world(dir.path(), &backend, "world"), "first\x17added");
+  }
+}
+
+#[test]
+fn scrolls_insert_update_and_delete_through_the_api() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "first\x17second");
+    let client = client(dir.path(), &backend, false);
+    let post = |path: &str, content: &str| -> Status {
+      return client.post(path).header(ContentType::Form).body(format!("content={}", content)).dispatch().status();
+    };
+    let select = |path: &str| client.get(path).dispatch().into_string().unwrap();
+
+    assert_eq!(post("/api/v1/insert/world/1.1.1;1.1.1;1.1.2", "+more"), Status::Ok);
+    assert_eq!(select("/api/v1/select/world/1.1.1;1.1.1;1.1.2"), "second more");
+    assert_eq!(post("/api/v1/insert/world", "%17third"), Status::Ok);
+    assert_eq!(post("/api/v1/update/world/1.1.1;1.1.1;1.1.1", "changed"), Status::Ok);
+    assert_eq!(read_world(dir.path(), &backend, "world"), "changed\x17second more\x17third");
+
+    assert_eq!(client.post("/api/v1/delete/world/1.1.1;1.1.1;1.1.2").dispatch().status(), Status::Ok);
+    assert_eq!(select("/api/v1/select/world/1.1.1;1.1.1;1.1.2"), "");
+    assert_eq!(select("/api/v1/select/world/1.1.1;1.1.1;1.1.3"), "third");
+    let saved = client.post("/api/v1/save/world/1.1.1;1.1.1;1.1.2").header(ContentType::Form).body("content=restored").dispatch();
+    assert_eq!(saved.content_type(), Some(ContentType::HTML));
+    assert_eq!(select("/api/v1/select/world/1.1.1;1.1.1;1.1.2"), "restored");
+
+    assert_eq!(client.post("/api/v1/delete/world").dispatch().status(), Status::Ok);
+    assert_eq!(read_world(dir.path(), &backend, "world"), "");
+  }
+}
+
+#[test]
+fn normalize_expand_and_contract_rewrite_worlds() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "");
+    let client = client(dir.path(), &backend, false);
+    let rewrite = |path: &str, content: &str| {
+      let response = client.post(path).header(ContentType::Form).body(format!("content={}", content)).dispatch();
+      assert_eq!(response.status(), Status::Ok);
+      assert_eq!(response.content_type(), Some(ContentType::HTML));
+      return read_world(dir.path(), &backend, "world");
+    };
+
+    assert_eq!(rewrite("/api/v1/expand/world", "a%17b%18c"), "a\x18b\x19c");
+    assert_eq!(rewrite("/api/v1/contract/world", "a%18b%19c"), "a\x17b\x18c");
+    assert_eq!(rewrite("/api/v1/normalize/world", "a%17%17%18b%17"), "a\x18b");
+  }
+}
+
+#[test]
+fn read_only_servers_refuse_writes() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "first");
+    let client = client(dir.path(), &backend, true);
+
+    let update = client.post("/api/v1/update/world/1.1.1;1.1.1;1.1.1")
+      .header(ContentType::Form)
+      .body("content=changed")
+      .dispatch();
+    assert_eq!(update.status(), Status::Forbidden);
+    assert_eq!(client.post("/api/v1/delete/world").dispatch().status(), Status::Forbidden);
+
+    let preview = client.post("/api/v1/find-replace/world")
+      .header(ContentType::Form)
+      .body("pattern=first&replacement=changed")
+      .dispatch();
+    assert_eq!(preview.status(), Status::Ok);
+    let apply = client.post("/api/v1/find-replace/world")
+      .header(ContentType::Form)
+      .body("pattern=first&replacement=changed&apply=true")
+      .dispatch();
+    assert_eq!(apply.status(), Status::Forbidden);
+
+    assert_eq!(read_world(dir.path(), &backend, "world"), "first");
+    assert_eq!(client.get("/api/v1/select/world").dispatch().into_string().unwrap(), "first");
+  }
+}
+
+#[test]
+fn read_only_libraries_refuse_saves_without_panicking() {
+  let dir = tempfile::tempdir().unwrap();
+  let library = Library::new(dir.path().to_path_buf(), storage::open(&Backend::File, dir.path()).unwrap(), true);
+  let (status, message) = library.save("world", "changed", &Version::Any).unwrap_err();
+  assert_eq!(status, Status::Forbidden);
+  assert!(message.contains("read-only"), "{message}");
+  assert!(!dir.path().join("world.phext").exists());
+}
+
+#[test]
+fn edits_based_on_a_stale_read_conflict() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "original");
+    let library = Library::new(dir.path().to_path_buf(), storage::open(&backend, dir.path()).unwrap(), false);
+
+    let (_, first) = library.checkout("world").unwrap();
+    let (_, second) = library.checkout("world").unwrap();
+    library.save("world", "saved by the first request", &first).unwrap();
+    let (status, message) = library.save("world", "saved by the second request", &second).unwrap_err();
+    assert_eq!(status, Status::Conflict);
+    assert!(message.contains("was changed by another writer"), "{message}");
+    assert_eq!(read_world(dir.path(), &backend, "world"), "saved by the first request");
+
+    let (_, current) = library.checkout("world").unwrap();
+    library.save("world", "saved after reloading", &current).unwrap();
+    assert_eq!(library.save("created", "new", &Version::Absent), Ok(()));
+    assert_eq!(library.save("created", "again", &Version::Absent).unwrap_err().0, Status::Conflict);
+    assert_eq!(library.save("created", "replaced", &Version::Any), Ok(()));
+    assert_eq!(read_world(dir.path(), &backend, "created"), "replaced");
+  }
+}
+
+#[test]
+fn world_names_stay_inside_the_data_root() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir(&root).unwrap();
+    fs::write(dir.path().join("secret.phext"), "classified").unwrap();
+    let client = client(&root, &backend, false);
+
+    let response = client.get("/api/v1/raw/..%2Fsecret").dispatch();
+    assert_ne!(response.status(), Status::Ok);
+    assert!(!response.into_string().unwrap_or_default().contains("classified"));
+
+    for world in ["..%2Fescaped", "..%5Cescaped", ".hidden"] {
+      let response = client.post(format!("/api/v1/update/{world}"))
+        .header(ContentType::Form)
+        .body("content=written")
+        .dispatch();
+      assert_eq!(response.status(), Status::BadRequest, "{world}");
+    }
+    assert!(!dir.path().join("escaped.phext").exists());
+    assert!(!root.join(".hidden.phext").exists());
+    assert_eq!(fs::read_to_string(dir.path().join("secret.phext")).unwrap(), "classified");
+  }
+
+  let files = storage::FileStorage::new(std::env::temp_dir());
+  assert_eq!(files.load("../secret").unwrap_err(), StorageError::Invalid("Invalid world name: ../secret".to_string()));
+  assert_eq!(files.store("nested/world", "content", &Version::Any), Err(StorageError::Invalid("Invalid world name: nested/world".to_string())));
+  for name in ["world", "world-2", "notes.v1"] {
+    assert!(storage::is_world_name(name), "{name}");
+  }
+  for name in ["", ".hidden", "..", "a/b", "a\\b"] {
+    assert!(!storage::is_world_name(name), "{name}");
+  }
+}
+
+#[test]
+fn storage_failures_answer_with_their_status() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "first");
+    let client = client(dir.path(), &backend, false);
+
+    for path in ["/api/v1/select/missing/1.1.1;1.1.1;1.1.1", "/api/v1/select/missing", "/api/v1/raw/missing",
+                 "/api/v1/index/missing/1.1.1;1.1.1;1.1.1", "/api/v1/edit/missing/1.1.1;1.1.1;1.1.1",
+                 "/api/v1/select-range/missing/1.1.1;1.1.1;1.1.1/1.1.1;1.1.1;1.1.2", "/api/v1/query/missing/*.*.*;*.*.*;*.*.*",
+                 "/api/v1/toc/missing", "/api/v1/stats/missing", "/api/v1/fsck/missing", "/api/v1/json/missing",
+                 "/api/v1/search/missing?q=first", "/api/v1/grep/missing?pattern=first"] {
+      let response = client.get(path).dispatch();
+      assert_eq!(response.status(), Status::NotFound, "{path}");
+      assert_eq!(response.into_string().unwrap(), "Unable to find missing", "{path}");
+    }
+    let insert = client.post("/api/v1/insert/missing/1.1.1;1.1.1;1.1.1").header(ContentType::Form).body("content=x").dispatch();
+    assert_eq!(insert.status(), Status::NotFound);
+    assert_eq!(client.get("/api/v1/merge/world/world/missing").dispatch().status(), Status::NotFound);
+    assert_eq!(read_world(dir.path(), &backend, "world"), "first");
+  }
+
+  let dir = tempfile::tempdir().unwrap();
+  write_world(dir.path(), &Backend::Sqlite, "world", "first");
+  let database = client(dir.path(), &Backend::Sqlite, false);
+  rusqlite::Connection::open(dir.path().join(storage::SQLITE_NAME)).unwrap().execute_batch("DROP TABLE scrolls").unwrap();
+  let broken = database.get("/api/v1/select/world/1.1.1;1.1.1;1.1.1").dispatch();
+  assert_eq!(broken.status(), Status::ServiceUnavailable);
+  assert!(broken.into_string().unwrap().contains("SQLite error"));
+
+  let dir = tempfile::tempdir().unwrap();
+  let root = dir.path().join("root");
+  fs::create_dir(&root).unwrap();
+  let files = client(&root, &Backend::File, false);
+  fs::remove_dir(&root).unwrap();
+  assert_eq!(files.get("/api/v1/search?q=first").dispatch().status(), Status::ServiceUnavailable);
+  assert_eq!(files.get("/index.html").dispatch().status(), Status::ServiceUnavailable);
+}
+
+#[test]
+fn route_edits_sync_back_to_files() {
+  let dir = tempfile::tempdir().unwrap();
+  let docs = dir.path().join("docs");
+  fs::create_dir(&docs).unwrap();
+  fs::write(docs.join("a.txt"), "alpha").unwrap();
+  let ignore = |_: &Path, _: bool| false;
+  let packed = hello_phext::archive::pack(&hello_phext::archive::collect(&docs, &ignore).unwrap(), &[]);
+  fs::write(dir.path().join("world.phext"), packed).unwrap();
+
+  let client = client(dir.path(), &Backend::File, false);
+  let response = client.post("/api/v1/update/world/1.1.1;1.1.1;1.1.1")
+    .header(ContentType::Form)
+    .body("content=edited")
+    .dispatch();
+  assert_eq!(response.status(), Status::Ok);
+
+  let buffer = fs::read_to_string(dir.path().join("world.phext")).unwrap();
+  let result = hello_phext::sync::reconcile(&buffer, &docs, &ignore).unwrap();
+  assert_eq!(result.events.len(), 1);
+  assert_eq!(fs::read_to_string(docs.join("a.txt")).unwrap(), "alpha");
+  hello_phext::sync::apply(&docs, &result.writes).unwrap();
+  assert_eq!(fs::read_to_string(docs.join("a.txt")).unwrap(), "edited");
+}
+
+#[test]
+fn fsck_reports_findings_as_json() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "good\x17\x18ok");
+    let client = client(dir.path(), &backend, true);
+
+    let response = client.get("/api/v1/fsck/world").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    let report = response.into_string().unwrap();
+    assert!(report.contains("\"errors\":0"), "{report}");
+    assert!(report.contains("\"kind\":\"denormalized\""), "{report}");
+  }
+
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("world.phext"), b"bad\xff\x17\x18ok").unwrap();
+  let client = client(dir.path(), &Backend::File, true);
+
+  let report = client.get("/api/v1/fsck/world").dispatch().into_string().unwrap();
+  assert!(report.contains("\"errors\":1"), "{report}");
+  assert!(report.contains("\"severity\":\"error\",\"kind\":\"utf8\""), "{report}");
+  assert!(report.contains("\"kind\":\"denormalized\""), "{report}");
+
+  let unreadable = client.get("/api/v1/select/world/1.1.1;1.1.1;1.1.1").dispatch();
+  assert_eq!(unreadable.status(), Status::Conflict);
+  assert!(unreadable.into_string().unwrap().contains("as UTF-8"));
+}
+
+#[test]
+fn json_documents_round_trip_through_the_api() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "first\x17\x18second\x17");
+    let client = client(dir.path(), &backend, false);
+
+    let flat = client.get("/api/v1/json/world").dispatch();
+    assert_eq!(flat.content_type(), Some(ContentType::JSON));
+    let flat = flat.into_string().unwrap();
+    assert!(flat.contains(r#"{"coordinate":"1.1.1/1.1.1/1.1.2","content":""}"#), "{}", flat);
+    let nested = client.get("/api/v1/json/world?layout=nested").dispatch().into_string().unwrap();
+    assert!(nested.starts_with(r#"{"1":{"1":{"1":{"1":{"1":{"1":{"1":{"1":{"1":"first","2":""}"#), "{}", nested);
+    assert_eq!(client.get("/api/v1/json/world?layout=tree").dispatch().status(), Status::BadRequest);
+
+    for document in [flat, nested] {
+      let response = client.post("/api/v1/json/copy").header(ContentType::JSON).body(document).dispatch();
+      assert_eq!(response.status(), Status::Ok);
+      assert_eq!(read_world(dir.path(), &backend, "copy"), "first\x17\x18second\x17");
+    }
+
+    let rejected = client.post("/api/v1/json/copy")
+      .header(ContentType::JSON)
+      .body(r#"[{"coordinate":"1.1.1","content":"a"}]"#)
+      .dispatch();
+    assert_eq!(rejected.status(), Status::BadRequest);
+    assert!(rejected.into_string().unwrap().contains("Invalid coordinate"));
+  }
+}
+
+#[test]
+fn backups_download_and_restore_with_conflict_checks() {
+  for (from, to) in [(Backend::File, Backend::File), (Backend::File, Backend::Sqlite), (Backend::Sqlite, Backend::File)] {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &from, "world", "first");
+    fs::write(dir.path().join("world.phext.history"), "lp world.phext\n").unwrap();
+    let source = client(dir.path(), &from, true);
+
+    let response = source.get("/api/v1/backup?format=zip").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::ZIP));
+    let archive = response.into_bytes().unwrap();
+    assert_eq!(source.get("/api/v1/backup?format=rar").dispatch().status(), Status::BadRequest);
+    assert_eq!(source.post("/api/v1/restore").body(archive.clone()).dispatch().status(), Status::Forbidden);
+
+    let restored = tempfile::tempdir().unwrap();
+    write_world(restored.path(), &to, "world", "diverged");
+    let target = client(restored.path(), &to, false);
+    let conflict = target.post("/api/v1/restore").body(archive.clone()).dispatch();
+    assert_eq!(conflict.status(), Status::Conflict);
+    assert!(conflict.into_string().unwrap().contains("world.phext"));
+    assert_eq!(read_world(restored.path(), &to, "world"), "diverged");
+
+    let response = target.post("/api/v1/restore?overwrite=true").body(archive).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().unwrap(), r#"{"written":["world.phext","world.phext.history"],"unchanged":[]}"#);
+    assert_eq!(read_world(restored.path(), &to, "world"), "first");
+    assert_eq!(fs::read_to_string(restored.path().join("world.phext.history")).unwrap(), "lp world.phext\n");
+    assert_eq!(target.get("/api/v1/select/world").dispatch().into_string().unwrap(), "first");
+
+    let garbage = target.post("/api/v1/restore").body("not an archive").dispatch();
+    assert_eq!(garbage.status(), Status::BadRequest);
+  }
+}
+
+#[test]
+fn sqlite_worlds_keep_one_row_per_scroll() {
+  let dir = tempfile::tempdir().unwrap();
+  let client = client(dir.path(), &Backend::Sqlite, false);
+  let response = client.post("/api/v1/update/world")
+    .header(ContentType::Form)
+    .body("content=first%17%17third%18%01far")
+    .dispatch();
+  assert_eq!(response.status(), Status::Ok);
+  assert!(!dir.path().join("world.phext").exists());
+  assert_eq!(client.get("/api/v1/select/world/1.1.1;1.1.1;1.1.3").dispatch().into_string().unwrap(), "third");
+
+  let database = rusqlite::Connection::open(dir.path().join(storage::SQLITE_NAME)).unwrap();
+  let rows: Vec<(String, i64, i64, String)> = database
+    .prepare("SELECT world, library, scroll, content FROM scrolls ORDER BY library, section, scroll").unwrap()
+    .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))).unwrap()
+    .collect::<Result<_, _>>().unwrap();
+  assert_eq!(rows, vec![
+    ("world".to_string(), 1, 1, "first".to_string()),
+    ("world".to_string(), 1, 3, "third".to_string()),
+    ("world".to_string(), 1, 1, "".to_string()),
+    ("world".to_string(), 2, 1, "far".to_string()),
+  ]);
+}
+
+#[test]
+fn worlds_convert_between_backends() {
+  let dir = tempfile::tempdir().unwrap();
+  let buffers = [("alpha", "a\x17\x18b\x17"), ("beta", ""), ("gamma", "\x01far away")];
+  for (world, buffer) in buffers {
+    write_world(dir.path(), &Backend::File, world, buffer);
+  }
+  let files = storage::open(&Backend::File, dir.path()).unwrap();
+  let database = storage::open(&Backend::Sqlite, dir.path()).unwrap();
+
+  assert_eq!(storage::copy(files.as_ref(), database.as_ref(), &[]).unwrap(), vec!["alpha", "beta", "gamma"]);
+  assert_eq!(database.worlds().unwrap(), vec!["alpha", "beta", "gamma"]);
+  for (world, buffer) in buffers {
+    assert_eq!(read_world(dir.path(), &Backend::Sqlite, world), buffer);
+  }
+
+  let copy = tempfile::tempdir().unwrap();
+  let files = storage::open(&Backend::File, copy.path()).unwrap();
+  storage::copy(database.as_ref(), files.as_ref(), &["gamma".to_string()]).unwrap();
+  assert_eq!(fs::read_to_string(copy.path().join("gamma.phext")).unwrap(), "\x01far away");
+  assert!(storage::copy(database.as_ref(), files.as_ref(), &["missing".to_string()]).unwrap_err().contains("Unable to find missing"));
+
+  let beyond = "x\x17".repeat(100);
+  assert!(matches!(database.store("beyond", &beyond, &Version::Any), Err(StorageError::Invalid(message)) if message.contains("beyond 99")));
+}
+
+#[test]
+fn compressed_worlds_are_listed_served_and_recompressed() {
+  let dir = tempfile::tempdir().unwrap();
+  let zstd = compression::encode(b"first\x17second", Codec::Zstd).unwrap();
+  fs::write(dir.path().join("packed.phext.zst"), zstd).unwrap();
+  fs::write(dir.path().join("small.phext"), "tiny").unwrap();
+  let compression = Compression { codec: Codec::Gzip, min_bytes: 10, idle: None };
+  let config = ServerConfig { root: dir.path().to_path_buf(), compression: Some(compression), ..ServerConfig::default() };
+  let client = Client::tracked(build_rocket(config)).unwrap();
+
+  // worlds already over the threshold are swept into the policy's codec at launch
+  assert!(dir.path().join("packed.phext.gz").exists());
+  assert!(!dir.path().join("packed.phext.zst").exists());
+  assert!(dir.path().join("small.phext").exists());
+
+  let homepage = client.get("/index.html").dispatch().into_string().unwrap();
+  assert!(homepage.contains("packed") && homepage.contains("small"));
+  let scroll = client.get("/api/v1/select/packed/1.1.1;1.1.1;1.1.2").dispatch();
+  assert_eq!(scroll.into_string().unwrap(), "second");
+
+  let response = client.post("/api/v1/update/small/1.1.1;1.1.1;1.1.2")
+    .header(ContentType::Form)
+    .body("content=grown+past+the+threshold")
+    .dispatch();
+  assert_eq!(response.status(), Status::Ok);
+  assert!(!dir.path().join("small.phext").exists());
+  let stored = fs::read(dir.path().join("small.phext.gz")).unwrap();
+  assert_eq!(compression::decode(&stored, Codec::Gzip).unwrap(), b"tiny\x17grown past the threshold");
+}
+
+#[test]
+fn whole_worlds_negotiate_content_encoding() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "first\x17second");
+    let client = client(dir.path(), &backend, false);
+
+    for path in ["/api/v1/raw/world", "/api/v1/select/world"] {
+      let plain = client.get(path).dispatch();
+      assert_eq!(plain.headers().get_one("Content-Encoding"), None);
+      assert_eq!(plain.headers().get_one("Vary"), Some("Accept-Encoding"));
+      assert_eq!(plain.into_string().unwrap(), "first\x17second");
+
+      let gzip = client.get(path).header(Header::new("Accept-Encoding", "gzip, deflate")).dispatch();
+      assert_eq!(gzip.headers().get_one("Content-Encoding"), Some("gzip"));
+      assert_eq!(compression::decode(&gzip.into_bytes().unwrap(), Codec::Gzip).unwrap(), b"first\x17second");
+
+      let zstd = client.get(path).header(Header::new("Accept-Encoding", "gzip, zstd")).dispatch();
+      assert_eq!(zstd.headers().get_one("Content-Encoding"), Some("zstd"));
+      assert_eq!(compression::decode(&zstd.into_bytes().unwrap(), Codec::Zstd).unwrap(), b"first\x17second");
+
+      let refused = client.get(path).header(Header::new("Accept-Encoding", "zstd;q=0, gzip;q=0.5")).dispatch();
+      assert_eq!(refused.headers().get_one("Content-Encoding"), Some("gzip"));
+      let identity = client.get(path).header(Header::new("Accept-Encoding", "identity, br")).dispatch();
+      assert_eq!(identity.headers().get_one("Content-Encoding"), None);
+    }
+  }
+}
+
+#[test]
+fn ranges_select_rebase_and_delete() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "one\x17two\x17three\x18four\x19five");
+    let client = client(dir.path(), &backend, false);
+    let select = |path: &str| client.get(path).dispatch().into_string().unwrap();
+
+    assert_eq!(select("/api/v1/select-range/world/1.1.1;1.1.1;1.1.2/1.1.1;1.1.1;1.2.1"), "\x17two\x17three\x18four");
+    assert_eq!(select("/api/v1/select-range/world/1.1.1;1.1.1;1.1.2/1.1.1;1.1.1;1.2.1?rebase=true"), "two\x17three\x18four");
+    assert_eq!(select("/api/v1/select-range/world/1.1.1;1.1.1;1.1.4/1.1.1;1.1.1;1.1.9"), "");
+    assert_eq!(select("/api/v1/select-range/world/1.1.1;1.1.1;1.2.1/1.1.1;1.1.1;1.1.1"), "");
+
+    let delete = |path: &str| client.post(path).dispatch().status();
+    assert_eq!(delete("/api/v1/delete-range/world/1.1.1;1.1.1;1.2.1/1.1.1;1.1.1;1.1.1"), Status::Ok);
+    assert_eq!(read_world(dir.path(), &backend, "world"), "one\x17two\x17three\x18four\x19five");
+    assert_eq!(delete("/api/v1/delete-range/world/1.1.1;1.1.1;1.1.4/1.1.1;1.1.1;1.1.9"), Status::Ok);
+    assert_eq!(read_world(dir.path(), &backend, "world"), "one\x17two\x17three\x18four\x19five");
+    assert_eq!(delete("/api/v1/delete-range/world/1.1.1;1.1.1;1.1.2/1.1.1;1.1.1;1.2.1"), Status::Ok);
+    assert_eq!(read_world(dir.path(), &backend, "world"), "one\x19five");
+  }
+}
+
+#[test]
+fn range_routes_reject_malformed_coordinates() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "one\x17two");
+    let client = client(dir.path(), &backend, false);
+
+    for path in ["/api/v1/select-range/world/1.1.1;1.1.1;1.1.1/garbage", "/api/v1/select-range/world/0.1.1;1.1.1;1.1.1/1.1.1;1.1.1;1.1.2"] {
+      let response = client.get(path).dispatch();
+      assert_eq!(response.status(), Status::BadRequest, "{path}");
+    }
+    for path in ["/api/v1/delete-range/world/1.1.1;1.1.1;1.1.1/garbage", "/api/v1/delete-range/world/1.1.1;1.1.1;*/1.1.1;1.1.1;1.1.2",
+                 "/api/v1/export-range/world/garbage/1.1.1;1.1.1;1.1.2/copy"] {
+      let response = client.post(path).dispatch();
+      assert_eq!(response.status(), Status::BadRequest, "{path}");
+      assert!(response.into_string().unwrap().starts_with("Invalid coordinate"), "{path}");
+    }
+    assert_eq!(read_world(dir.path(), &backend, "world"), "one\x17two");
+    assert_eq!(client.get("/api/v1/select/copy").dispatch().status(), Status::NotFound);
+  }
+}
+
+#[test]
+fn export_range_writes_new_worlds_only_when_asked_to_overwrite() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "one\x17two\x17three");
+    write_world(dir.path(), &backend, "kept", "precious");
+    let client = client(dir.path(), &backend, false);
+
+    let export = |path: &str| client.post(path).dispatch().status();
+    assert_eq!(client.get("/api/v1/export-range/world/1.1.1;1.1.1;1.1.2/1.1.1;1.1.1;1.1.3/copy").dispatch().status(), Status::NotFound);
+    assert_eq!(export("/api/v1/export-range/world/1.1.1;1.1.1;1.1.2/1.1.1;1.1.1;1.1.3/copy?rebase=true"), Status::Ok);
+    assert_eq!(read_world(dir.path(), &backend, "copy"), "two\x17three");
+
+    let refused = client.post("/api/v1/export-range/world/1.1.1;1.1.1;1.1.2/1.1.1;1.1.1;1.1.3/kept").dispatch();
+    assert_eq!(refused.status(), Status::Conflict);
+    assert_eq!(refused.into_string().unwrap(), "kept already exists (pass overwrite=true to replace it)");
+    assert_eq!(read_world(dir.path(), &backend, "kept"), "precious");
+
+    assert_eq!(export("/api/v1/export-range/world/1.1.1;1.1.1;1.1.3/1.1.1;1.1.1;1.1.3/kept?overwrite=true"), Status::Ok);
+    assert_eq!(read_world(dir.path(), &backend, "kept"), "\x17\x17three");
+  }
+}
+
+#[test]
+fn queries_match_coordinate_patterns() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "a\x17b\x18c\x17d\x19e");
+    let client = client(dir.path(), &backend, false);
+
+    let json = client.get("/api/v1/query/world/*.*.*;*.*.*;1.*.2").dispatch();
+    assert_eq!(json.content_type(), Some(ContentType::JSON));
+    assert_eq!(json.into_string().unwrap(), r#"[{"coordinate":"1.1.1/1.1.1/1.1.2","content":"b"},{"coordinate":"1.1.1/1.1.1/1.2.2","content":"d"}]"#);
+
+    let merged = client.get("/api/v1/query/world/1.1.1;1.1.1;1-2.*.1?format=phext").dispatch();
+    assert_eq!(merged.into_string().unwrap(), "a\x18c\x19e");
+    let none = client.get("/api/v1/query/world/1.1.1;1.1.1;9.*.*").dispatch();
+    assert_eq!(none.into_string().unwrap(), "[]");
+
+    let invalid = client.get("/api/v1/query/world/1.1.1;1.1.1;5-2.1.1").dispatch();
+    assert_eq!(invalid.status(), Status::BadRequest);
+  }
+}
+
+#[test]
+fn table_of_contents_summarizes_and_pages_scrolls() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "\n  Title line\nbody\x17\x17three\x18four\x1abook two");
+    let client = client(dir.path(), &backend, false);
+    let toc = |query: &str| -> Value {
+      let response = client.get(format!("/api/v1/toc/world{}", query)).dispatch();
+      assert_eq!(response.content_type(), Some(ContentType::JSON));
+      return json::from_str(&response.into_string().unwrap()).unwrap();
+    };
+
+    let all = toc("");
+    assert_eq!(all["total"], 4);
+    assert_eq!(all["limit"], 1000);
+    let first = &all["entries"][0];
+    assert_eq!(first["coordinate"], "1.1.1/1.1.1/1.1.1");
+    assert_eq!(first["bytes"], 18);
+    assert_eq!(first["lines"], 3);
+    assert_eq!(first["title"], "Title line");
+    assert_eq!(first["hash"], libphext::phext::checksum("\n  Title line\nbody"));
+    let coordinates: Vec<&str> = all["entries"].as_array().unwrap().iter().map(|entry| entry["coordinate"].as_str().unwrap()).collect();
+    assert_eq!(coordinates, ["1.1.1/1.1.1/1.1.1", "1.1.1/1.1.1/1.1.3", "1.1.1/1.1.1/1.2.1", "1.1.1/1.1.2/1.1.1"]);
+
+    let page = toc("?offset=1&limit=2");
+    assert_eq!(page["total"], 4);
+    assert_eq!(page["entries"].as_array().unwrap().len(), 2);
+    assert_eq!(page["entries"][0]["title"], "three");
+    assert_eq!(page["entries"][1]["title"], "four");
+
+    let book = toc("?prefix=1.1.1;1.1.1");
+    assert_eq!(book["total"], 3);
+    let past = toc("?prefix=1.1.1;1.1.1&offset=5");
+    assert_eq!(past["entries"].as_array().unwrap().len(), 0);
+    assert_eq!(client.get("/api/v1/toc/world?prefix=x.y").dispatch().status(), Status::BadRequest);
+  }
+}
+
+#[test]
+fn stats_report_occupancy_sizes_and_anomalies() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "one\x17\x17three\r\nline\x18four\x02\x01far");
+    write_world(dir.path(), &backend, "clean", "trailing\x17\x18");
+    write_world(dir.path(), &backend, "<i>", "tagged");
+    let client = client(dir.path(), &backend, false);
+    let stats = |world: &str| -> Value {
+      let response = client.get(format!("/api/v1/stats/{}?format=json", world)).dispatch();
+      assert_eq!(response.content_type(), Some(ContentType::JSON));
+      return json::from_str(&response.into_string().unwrap()).unwrap();
+    };
+
+    let world = stats("world");
+    assert_eq!(world["bytes"], 26);
+    assert_eq!(world["scrolls"], 4);
+    assert_eq!(world["empty_scrolls"], 1);
+    assert_eq!(world["delimiters"]["line"], 1);
+    assert_eq!(world["delimiters"]["scroll"], 2);
+    assert_eq!(world["delimiters"]["section"], 1);
+    assert_eq!(world["delimiters"]["library"], 1);
+    assert_eq!(world["distribution"][0]["dimension"], "library");
+    assert_eq!(world["distribution"][0]["nodes"], json::json!([{"prefix": "1", "scrolls": 3}, {"prefix": "2", "scrolls": 1}]));
+    assert_eq!(world["distribution"][7]["nodes"][1], json::json!({"prefix": "1.1.1/1.1.1/1.2", "scrolls": 1}));
+    assert_eq!(world["largest"][0], json::json!({"coordinate": "1.1.1/1.1.1/1.1.3", "bytes": 11}));
+    let kinds: Vec<&str> = world["anomalies"].as_array().unwrap().iter().map(|anomaly| anomaly["kind"].as_str().unwrap()).collect();
+    assert_eq!(kinds, ["control", "crlf"]);
+    assert_eq!(world["anomalies"][0]["coordinates"], json::json!(["1.1.1/1.1.1/1.2.1"]));
+
+    let clean = stats("clean");
+    assert_eq!(clean["anomalies"][0]["kind"], "denormalized");
+    assert_eq!(clean["anomalies"][0]["count"], 2);
+
+    let report = client.get("/api/v1/stats/world").dispatch();
+    assert_eq!(report.content_type(), Some(ContentType::HTML));
+    let report = report.into_string().unwrap();
+    assert!(report.contains("CRLF line endings"), "{report}");
+
+    let tagged = client.get("/api/v1/stats/%3Ci%3E").dispatch().into_string().unwrap();
+    assert!(tagged.contains("<h1><a href='/api/v1/index/&lt;i&gt;/1.1.1;1.1.1;1.1.1'>&lt;i&gt;</a></h1>"), "{tagged}");
+    assert!(!tagged.contains("<i>"), "{tagged}");
+  }
+}
+
+#[test]
+fn search_ranks_scrolls_and_follows_changes() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "the red fox\x17red red red\x17a fox that is red\x17nothing here");
+    write_world(dir.path(), &backend, "other", "red panda");
+    let client = client(dir.path(), &backend, false);
+    let search = |path: &str| -> Vec<Value> {
+      let response = client.get(path).dispatch();
+      assert_eq!(response.content_type(), Some(ContentType::JSON));
+      return json::from_str(&response.into_string().unwrap()).unwrap();
+    };
+    let coordinates = |hits: &[Value]| -> Vec<String> {
+      return hits.iter().map(|hit| format!("{}:{}", hit["world"].as_str().unwrap(), hit["coordinate"].as_str().unwrap())).collect();
+    };
+
+    let red = search("/api/v1/search/world?q=red");
+    assert_eq!(coordinates(&red), ["world:1.1.1/1.1.1/1.1.2", "world:1.1.1/1.1.1/1.1.1", "world:1.1.1/1.1.1/1.1.3"]);
+    assert_eq!(red[0]["snippet"], "<mark>red</mark> <mark>red</mark> <mark>red</mark>");
+    let phrase = search("/api/v1/search/world?q=red+fox");
+    assert_eq!(coordinates(&phrase)[0], "world:1.1.1/1.1.1/1.1.1");
+    assert_eq!(search("/api/v1/search/world?q=red&limit=1").len(), 1);
+    assert!(search("/api/v1/search/world?q=missing").is_empty());
+    assert_eq!(coordinates(&search("/api/v1/search?q=panda")), ["other:1.1.1/1.1.1/1.1.1"]);
+
+    let response = client.post("/api/v1/update/world/1.1.1;1.1.1;1.1.4")
+      .header(ContentType::Form)
+      .body("content=panda+sighting")
+      .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(coordinates(&search("/api/v1/search?q=panda")), ["world:1.1.1/1.1.1/1.1.4", "other:1.1.1/1.1.1/1.1.1"]);
+
+    write_world(dir.path(), &backend, "world", "moved\x17\x17the red fox");
+    let moved = search("/api/v1/search/world?q=fox");
+    assert_eq!(coordinates(&moved), ["world:1.1.1/1.1.1/1.1.3"]);
+    assert_eq!(moved[0]["snippet"], "the red <mark>fox</mark>");
+    assert!(search("/api/v1/search/world?q=panda").is_empty());
+
+    let html = client.get("/api/v1/search/world?q=fox&format=html").dispatch();
+    assert_eq!(html.content_type(), Some(ContentType::HTML));
+    assert!(html.into_string().unwrap().contains("the red <mark>fox</mark>"));
+  }
+}
+
+#[test]
+fn grep_streams_matching_lines() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    write_world(dir.path(), &backend, "world", "the red fox\nran\x17nothing\x18Red again");
+    let client = client(dir.path(), &backend, false);
+    let grep = |query: &str| client.get(format!("/api/v1/grep/world?{}", query)).dispatch().into_string().unwrap();
+
+    assert_eq!(grep("pattern=red"), "1.1.1/1.1.1/1.1.1:1:5:the red fox\n");
+    assert_eq!(grep("pattern=red&ignore_case=true&start=1.1.1;1.1.1;1.1.2"), "1.1.1/1.1.1/1.2.1:1:1:Red again\n");
+    assert_eq!(grep("pattern=r.d&literal=true"), "");
+    assert_eq!(client.get("/api/v1/grep/world?pattern=(red").dispatch().status(), Status::BadRequest);
+    let typo = client.get("/api/v1/grep/world?pattern=red&start=1.1.1;1.1.1;1.1.x").dispatch();
+    assert_eq!(typo.status(), Status::BadRequest);
+    assert_eq!(typo.into_string().unwrap(), "Invalid coordinate: 1.1.1;1.1.1;1.1.x");
+  }
+}
+
+#[test]
+fn find_replace_previews_then_applies() {
+  for backend in BACKENDS {
+    let dir = tempfile::tempdir().unwrap();
+    let original = "Cat cat CAT\x17price: $5 (cat.)\x18cat";
+    write_world(dir.path(), &backend, "world", original);
+    let client = client(dir.path(), &backend, false);
+    let replace = |body: &str| -> Value {
+      let response = client.post("/api/v1/find-replace/world").header(ContentType::Form).body(body).dispatch();
+      assert_eq!(response.status(), Status::Ok);
+      return json::from_str(&response.into_string().unwrap()).unwrap();
+    };
+
+    let preview = replace("pattern=c(a)t&replacement=d${1}g");
+    assert_eq!(preview["applied"], false);
+    assert_eq!(preview["scrolls"], 3);
+    assert_eq!(preview["replacements"], 3);
+    assert_eq!(preview["changes"][1]["coordinate"], "1.1.1/1.1.1/1.1.2");
+    assert_eq!(preview["changes"][1]["before"], "price: $5 (cat.)");
+    assert_eq!(preview["changes"][1]["after"], "price: $5 (dag.)");
+    assert_eq!(read_world(dir.path(), &backend, "world"), original);
+
+    let insensitive = replace("pattern=cat&replacement=dog&ignore_case=true");
+    assert_eq!(insensitive["replacements"], 5);
+    let literal = replace("pattern=(cat.)&replacement=$1&literal=true");
+    assert_eq!(literal["replacements"], 1);
+    assert_eq!(literal["changes"][0]["after"], "price: $5 $1");
+    let ranged = replace("pattern=cat&replacement=dog&start=1.1.1;1.1.1;1.2.1&end=1.1.1;1.1.1;1.2.1");
+    assert_eq!(ranged["changes"][0]["coordinate"], "1.1.1/1.1.1/1.2.1");
+    assert_eq!(ranged["scrolls"], 1);
+
+    let applied = replace("pattern=cat&replacement=dog&ignore_case=true&apply=true");
+    assert_eq!(applied["applied"], true);
+    assert_eq!(read_world(dir.path(), &backend, "world"), "dog dog dog\x17price: $5 (dog.)\x18dog");
+    let nothing = replace("pattern=cat&replacement=dog&apply=true");
+    assert_eq!(nothing["applied"], false);
+    assert_eq!(nothing["scrolls"], 0);
+
+    let delimiter = client.post("/api/v1/find-replace/world").header(ContentType::Form).body("pattern=dog&replacement=a%17b").dispatch();
+    assert_eq!(delimiter.status(), Status::BadRequest);
+    let invalid = client.post("/api/v1/find-replace/world").header(ContentType::Form).body("pattern=(dog&replacement=x").dispatch();
+    assert_eq!(invalid.status(), Status::BadRequest);
+    let typo = client.post("/api/v1/find-replace/world").header(ContentType::Form).body("pattern=dog&replacement=cat&end=1.1.1;1.1.1;1.2&apply=true").dispatch();
+    assert_eq!(typo.status(), Status::BadRequest);
+    assert_eq!(read_world(dir.path(), &backend, "world"), "dog dog dog\x17price: $5 (dog.)\x18dog");
+  }
+}
@@ -0,0 +1,82 @@
+#![allow(clippy::needless_return)]
+
+/// ----------------------------------------------------------------------------------------------------------
+/// Subspace and Query Tests
+///
+/// Walks buffers with the scroll iterator and matches coordinates against query patterns.
+/// ----------------------------------------------------------------------------------------------------------
+use hello_phext::query::{self, DimensionPattern};
+use hello_phext::subspace;
+use libphext::phext;
+
+fn walk(buffer: &str) -> Vec<(String, &str, usize)> {
+  return subspace::scrolls(buffer).map(|scroll| (scroll.coord.to_string(), scroll.content, scroll.offset)).collect();
+}
+
+#[test]
+fn scroll_iterator_yields_populated_scrolls_in_order() {
+  assert_eq!(walk(""), vec![]);
+  assert_eq!(walk("only"), vec![("1.1.1/1.1.1/1.1.1".to_string(), "only", 0)]);
+  assert_eq!(walk("one\x17two\x18three\x01far"), vec![
+    ("1.1.1/1.1.1/1.1.1".to_string(), "one", 0),
+    ("1.1.1/1.1.1/1.1.2".to_string(), "two", 4),
+    ("1.1.1/1.1.1/1.2.1".to_string(), "three", 8),
+    ("2.1.1/1.1.1/1.1.1".to_string(), "far", 14),
+  ]);
+}
+
+#[test]
+fn scroll_iterator_skips_empty_scrolls_but_counts_their_delimiters() {
+  assert_eq!(walk("\x17\x17third\x17"), vec![("1.1.1/1.1.1/1.1.3".to_string(), "third", 2)]);
+  assert_eq!(walk("\x17\x18\x19\x1a\x1c\x1d\x1e\x1f\x01"), vec![]);
+  assert_eq!(walk("\x18\x17second\x19new chapter"), vec![
+    ("1.1.1/1.1.1/1.2.2".to_string(), "second", 2),
+    ("1.1.1/1.1.1/2.1.1".to_string(), "new chapter", 9),
+  ]);
+  let text = "caf\u{e9}\x17\u{1f680}";
+  assert_eq!(walk(text), vec![("1.1.1/1.1.1/1.1.1".to_string(), "caf\u{e9}", 0), ("1.1.1/1.1.1/1.1.2".to_string(), "\u{1f680}", 6)]);
+}
+
+#[test]
+fn fetch_only_returns_populated_scrolls() {
+  let buffer = "one\x17two\x18three";
+  assert_eq!(subspace::fetch(buffer, phext::to_coordinate("1.1.1/1.1.1/1.1.2")), "two");
+  assert_eq!(subspace::fetch(buffer, phext::to_coordinate("1.1.1/1.1.1/1.1.3")), "");
+  assert_eq!(subspace::fetch(buffer, phext::to_coordinate("1.1.1/1.1.1/1.2.1")), "three");
+  assert_eq!(subspace::fetch(buffer, phext::to_coordinate("1.1.1/1.1.1/9.1.1")), "");
+}
+
+#[test]
+fn replace_keeps_other_scrolls_in_place() {
+  let buffer = "one\x17two\x18three";
+  assert_eq!(subspace::replace(buffer, phext::to_coordinate("1.1.1/1.1.1/1.1.3"), "gap"), "one\x17two\x17gap\x18three");
+  assert_eq!(subspace::replace(buffer, phext::to_coordinate("1.1.1/1.1.1/1.1.2"), "2"), "one\x172\x18three");
+  assert_eq!(subspace::replace(buffer, phext::to_coordinate("1.1.1/1.1.1/1.1.2"), ""), "one\x18three");
+  assert_eq!(subspace::replace(buffer, phext::to_coordinate("1.1.1/1.1.1/3.1.1"), "end"), "one\x17two\x18three\x19\x19end");
+  assert_eq!(subspace::replace(buffer, phext::to_coordinate("1.1.1/1.1.1/1.1.3"), ""), buffer);
+}
+
+#[test]
+fn patterns_accept_values_ranges_and_wildcards() {
+  let pattern = query::to_pattern("1.1.1/*.1.1/3.2-4.1").unwrap();
+  assert_eq!(pattern.dimensions[3], DimensionPattern::Any);
+  assert_eq!(pattern.dimensions[6], DimensionPattern::Between(3, 3));
+  assert_eq!(pattern.dimensions[7], DimensionPattern::Between(2, 4));
+  assert_eq!(query::to_pattern("1.1.1;*.1.1;3.2-4.1").unwrap(), pattern);
+
+  assert!(pattern.matches(phext::to_coordinate("1.1.1/7.1.1/3.3.1")));
+  assert!(!pattern.matches(phext::to_coordinate("1.1.1/7.1.1/3.5.1")));
+  assert!(!pattern.matches(phext::to_coordinate("1.1.1/7.1.1/3.3.2")));
+
+  for invalid in ["", "1.1.1/1.1.1", "1.1.1/1.1.1/1.1.1/1", "1.1.1/1.1.1/1.1.x", "1.1.1/1.1.1/5-2.1.1", "1.1.1/1.1.1/1.1.-"] {
+    assert!(query::to_pattern(invalid).is_err(), "{invalid}");
+  }
+}
+
+#[test]
+fn select_returns_matching_scrolls() {
+  let buffer = "a\x17b\x18c\x17d\x19e";
+  let pattern = query::to_pattern("*.*.*/*.*.*/1.*.2").unwrap();
+  let matches: Vec<&str> = query::select(buffer, &pattern).iter().map(|scroll| scroll.content).collect();
+  assert_eq!(matches, vec!["b", "d"]);
+}